@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use ygrep_core::Config;
+
+use crate::OutputFormat;
+
+/// Print the config in effect and the file it was loaded from
+pub fn show(format: OutputFormat) -> Result<()> {
+    let (config, source) = Config::load_with_source();
+    let source = source.map(|path| absolute(&path));
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({
+            "source": source,
+            "config": config,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    match &source {
+        Some(path) => println!("# Loaded from {}", path.display()),
+        None => println!("# No config file found, using built-in defaults"),
+    }
+    println!();
+    print!("{}", config.to_toml().context("Failed to format config")?);
+
+    Ok(())
+}
+
+/// Print the config files ygrep looks for, in order of precedence
+pub fn path(format: OutputFormat) -> Result<()> {
+    let (_, source) = Config::load_with_source();
+
+    let files: Vec<_> = Config::search_paths()
+        .into_iter()
+        .map(|path| {
+            let status = if Some(&path) == source.as_ref() {
+                "in use"
+            } else if !path.is_file() {
+                "not found"
+            } else if source.is_none() || Config::load_from(&path).is_err() {
+                "invalid"
+            } else {
+                "ignored, a higher-precedence file is in use"
+            };
+            (absolute(&path), status)
+        })
+        .collect();
+
+    if format == OutputFormat::Json {
+        let output: Vec<_> = files.iter()
+            .map(|(path, status)| serde_json::json!({ "path": path, "status": status }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("# Config files in order of precedence (the first valid one is used, files are not merged)");
+    for (i, (path, status)) in files.iter().enumerate() {
+        println!("{}. {} ({})", i + 1, path.display(), status);
+    }
+    if source.is_none() {
+        println!("Using built-in defaults");
+    }
+
+    Ok(())
+}
+
+/// Resolve relative paths (the project config) against the current directory
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -1,17 +1,45 @@
 use anyhow::{Context, Result};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
-use ygrep_core::Workspace;
+use ygrep_core::fs::FileWalker;
+use ygrep_core::config::LOCAL_DATA_DIR;
+use ygrep_core::{Config, Workspace};
 
-pub fn run(workspace_path: &Path, rebuild: bool, semantic_flag: bool, text_flag: bool) -> Result<()> {
+/// How many skipped paths `--dry-run` lists
+const SKIPPED_SAMPLE: usize = 20;
+
+/// With `local`, the index is stored in `.ygrep-index` inside the workspace instead of the data dir;
+/// `progress` shows progress bars for the indexing and embedding phases, and `no_ignore` indexes
+/// files the ignore patterns, .gitignore and built-in skipped directories would leave out
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    workspace_path: &Path,
+    rebuild: bool,
+    semantic_flag: bool,
+    text_flag: bool,
+    local: bool,
+    progress: bool,
+    no_ignore: bool,
+    mut config: Config,
+) -> Result<()> {
     let start = Instant::now();
 
+    if local {
+        config.indexer.data_dir = LOCAL_DATA_DIR.into();
+    }
+    if no_ignore {
+        config.indexer.no_ignore = true;
+        eprintln!("Ignoring no files (--no-ignore): this can index very large trees such as node_modules");
+    }
+
     eprintln!("Indexing {}...", workspace_path.display());
 
     // Open workspace first to read stored flag (before potential rebuild)
     // Use create() here since we may need to create the index
     let stored_semantic = if !rebuild {
-        Workspace::create(workspace_path)
+        Workspace::create_with_config(workspace_path, config.clone())
             .ok()
             .and_then(|ws| ws.stored_semantic_flag())
     } else {
@@ -21,7 +49,7 @@ pub fn run(workspace_path: &Path, rebuild: bool, semantic_flag: bool, text_flag:
     if rebuild {
         eprintln!("Rebuilding index from scratch...");
         // Delete existing index directory
-        if let Ok(workspace) = Workspace::create(workspace_path) {
+        if let Ok(workspace) = Workspace::create_with_config(workspace_path, config.clone()) {
             let index_path = workspace.index_path().to_path_buf();
             drop(workspace); // Release the workspace before deleting
             if index_path.exists() {
@@ -57,31 +85,114 @@ pub fn run(workspace_path: &Path, rebuild: bool, semantic_flag: bool, text_flag:
     }
 
     // Create or open workspace for indexing
-    let workspace = Workspace::create(workspace_path)
+    let workspace = Workspace::create_with_config(workspace_path, config)
         .context("Failed to create workspace")?;
 
-    // Index all files
-    let stats = workspace.index_all_with_options(with_embeddings)
+    // Index all files; Ctrl-C stops after the current file or embedding batch, keeping the work done
+    let interrupted = Arc::new(AtomicBool::new(false));
+    handle_ctrl_c(Arc::clone(&interrupted));
+    let stats = workspace.index_all_interruptible(with_embeddings, progress, &interrupted)
         .context("Failed to index workspace")?;
 
     let elapsed = start.elapsed();
     let index_size = dir_size(workspace.index_path());
 
-    let index_type = if with_embeddings { "semantic" } else { "text" };
+    // Label by what was actually built: vectors are only written when documents got embedded
+    let vectors_dir = workspace.index_path().join("vectors");
+    let has_vectors = std::fs::read_dir(&vectors_dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    let index_type = match (with_embeddings, has_vectors) {
+        (true, true) => "semantic",
+        (true, false) => "text (no documents were embedded)",
+        (false, _) => "text",
+    };
 
     eprintln!();
-    eprintln!("Indexing complete in {:.2}s", elapsed.as_secs_f64());
+    let outcome = if stats.interrupted { "interrupted" } else { "complete" };
+    eprintln!("Indexing {} in {:.2}s", outcome, elapsed.as_secs_f64());
     eprintln!("  Index type: {}", index_type);
     eprintln!("  Files indexed: {}", stats.indexed);
     if stats.embedded > 0 {
         eprintln!("  Semantic indexed: {}", stats.embedded);
     }
     eprintln!("  Files skipped: {}", stats.skipped);
+    if stats.deduplicated > 0 {
+        eprintln!("  Duplicate files: {}", stats.deduplicated);
+    }
+    if stats.lossy_decoded > 0 {
+        eprintln!("  Invalid UTF-8 files: {} (indexed with replacement characters)", stats.lossy_decoded);
+    }
     eprintln!("  Errors: {}", stats.errors);
+    if stats.embedding_errors > 0 {
+        eprintln!("  Embedding errors: {}", stats.embedding_errors);
+    }
     eprintln!("  Index size: {}", format_size(index_size));
     eprintln!();
     eprintln!("Index stored at: {}", workspace.index_path().display());
 
+    if stats.interrupted {
+        eprintln!();
+        eprintln!("Indexing was interrupted. Run `ygrep index` again to finish; documents already embedded are kept.");
+        std::process::exit(130);
+    }
+
+    Ok(())
+}
+
+/// Set `interrupted` on Ctrl-C so indexing can stop cleanly; a second Ctrl-C exits immediately
+fn handle_ctrl_c(interrupted: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        let Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_all().build() else {
+            return;
+        };
+        runtime.block_on(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if interrupted.swap(true, Ordering::Relaxed) {
+                    std::process::exit(130);
+                }
+                eprintln!("\nInterrupted: saving what was indexed so far (Ctrl-C again to quit now)...");
+            }
+        });
+    });
+}
+
+/// Walk the workspace like `run` would, reporting what would be indexed without
+/// writing to the index or loading the embedding model
+pub fn dry_run(workspace_path: &Path, no_ignore: bool, mut config: Config) -> Result<()> {
+    let root = std::fs::canonicalize(workspace_path)
+        .with_context(|| format!("Workspace not found: {}", workspace_path.display()))?;
+    config.indexer.no_ignore |= no_ignore;
+
+    let roots = std::iter::once(root.clone())
+        .chain(config.indexer.extra_roots.iter().map(|dir| root.join(dir)))
+        .collect();
+    let mut walker = FileWalker::new_multi(roots, config.indexer)
+        .context("Failed to walk workspace")?;
+    walker.record_skipped();
+
+    let mut files = 0usize;
+    let mut bytes = 0u64;
+    for entry in walker.walk() {
+        files += 1;
+        bytes += std::fs::metadata(&entry.path).map(|m| m.len()).unwrap_or(0);
+    }
+    let skipped = walker.skipped();
+
+    println!("Dry run for {} (nothing was written)", root.display());
+    println!("  Files to index: {}", files);
+    println!("  Total size: {}", format_size(bytes));
+    println!("  Skipped: {}", skipped.len());
+
+    if !skipped.is_empty() {
+        println!();
+        println!("Skipped paths (first {}):", SKIPPED_SAMPLE.min(skipped.len()));
+        for skip in skipped.iter().take(SKIPPED_SAMPLE) {
+            let path = skip.path.strip_prefix(&root).unwrap_or(&skip.path);
+            println!("  {} ({})", path.display(), skip.reason);
+        }
+    }
+
     Ok(())
 }
 
@@ -1,6 +1,10 @@
 use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::OutputFormat;
 
 /// Get the indexes directory
 fn get_indexes_dir() -> Result<PathBuf> {
@@ -14,31 +18,50 @@ fn get_indexes_dir() -> Result<PathBuf> {
 #[derive(Debug)]
 struct IndexInfo {
     hash: String,
-    #[allow(dead_code)]
     path: PathBuf,
     workspace: Option<String>,
     size_bytes: u64,
     semantic: Option<bool>,
+    files_indexed: Option<u64>,
+    indexed_at: Option<DateTime<Utc>>,
+    embedding_model: Option<String>,
+    schema_version: Option<u64>,
+    vector_count: Option<usize>,
 }
 
 /// Read index info from a directory
 fn read_index_info(hash: &str, index_path: &PathBuf) -> Result<IndexInfo> {
-    // Try to read workspace path and semantic flag from workspace.json (our metadata file)
+    // Try to read workspace metadata from workspace.json (our metadata file)
     let workspace_meta_path = index_path.join("workspace.json");
-    let (workspace, semantic) = if workspace_meta_path.exists() {
-        let json = fs::read_to_string(&workspace_meta_path)
-            .ok()
-            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
-
-        let workspace = json.as_ref()
-            .and_then(|v| v.get("workspace").and_then(|w| w.as_str()).map(String::from));
-        let semantic = json.as_ref()
-            .and_then(|v| v.get("semantic").and_then(|s| s.as_bool()));
-
-        (workspace, semantic)
-    } else {
-        (None, None)
-    };
+    let json = fs::read_to_string(&workspace_meta_path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+
+    let workspace = json.as_ref()
+        .and_then(|v| v.get("workspace").and_then(|w| w.as_str()).map(String::from));
+    let files_indexed = json.as_ref()
+        .and_then(|v| v.get("files_indexed").and_then(|f| f.as_u64()));
+    let indexed_at = json.as_ref()
+        .and_then(|v| v.get("indexed_at").and_then(|t| t.as_str()))
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc));
+
+    let embedding_model = json.as_ref()
+        .and_then(|v| v.get("embedding_model").and_then(|m| m.as_str()).map(String::from));
+    let schema_version = json.as_ref()
+        .and_then(|v| v.get("schema_version").and_then(|s| s.as_u64()));
+    let vector_count = ygrep_core::index::vector_count(&index_path.join("vectors"));
+
+    // Older indexes don't store the semantic flag - fall back to looking for vector data
+    let semantic = json.as_ref()
+        .and_then(|v| v.get("semantic").and_then(|s| s.as_bool()))
+        .or_else(|| {
+            let vectors = index_path.join("vectors");
+            let has_vectors = ["doc_ids.bin", "doc_ids.json", "vectors.json"]
+                .iter()
+                .any(|name| vectors.join(name).exists());
+            has_vectors.then_some(true)
+        });
 
     // Calculate total size
     let size_bytes = dir_size(index_path).unwrap_or(0);
@@ -49,9 +72,49 @@ fn read_index_info(hash: &str, index_path: &PathBuf) -> Result<IndexInfo> {
         workspace,
         size_bytes,
         semantic,
+        files_indexed,
+        indexed_at,
+        embedding_model,
+        schema_version,
+        vector_count,
     })
 }
 
+/// Find an index by hash, or by workspace path
+fn find_index(indexes_dir: &std::path::Path, identifier: &str) -> Result<Option<IndexInfo>> {
+    // First try as hash (a path would make join() escape the indexes directory)
+    let is_hash = !identifier.is_empty() && identifier.chars().all(|c| c.is_ascii_alphanumeric());
+    let index_path = indexes_dir.join(identifier);
+    if is_hash && index_path.is_dir() {
+        return read_index_info(identifier, &index_path).map(Some);
+    }
+
+    // Try to find by workspace path
+    let target_path = std::fs::canonicalize(identifier).ok();
+
+    for entry in fs::read_dir(indexes_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(hash) = path.file_name().and_then(|n| n.to_str()) {
+                if let Ok(info) = read_index_info(hash, &path) {
+                    let matches = match (&info.workspace, &target_path) {
+                        (Some(ws), Some(target)) => std::path::Path::new(ws) == target,
+                        (Some(ws), None) => ws.contains(identifier),
+                        _ => false,
+                    };
+
+                    if matches {
+                        return Ok(Some(info));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
 /// Calculate directory size recursively
 fn dir_size(path: &PathBuf) -> Result<u64> {
     let mut size = 0;
@@ -70,7 +133,7 @@ fn dir_size(path: &PathBuf) -> Result<u64> {
 }
 
 /// Format bytes as human readable
-fn format_size(bytes: u64) -> String {
+pub(crate) fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
@@ -86,19 +149,67 @@ fn format_size(bytes: u64) -> String {
     }
 }
 
-/// List all indexes
-pub fn list() -> Result<()> {
-    let indexes_dir = get_indexes_dir()?;
+/// Index type label (older indexes without the flag are text-only)
+fn index_type(info: &IndexInfo) -> &'static str {
+    if info.semantic == Some(true) { "semantic" } else { "text" }
+}
 
-    if !indexes_dir.exists() {
-        println!("No indexes found.");
-        return Ok(());
+/// JSON representation of an index for `--json` output
+#[derive(Debug, Serialize)]
+struct IndexEntry {
+    hash: String,
+    workspace: Option<String>,
+    /// `semantic` or `text`
+    #[serde(rename = "type")]
+    index_type: &'static str,
+    has_semantic: bool,
+    size_bytes: u64,
+    files_indexed: Option<u64>,
+    /// RFC 3339 timestamp
+    indexed_at: Option<String>,
+    embedding_model: Option<String>,
+    vector_count: Option<usize>,
+    schema_version: Option<u64>,
+}
+
+impl From<&IndexInfo> for IndexEntry {
+    fn from(info: &IndexInfo) -> Self {
+        Self {
+            hash: info.hash.clone(),
+            workspace: info.workspace.clone(),
+            index_type: index_type(info),
+            has_semantic: info.semantic == Some(true),
+            size_bytes: info.size_bytes,
+            files_indexed: info.files_indexed,
+            indexed_at: info.indexed_at.map(|t| t.to_rfc3339()),
+            embedding_model: info.embedding_model.clone(),
+            vector_count: info.vector_count,
+            schema_version: info.schema_version,
+        }
+    }
+}
+
+/// Format how long ago an index was built (e.g. "just now", "3h ago")
+fn format_age(indexed_at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (now - indexed_at).num_seconds().max(0);
+
+    if secs < 60 {
+        "just now".to_string()
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86400)
     }
+}
 
+/// Read every complete index in `indexes_dir`, most recently indexed first
+fn read_indexes(indexes_dir: &Path) -> Result<Vec<IndexInfo>> {
     let mut indexes = Vec::new();
-    let mut total_size = 0u64;
 
-    for entry in fs::read_dir(&indexes_dir)? {
+    let entries = if indexes_dir.exists() { fs::read_dir(indexes_dir)?.collect() } else { vec![] };
+    for entry in entries {
         let entry = entry?;
         let path = entry.path();
         if path.is_dir() {
@@ -108,13 +219,34 @@ pub fn list() -> Result<()> {
             }
             if let Some(hash) = path.file_name().and_then(|n| n.to_str()) {
                 if let Ok(info) = read_index_info(hash, &path) {
-                    total_size += info.size_bytes;
                     indexes.push(info);
                 }
             }
         }
     }
 
+    // Most recently indexed first
+    indexes.sort_by_key(|info| std::cmp::Reverse(info.indexed_at));
+    Ok(indexes)
+}
+
+/// All indexes as a JSON array, one object per index (what `ygrep indexes list --json` prints)
+pub fn list_json() -> Result<String> {
+    let indexes = read_indexes(&get_indexes_dir()?)?;
+    let entries: Vec<IndexEntry> = indexes.iter().map(IndexEntry::from).collect();
+    Ok(serde_json::to_string_pretty(&entries)?)
+}
+
+/// List all indexes
+pub fn list(format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", list_json()?);
+        return Ok(());
+    }
+
+    let indexes = read_indexes(&get_indexes_dir()?)?;
+    let total_size: u64 = indexes.iter().map(|info| info.size_bytes).sum();
+
     if indexes.is_empty() {
         println!("No indexes found.");
         return Ok(());
@@ -122,22 +254,47 @@ pub fn list() -> Result<()> {
 
     println!("# {} indexes ({})\n", indexes.len(), format_size(total_size));
 
+    let now = Utc::now();
     for info in &indexes {
         let workspace = info.workspace.as_deref().unwrap_or("(unknown)");
-        let index_type = match info.semantic {
-            Some(true) => "semantic",
-            Some(false) => "text",
-            None => "text", // Default for older indexes without the flag
-        };
-        println!("{}  {}  [{}]", info.hash, format_size(info.size_bytes), index_type);
+        let index_type = index_type(info);
+        let files = info.files_indexed
+            .map(|n| format!("{} files", n))
+            .unwrap_or_else(|| "? files".to_string());
+        let age = info.indexed_at
+            .map(|t| format_age(t, now))
+            .unwrap_or_else(|| "unknown".to_string());
+        println!("{}  {}  {}  {}  [{}]", info.hash, format_size(info.size_bytes), files, age, index_type);
         println!("  {}\n", workspace);
     }
 
+    println!("Remove an index:   ygrep indexes remove <hash|path>");
+    println!("Remove orphans:    ygrep indexes clean [--dry-run]");
+
     Ok(())
 }
 
+/// Why an index is considered orphaned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Orphan {
+    /// workspace.json is missing or corrupt (may be an index still being built)
+    Unknown,
+    /// The workspace directory no longer exists
+    Gone,
+}
+
+/// Classify an index as orphaned, if it is
+fn orphan_kind(info: &IndexInfo) -> Option<Orphan> {
+    match &info.workspace {
+        Some(ws) if PathBuf::from(ws).exists() => None,
+        Some(_) => Some(Orphan::Gone),
+        None => Some(Orphan::Unknown),
+    }
+}
+
 /// Remove orphaned indexes (workspaces that no longer exist)
-pub fn clean() -> Result<()> {
+/// Indexes with missing or corrupt metadata are only reported, never removed
+pub fn clean(dry_run: bool) -> Result<()> {
     let indexes_dir = get_indexes_dir()?;
 
     if !indexes_dir.exists() {
@@ -145,8 +302,8 @@ pub fn clean() -> Result<()> {
         return Ok(());
     }
 
-    let mut removed = 0;
-    let mut freed = 0u64;
+    let mut gone = Vec::new();
+    let mut unknown = Vec::new();
 
     for entry in fs::read_dir(&indexes_dir)? {
         let entry = entry?;
@@ -154,28 +311,44 @@ pub fn clean() -> Result<()> {
         if path.is_dir() {
             if let Some(hash) = path.file_name().and_then(|n| n.to_str()) {
                 if let Ok(info) = read_index_info(hash, &path) {
-                    // Check if workspace still exists
-                    let should_remove = match &info.workspace {
-                        Some(ws) => !PathBuf::from(ws).exists(),
-                        None => true, // Remove indexes with unknown workspace
-                    };
-
-                    if should_remove {
-                        let size = info.size_bytes;
-                        fs::remove_dir_all(&path)?;
-                        println!("Removed: {} ({})", info.workspace.as_deref().unwrap_or(&info.hash), format_size(size));
-                        removed += 1;
-                        freed += size;
+                    match orphan_kind(&info) {
+                        Some(Orphan::Gone) => gone.push(info),
+                        Some(Orphan::Unknown) => unknown.push(info),
+                        None => {}
                     }
                 }
             }
         }
     }
 
-    if removed == 0 {
+    if gone.is_empty() && unknown.is_empty() {
         println!("No orphaned indexes found.");
+        return Ok(());
+    }
+
+    let freed: u64 = gone.iter().map(|info| info.size_bytes).sum();
+
+    if dry_run {
+        println!("# {} orphaned-gone (workspace no longer exists, would remove {})", gone.len(), format_size(freed));
+        for info in &gone {
+            println!("{}  {}  {}", info.hash, format_size(info.size_bytes), info.workspace.as_deref().unwrap_or(""));
+        }
     } else {
-        println!("\nRemoved {} indexes, freed {}", removed, format_size(freed));
+        for info in &gone {
+            fs::remove_dir_all(&info.path)?;
+            println!("Removed: {} ({})", info.workspace.as_deref().unwrap_or(&info.hash), format_size(info.size_bytes));
+        }
+        if !gone.is_empty() {
+            println!("\nRemoved {} indexes, freed {}", gone.len(), format_size(freed));
+        }
+    }
+
+    if !unknown.is_empty() {
+        println!("\n# {} orphaned-unknown (workspace.json missing or corrupt, kept)", unknown.len());
+        for info in &unknown {
+            println!("{}  {}", info.hash, format_size(info.size_bytes));
+        }
+        println!("\nRun `ygrep index` in the workspace to fix these, or `ygrep indexes remove <hash>` to delete one.");
     }
 
     Ok(())
@@ -190,40 +363,169 @@ pub fn remove(identifier: &str) -> Result<()> {
         return Ok(());
     }
 
-    // First try as hash
-    let index_path = indexes_dir.join(identifier);
-    if index_path.exists() && index_path.is_dir() {
-        let info = read_index_info(identifier, &index_path)?;
-        fs::remove_dir_all(&index_path)?;
-        println!("Removed index: {} ({})", identifier, format_size(info.size_bytes));
-        return Ok(());
+    match find_index(&indexes_dir, identifier)? {
+        Some(info) if info.hash == identifier => {
+            fs::remove_dir_all(&info.path)?;
+            println!("Removed index: {} ({})", identifier, format_size(info.size_bytes));
+        }
+        Some(info) => {
+            fs::remove_dir_all(&info.path)?;
+            println!("Removed index for: {} ({})", info.workspace.as_deref().unwrap_or(&info.hash), format_size(info.size_bytes));
+        }
+        None => println!("Index not found: {}", identifier),
     }
 
-    // Try to find by workspace path
-    let target_path = std::fs::canonicalize(identifier).ok();
+    Ok(())
+}
 
-    for entry in fs::read_dir(&indexes_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            if let Some(hash) = path.file_name().and_then(|n| n.to_str()) {
-                if let Ok(info) = read_index_info(hash, &path) {
-                    let matches = match (&info.workspace, &target_path) {
-                        (Some(ws), Some(target)) => PathBuf::from(ws) == *target,
-                        (Some(ws), None) => ws.contains(identifier),
-                        _ => false,
-                    };
+/// Show details for a single index by hash or workspace path
+pub fn info(identifier: &str, format: OutputFormat) -> Result<()> {
+    let indexes_dir = get_indexes_dir()?;
 
-                    if matches {
-                        fs::remove_dir_all(&path)?;
-                        println!("Removed index for: {} ({})", info.workspace.as_deref().unwrap_or(&info.hash), format_size(info.size_bytes));
-                        return Ok(());
-                    }
-                }
-            }
-        }
+    let info = if indexes_dir.exists() {
+        find_index(&indexes_dir, identifier)?
+    } else {
+        None
+    };
+
+    let Some(info) = info else {
+        println!("Index not found: {}", identifier);
+        return Ok(());
+    };
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&IndexEntry::from(&info))?);
+        return Ok(());
     }
 
-    println!("Index not found: {}", identifier);
+    let index_type = index_type(&info);
+
+    let unknown = || "unknown".to_string();
+    println!("Workspace:       {}", info.workspace.as_deref().unwrap_or("(unknown)"));
+    println!("Hash:            {}", info.hash);
+    println!("Type:            {}", index_type);
+    println!("Size:            {}", format_size(info.size_bytes));
+    println!("Files:           {}", info.files_indexed.map(|n| n.to_string()).unwrap_or_else(unknown));
+    println!("Last indexed:    {}", info.indexed_at
+        .map(|t| format!("{} ({})", t.to_rfc3339(), format_age(t, Utc::now())))
+        .unwrap_or_else(unknown));
+    println!("Embedding model: {}", info.embedding_model.as_deref().unwrap_or("none"));
+    println!("Vectors:         {}", info.vector_count.unwrap_or(0));
+    println!("Schema version:  {}", info.schema_version.map(|v| v.to_string()).unwrap_or_else(unknown));
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::Options;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_index_info() {
+        let temp_dir = tempdir().unwrap();
+        let index_path = temp_dir.path().join("1bb65a32a7aa44ba");
+        fs::create_dir_all(&index_path).unwrap();
+        fs::write(
+            index_path.join("workspace.json"),
+            r#"{"workspace": "/path/to/project", "indexed_at": "2025-12-10T12:00:00+00:00", "files_indexed": 84}"#,
+        ).unwrap();
+
+        // No semantic flag stored, but vector data present
+        fs::create_dir_all(index_path.join("vectors")).unwrap();
+        fs::write(index_path.join("vectors/doc_ids.json"), "{}").unwrap();
+
+        let info = read_index_info("1bb65a32a7aa44ba", &index_path).unwrap();
+        assert_eq!(info.workspace.as_deref(), Some("/path/to/project"));
+        assert_eq!(info.files_indexed, Some(84));
+        assert_eq!(info.semantic, Some(true));
+        assert_eq!(info.vector_count, None);
+
+        let indexed_at = info.indexed_at.unwrap();
+        assert_eq!(format_age(indexed_at, indexed_at), "just now");
+        assert_eq!(format_age(indexed_at, indexed_at + chrono::Duration::hours(3)), "3h ago");
+    }
+
+    #[test]
+    fn test_index_entries_json() {
+        let temp_dir = tempdir().unwrap();
+        let write_index = |hash: &str, json: &str| {
+            let index_path = temp_dir.path().join(hash);
+            fs::create_dir_all(&index_path).unwrap();
+            fs::write(index_path.join("workspace.json"), json).unwrap();
+        };
+        write_index("aaaa", r#"{"workspace": "/old", "indexed_at": "2025-12-01T12:00:00+00:00", "semantic": false, "schema_version": 4}"#);
+        write_index("bbbb", r#"{"workspace": "/new", "indexed_at": "2025-12-10T12:00:00+00:00", "semantic": true, "files_indexed": 84}"#);
+        // Never finished indexing
+        fs::create_dir_all(temp_dir.path().join("cccc")).unwrap();
+
+        let indexes = read_indexes(temp_dir.path()).unwrap();
+        let entries: Vec<IndexEntry> = indexes.iter().map(IndexEntry::from).collect();
+        let json: serde_json::Value = serde_json::to_value(&entries).unwrap();
+        let array = json.as_array().unwrap();
+        assert_eq!(array.len(), 2);
+        assert_eq!(array[0]["hash"], "bbbb");
+        assert_eq!(array[0]["has_semantic"], true);
+        assert_eq!(array[0]["files_indexed"], 84);
+        assert_eq!(array[0]["indexed_at"], "2025-12-10T12:00:00+00:00");
+        assert_eq!(array[1]["workspace"], "/old");
+        assert_eq!(array[1]["has_semantic"], false);
+        assert_eq!(array[1]["type"], "text");
+        assert_eq!(array[1]["schema_version"], 4);
+        assert!(array[1]["size_bytes"].is_u64());
+    }
+
+    #[test]
+    fn test_find_index() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = tempdir().unwrap();
+        let workspace_path = std::fs::canonicalize(workspace.path()).unwrap();
+
+        let index_path = temp_dir.path().join("c4f2ba4712ed98e7");
+        fs::create_dir_all(index_path.join("vectors")).unwrap();
+        fs::write(
+            index_path.join("workspace.json"),
+            serde_json::json!({ "workspace": workspace_path, "semantic": true, "schema_version": 1 }).to_string(),
+        ).unwrap();
+        fs::write(
+            index_path.join("vectors/doc_ids.json"),
+            r#"{"dimension": 384, "doc_ids": ["a", "", "b"]}"#,
+        ).unwrap();
+
+        let by_hash = find_index(temp_dir.path(), "c4f2ba4712ed98e7").unwrap().unwrap();
+        let by_path = find_index(temp_dir.path(), workspace_path.to_str().unwrap()).unwrap().unwrap();
+        assert_eq!(by_hash.hash, by_path.hash);
+        assert_eq!(by_path.vector_count, Some(2));
+        assert_eq!(by_path.schema_version, Some(1));
+
+        // The bincode doc_id index takes precedence over the older JSON one
+        let doc_ids = bincode::DefaultOptions::new().serialize(&(384usize, vec!["a", "", "b", "c"])).unwrap();
+        fs::write(index_path.join("vectors/doc_ids.bin"), doc_ids).unwrap();
+        let by_hash = find_index(temp_dir.path(), "c4f2ba4712ed98e7").unwrap().unwrap();
+        assert_eq!(by_hash.vector_count, Some(3));
+
+        assert!(find_index(temp_dir.path(), "/no/such/workspace").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_orphan_kind() {
+        let workspace = tempdir().unwrap();
+        let info = |ws: Option<String>| IndexInfo {
+            hash: "abc".to_string(),
+            path: PathBuf::new(),
+            workspace: ws,
+            size_bytes: 0,
+            semantic: None,
+            files_indexed: None,
+            indexed_at: None,
+            embedding_model: None,
+            schema_version: None,
+            vector_count: None,
+        };
+
+        assert_eq!(orphan_kind(&info(Some(workspace.path().to_string_lossy().to_string()))), None);
+        assert_eq!(orphan_kind(&info(Some("/no/such/workspace".to_string()))), Some(Orphan::Gone));
+        assert_eq!(orphan_kind(&info(None)), Some(Orphan::Unknown));
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::{Context, Result, bail};
+use std::ffi::OsStr;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 /// Skill content for ygrep
 const SKILL_CONTENT: &str = r#"---
@@ -104,8 +106,8 @@ fn home_dir() -> Result<PathBuf> {
     dirs::home_dir().context("Could not determine home directory")
 }
 
-/// Install ygrep for Claude Code
-pub fn install_claude_code() -> Result<()> {
+/// Install ygrep for Claude Code, returning the files written
+pub fn install_claude_code() -> Result<Vec<PathBuf>> {
     println!("Installing ygrep for Claude Code...");
 
     let home = home_dir()?;
@@ -185,7 +187,15 @@ pub fn install_claude_code() -> Result<()> {
 
     println!("Successfully installed ygrep for Claude Code");
     println!("Restart Claude Code to activate the plugin");
-    Ok(())
+    Ok(vec![
+        hooks_dir.join("hook.json"),
+        skills_dir.join("SKILL.md"),
+        claude_plugin_dir.join("plugin.json"),
+        marketplace_plugin_dir.join("marketplace.json"),
+        known_path,
+        installed_path,
+        settings_path,
+    ])
 }
 
 /// Uninstall ygrep from Claude Code
@@ -242,8 +252,8 @@ pub fn uninstall_claude_code() -> Result<()> {
     Ok(())
 }
 
-/// Install ygrep for OpenCode
-pub fn install_opencode() -> Result<()> {
+/// Install ygrep for OpenCode, returning the files written
+pub fn install_opencode() -> Result<Vec<PathBuf>> {
     println!("Installing ygrep for OpenCode...");
 
     let home = home_dir()?;
@@ -288,7 +298,7 @@ export default tool({{
     fs::write(&config_path, serde_json::to_string_pretty(&config)?)?;
 
     println!("Successfully installed ygrep for OpenCode");
-    Ok(())
+    Ok(vec![tool_dir.join("ygrep.ts"), config_path])
 }
 
 /// Uninstall ygrep from OpenCode
@@ -309,8 +319,8 @@ pub fn uninstall_opencode() -> Result<()> {
     Ok(())
 }
 
-/// Install ygrep for Codex
-pub fn install_codex() -> Result<()> {
+/// Install ygrep for Codex, returning the files written
+pub fn install_codex() -> Result<Vec<PathBuf>> {
     println!("Installing ygrep for Codex...");
 
     let home = home_dir()?;
@@ -326,7 +336,7 @@ pub fn install_codex() -> Result<()> {
     };
 
     if !content.contains("name: ygrep") {
-        content.push_str("\n");
+        content.push('\n');
         content.push_str(SKILL_CONTENT);
         fs::write(&agents_path, content)?;
         println!("Added ygrep skill to Codex AGENTS.md");
@@ -335,7 +345,7 @@ pub fn install_codex() -> Result<()> {
     }
 
     println!("Successfully installed ygrep for Codex");
-    Ok(())
+    Ok(vec![agents_path])
 }
 
 /// Uninstall ygrep from Codex
@@ -361,8 +371,8 @@ pub fn uninstall_codex() -> Result<()> {
     Ok(())
 }
 
-/// Install ygrep for Factory Droid
-pub fn install_droid() -> Result<()> {
+/// Install ygrep for Factory Droid, returning the files written
+pub fn install_droid() -> Result<Vec<PathBuf>> {
     println!("Installing ygrep for Factory Droid...");
 
     let home = home_dir()?;
@@ -411,7 +421,7 @@ pub fn install_droid() -> Result<()> {
     fs::write(&settings_path, serde_json::to_string_pretty(&settings)?)?;
 
     println!("Successfully installed ygrep for Factory Droid");
-    Ok(())
+    Ok(vec![skills_dir.join("SKILL.md"), settings_path])
 }
 
 /// Uninstall ygrep from Factory Droid
@@ -438,3 +448,99 @@ pub fn uninstall_droid() -> Result<()> {
     println!("Successfully uninstalled ygrep from Factory Droid");
     Ok(())
 }
+
+/// Check that an installation works: its files exist, `ygrep` is on the PATH and runs
+///
+/// Prints a checklist with a suggested fix for each failed check, and fails if any did.
+pub fn verify(target: &str, files: &[PathBuf]) -> Result<()> {
+    println!();
+    println!("Verifying installation...");
+    let mut ok = true;
+
+    let missing: Vec<&PathBuf> = files.iter().filter(|file| !file.is_file()).collect();
+    if missing.is_empty() {
+        println!("✓ Plugin files written");
+    } else {
+        ok = false;
+        println!("✗ Plugin files missing:");
+        for file in missing {
+            println!("    {}", file.display());
+        }
+        println!("  Check the directories are writable and re-run `ygrep install {}`", target);
+    }
+
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+    match find_in_path("ygrep", &path_var) {
+        Some(binary) => {
+            println!("✓ ygrep binary found at {}", binary.display());
+
+            match Command::new(&binary).arg("--version").output() {
+                Ok(output) if output.status.success() => {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    let version = stdout.split_whitespace().last().unwrap_or("");
+                    println!("✓ ygrep v{} responds", version.trim_start_matches('v'));
+                }
+                _ => {
+                    ok = false;
+                    println!("✗ `{} --version` failed", binary.display());
+                    println!("  Reinstall ygrep; the binary on your PATH may be broken or built for another platform");
+                }
+            }
+        }
+        None => {
+            ok = false;
+            println!("✗ ygrep binary not found in $PATH");
+            match std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+                Some(dir) => println!("  Add {} to your PATH so your AI tool can run ygrep", dir.display()),
+                None => println!("  Add the directory containing ygrep to your PATH so your AI tool can run it"),
+            }
+        }
+    }
+
+    if !ok {
+        bail!("Installation verification failed");
+    }
+    Ok(())
+}
+
+/// Find an executable in the directories of a PATH-style variable
+fn find_in_path(name: &str, path_var: &OsStr) -> Option<PathBuf> {
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(path_var)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_in_path() {
+        let empty = tempdir().unwrap();
+        let bin = tempdir().unwrap();
+        let binary = bin.path().join(format!("ygrep{}", std::env::consts::EXE_SUFFIX));
+        fs::write(&binary, "").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let path_var = std::env::join_paths([empty.path(), bin.path()]).unwrap();
+        assert_eq!(find_in_path("ygrep", &path_var), Some(binary));
+        assert_eq!(find_in_path("ygrep", &std::env::join_paths([empty.path()]).unwrap()), None);
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use std::path::Path;
+use ygrep_core::{Config, Workspace, YgrepError};
+
+use crate::OutputFormat;
+
+pub fn run(workspace_path: &Path, format: OutputFormat, config: Config) -> Result<()> {
+    // Open existing workspace (fails if not indexed)
+    let workspace = match Workspace::open_with_config(workspace_path, config) {
+        Ok(ws) => ws,
+        Err(e @ (YgrepError::IncompatibleIndex { .. } | YgrepError::EmbeddingDimensionMismatch { .. })) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        Err(_) => {
+            eprintln!("Workspace not indexed: {}", workspace_path.display());
+            eprintln!();
+            eprintln!("To index this workspace, run:");
+            eprintln!("  ygrep index              # Text-only (fast)");
+            eprintln!("  ygrep index --semantic   # With semantic search (slower, better results)");
+            std::process::exit(1);
+        }
+    };
+
+    let files = workspace.list_indexed_paths()?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&files)?);
+        return Ok(());
+    }
+
+    println!("# {} files", files.len());
+    for file in &files {
+        // Same marker as semantic search hits
+        let semantic = if file.has_embedding { " ~" } else { "" };
+        println!("{} ({} lines){}", file.path, file.line_count, semantic);
+    }
+
+    Ok(())
+}
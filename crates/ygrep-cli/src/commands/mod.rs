@@ -4,3 +4,7 @@ pub mod status;
 pub mod watch;
 pub mod install;
 pub mod indexes;
+pub mod list;
+pub mod warmup;
+pub mod config;
+pub mod proto;
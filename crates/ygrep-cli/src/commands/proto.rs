@@ -0,0 +1,16 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use ygrep_core::{protocol, Config, Workspace};
+
+/// Answer line-delimited JSON search requests on stdin until it closes (see `ygrep_core::protocol`)
+///
+/// Only responses go to stdout; problems opening the workspace are reported on stderr.
+pub fn run(workspace_path: &Path, config: Config) -> Result<()> {
+    let workspace = Workspace::open_with_config(workspace_path, config)
+        .with_context(|| format!("Failed to open index for {}", workspace_path.display()))?;
+
+    let stdin = std::io::stdin();
+    protocol::serve(&workspace, stdin.lock(), std::io::stdout().lock())
+        .context("Protocol I/O failed")?;
+    Ok(())
+}
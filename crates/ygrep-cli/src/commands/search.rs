@@ -1,36 +1,94 @@
 use anyhow::{Context, Result};
-use std::path::Path;
-use ygrep_core::Workspace;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use ygrep_core::search::{SearchFilters, SearchResult, SEARCHABLE_FIELDS};
+use ygrep_core::config::{MultiTermMode, OutputConfig, DEFAULT_RERANK_TOP_N};
+use ygrep_core::{Config, MultiWorkspace, Workspace, YgrepError};
 
-use crate::OutputFormat;
+use crate::{OutputFormat, SearchArgs};
 
 pub fn run(
     workspace_path: &Path,
     query: &str,
-    limit: usize,
-    extensions: Vec<String>,
-    paths: Vec<String>,
-    use_regex: bool,
-    _show_scores: bool,
-    text_only: bool,
+    args: &SearchArgs,
     format: OutputFormat,
+    config: Config,
 ) -> Result<()> {
-    // Open existing workspace (fails if not indexed)
-    let workspace = match Workspace::open(workspace_path) {
-        Ok(ws) => ws,
-        Err(_) => {
-            eprintln!("Workspace not indexed: {}", workspace_path.display());
-            eprintln!();
-            eprintln!("To index this workspace, run:");
-            eprintln!("  ygrep index              # Text-only (fast)");
-            eprintln!("  ygrep index --semantic   # With semantic search (slower, better results)");
-            std::process::exit(1);
+    let limit = args.limit;
+    let offset = args.offset;
+    let use_regex = args.regex;
+    let text_only = args.text_only;
+
+    if query.trim().is_empty() {
+        eprintln!("Warning: empty search query");
+        return Ok(());
+    }
+
+    if args.all {
+        return run_all(query, args, format, config);
+    }
+    check_languages(&args.languages)?;
+
+    let config = search_config(config, args);
+    let output = output_config(&config);
+    let workspace = open_workspace(workspace_path, config);
+    if workspace.scope().is_some() {
+        eprintln!("Using index from parent workspace: {}", workspace.root().display());
+    }
+    if args.no_ignore && workspace.stored_no_ignore_flag() != Some(true) {
+        eprintln!("Note: ignored files aren't in this index; run `ygrep index --no-ignore` to search them too");
+    }
+
+    let changed_files = match &args.since {
+        Some(git_ref) => changed_files(&workspace, git_ref)?,
+        None => None,
+    };
+
+    if args.files_without_match {
+        let mut files = workspace.files_without_match(query, use_regex).context("Search failed")?;
+        if let Some(changed) = &changed_files {
+            files.retain(|path| changed.contains(path));
         }
+        print_files(&files, format)?;
+        return Ok(());
+    }
+
+    let filters = SearchFilters {
+        extensions: if args.extensions.is_empty() { None } else { Some(args.extensions.clone()) },
+        paths: if args.paths.is_empty() { None } else { Some(args.paths.clone()) },
+        files: changed_files,
+        languages: if args.languages.is_empty() { None } else { Some(args.languages.clone()) },
+        scope: None,
+    };
+
+    // Boolean expression (text search only)
+    if args.query_syntax {
+        let result = workspace.search_boolean_with_filters(query, Some(limit), Some(offset), filters)
+            .context("Search failed")?;
+        warn_if_empty_index(&workspace, &result);
+        print_result(result, format, args.stats, &output);
+        return Ok(());
+    }
+
+    // Field search: --field NAME, or a `field:value` query
+    let field_query = match &args.field {
+        Some(field) => Some((field.as_str(), query)),
+        None if !use_regex => split_field_query(query),
+        None => None,
     };
+    if let Some((field, value)) = field_query {
+        let result = workspace.search_field_with_filters(field, value, Some(limit), Some(offset), filters.clone())
+            .context("Search failed")?
+            .with_query_context(query, &filters, limit);
+        warn_if_empty_index(&workspace, &result);
+        print_result(result, format, args.stats, &output);
+        return Ok(());
+    }
 
     // Search: use hybrid search by default if semantic index is available
     #[cfg(feature = "embeddings")]
-    let use_hybrid = !text_only && workspace.has_semantic_index();
+    let use_hybrid = !text_only && !args.invert_match && !args.defs
+        && workspace.has_semantic_index();
     #[cfg(not(feature = "embeddings"))]
     let use_hybrid = false;
     let _ = text_only; // Suppress unused warning when embeddings disabled
@@ -39,28 +97,262 @@ pub fn run(
         // Hybrid search (BM25 + vector with RRF) - not supported with regex
         #[cfg(feature = "embeddings")]
         {
-            workspace.search_hybrid(query, Some(limit))
+            workspace.search_hybrid_with_filters(query, Some(limit), Some(offset), filters.clone())
                 .context("Hybrid search failed")?
+                .with_query_context(query, &filters, limit)
         }
         #[cfg(not(feature = "embeddings"))]
         unreachable!()
     } else {
-        // Build filters for text-only search
-        let ext_filter = if extensions.is_empty() { None } else { Some(extensions) };
-        let path_filter = if paths.is_empty() { None } else { Some(paths) };
-
-        workspace.search_filtered(query, Some(limit), ext_filter, path_filter, use_regex)
+        workspace.search_with_filters(query, Some(limit), Some(offset), filters, use_regex)
             .context("Search failed")?
     };
 
-    // Output results
+    warn_if_empty_index(&workspace, &result);
+    print_result(result, format, args.stats, &output);
+
+    Ok(())
+}
+
+/// Files changed since `git_ref` (paths relative to the index root), or None with a warning
+/// when the workspace isn't in a git repository
+fn changed_files(workspace: &Workspace, git_ref: &str) -> Result<Option<Vec<String>>> {
+    let files = ygrep_core::fs::git::changed_files(workspace.root(), git_ref)
+        .with_context(|| format!("Failed to list files changed since {}", git_ref))?;
+    if files.is_none() {
+        eprintln!("Warning: {} isn't in a git repository, ignoring --since", workspace.root().display());
+    }
+    Ok(files)
+}
+
+/// Reject `--lang` values no file is ever detected as
+fn check_languages(languages: &[String]) -> Result<()> {
+    use ygrep_core::index::language::{is_known_language, known_languages};
+
+    if let Some(unknown) = languages.iter().find(|language| !is_known_language(language)) {
+        anyhow::bail!("Unknown language '{}' (known languages: {})", unknown, known_languages().join(", "));
+    }
+    Ok(())
+}
+
+/// Open an indexed workspace, or explain how to index it and exit
+fn open_workspace(workspace_path: &Path, config: Config) -> Workspace {
+    match Workspace::open_with_config(workspace_path, config) {
+        Ok(ws) => ws,
+        Err(e @ (YgrepError::IncompatibleIndex { .. } | YgrepError::EmbeddingDimensionMismatch { .. })) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        Err(_) => {
+            eprintln!("Workspace not indexed: {}", workspace_path.display());
+            eprintln!();
+            eprintln!("To index this workspace, run:");
+            eprintln!("  ygrep index              # Text-only (fast)");
+            eprintln!("  ygrep index --semantic   # With semantic search (slower, better results)");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Search several workspaces (`-C a -C b`) and print the merged results (text search only)
+pub fn run_multi(
+    workspace_paths: &[PathBuf],
+    query: &str,
+    args: &SearchArgs,
+    format: OutputFormat,
+    config: Config,
+) -> Result<()> {
+    if query.trim().is_empty() {
+        eprintln!("Warning: empty search query");
+        return Ok(());
+    }
+
+    if args.all {
+        return run_all(query, args, format, config);
+    }
+    if args.since.is_some() {
+        anyhow::bail!("--since only works when searching a single workspace");
+    }
+    if !args.languages.is_empty() {
+        anyhow::bail!("--lang only works when searching a single workspace");
+    }
+
+    let config = search_config(config, args);
+    let output = output_config(&config);
+    let workspaces: Vec<_> = workspace_paths.iter()
+        .map(|path| open_workspace(path, config.clone()))
+        .collect();
+
+    let ext_filter = if args.extensions.is_empty() { None } else { Some(args.extensions.clone()) };
+    let path_filter = if args.paths.is_empty() { None } else { Some(args.paths.clone()) };
+
+    let result = Workspace::search_multi_filtered(
+        &workspaces, query, Some(args.limit), Some(args.offset), ext_filter, path_filter, args.regex,
+    ).context("Search failed")?;
+
+    print_result(result, format, args.stats, &output);
+
+    Ok(())
+}
+
+/// Search every indexed workspace and print the merged results (text search only)
+fn run_all(query: &str, args: &SearchArgs, format: OutputFormat, config: Config) -> Result<()> {
+    let config = search_config(config, args);
+    let output = output_config(&config);
+    let multi = MultiWorkspace::open_all_with_config(config).context("Failed to open indexes")?;
+
+    if multi.workspaces().is_empty() {
+        eprintln!("No indexed workspaces found.");
+        eprintln!();
+        eprintln!("To index a workspace, run `ygrep index` in its directory.");
+        std::process::exit(1);
+    }
+
+    let ext_filter = if args.extensions.is_empty() { None } else { Some(args.extensions.clone()) };
+    let path_filter = if args.paths.is_empty() { None } else { Some(args.paths.clone()) };
+
+    let result = multi.search_filtered(query, Some(args.limit), Some(args.offset), ext_filter, path_filter, args.regex)
+        .context("Search failed")?;
+
+    print_result(result, format, args.stats, &output);
+
+    Ok(())
+}
+
+/// Apply the search flags that override the config
+fn search_config(mut config: Config, args: &SearchArgs) -> Config {
+    // An explicit `-n` is what the user asked for, so `max_limit` mustn't cut it short
+    config.search.max_limit = config.search.max_limit.max(args.limit);
+    if args.all_terms {
+        config.search.multi_term_mode = MultiTermMode::All;
+    }
+    if args.fuzzy {
+        config.search.fuzzy_enabled = true;
+    }
+    if let Some(slop) = args.slop {
+        config.search.phrase_slop = slop;
+    }
+    if args.invert_match {
+        config.search.invert_match = true;
+    }
+    if args.multiline {
+        config.search.multiline = true;
+    }
+    if args.defs {
+        config.search.definitions_only = true;
+    }
+    if args.rerank && config.search.rerank_top_n == 0 {
+        config.search.rerank_top_n = DEFAULT_RERANK_TOP_N;
+    }
+    if args.timeout.is_some() {
+        config.search.timeout_ms = args.timeout;
+    }
+    if args.max_per_file.is_some() {
+        config.search.max_results_per_file = args.max_per_file;
+    }
+    if args.highlight {
+        config.output.highlight = true;
+    }
+    if args.snippet_len.is_some() {
+        config.output.max_snippet_chars = args.snippet_len;
+    }
+    config
+}
+
+/// Output settings for printing results; highlighting is dropped unless stdout is a
+/// terminal that takes colors (not piped, `NO_COLOR` unset, `TERM` not `dumb`)
+fn output_config(config: &Config) -> OutputConfig {
+    let colors = std::io::stdout().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && std::env::var_os("TERM").is_none_or(|term| term != "dumb");
+
+    let mut output = config.output.clone();
+    output.highlight &= colors;
+    output
+}
+
+/// Warn when nothing matched because the index holds no documents at all
+/// (only checked for empty results, since listing the index isn't free)
+fn warn_if_empty_index(workspace: &Workspace, result: &SearchResult) {
+    if result.hits.is_empty()
+        && workspace.is_indexed()
+        && workspace.list_indexed_paths().is_ok_and(|files| files.is_empty())
+    {
+        eprintln!("Warning: index exists but contains 0 documents. Check your ignore_patterns or run `ygrep index --rebuild`");
+    }
+}
+
+/// Split a `field:value` query when `field` is a searchable field name (so `std::fs` or
+/// `http://` stay plain searches)
+fn split_field_query(query: &str) -> Option<(&str, &str)> {
+    let (field, value) = query.split_once(':')?;
+    (SEARCHABLE_FIELDS.contains(&field) && !value.is_empty()).then_some((field, value))
+}
+
+/// Print results in the requested output format
+/// With `stats`, the timing breakdown goes to stderr (or stays in the JSON output)
+fn print_result(mut result: SearchResult, format: OutputFormat, stats: bool, options: &OutputConfig) {
+    if result.timed_out {
+        eprintln!("Warning: search timed out after {} ms, results are partial", result.query_time_ms);
+    }
+
+    if !stats {
+        result.timings = None;
+    } else if format != OutputFormat::Json {
+        print_stats(&result);
+    }
+
     let output = match format {
-        OutputFormat::Ai => result.format_ai(),
+        OutputFormat::Ai => result.format_ai_with(options),
         OutputFormat::Json => result.format_json(),
-        OutputFormat::Pretty => result.format_pretty(),
+        OutputFormat::Pretty => result.format_pretty_with(options),
+        OutputFormat::Csv(separator) => result.format_csv(separator),
+        OutputFormat::Grep => result.format_grep(),
     };
 
     print!("{}", output);
+}
+
+/// Print the paths of files without a match (a JSON array with `--json`)
+fn print_files(files: &[String], format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(files)?);
+        return Ok(());
+    }
 
+    println!("# {} files without a match", files.len());
+    for file in files {
+        println!("{}", file);
+    }
     Ok(())
 }
+
+/// Print the per-phase timing breakdown to stderr
+fn print_stats(result: &SearchResult) {
+    eprintln!("# {} ms total", result.query_time_ms);
+
+    let Some(timings) = &result.timings else {
+        return;
+    };
+    let embed_source = if timings.embed_cache_hit { "cached" } else { "model" };
+    eprintln!("  bm25:   {:>8.2} ms", timings.bm25_ms);
+    eprintln!("  embed:  {:>8.2} ms ({})", timings.embed_ms, embed_source);
+    eprintln!("  vector: {:>8.2} ms", timings.vector_ms);
+    eprintln!("  fusion: {:>8.2} ms", timings.fusion_ms);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_field_query() {
+        assert_eq!(split_field_query("path:auth"), Some(("path", "auth")));
+        assert_eq!(split_field_query("content:a:b"), Some(("content", "a:b")));
+        assert_eq!(split_field_query("content:fn main"), Some(("content", "fn main")));
+        assert_eq!(split_field_query("std::io"), None);
+        assert_eq!(split_field_query("http://localhost"), None);
+        assert_eq!(split_field_query("author:me"), None);
+        assert_eq!(split_field_query("path:"), None);
+    }
+}
@@ -1,15 +1,17 @@
 use anyhow::Result;
 use std::path::Path;
-use ygrep_core::Workspace;
+use ygrep_core::{Config, Workspace, YgrepError};
 
-pub fn run(workspace_path: &Path, detailed: bool) -> Result<()> {
+use super::indexes::format_size;
+
+pub fn run(workspace_path: &Path, detailed: bool, config: Config) -> Result<()> {
     println!("ygrep status");
     println!("============");
     println!();
     println!("Workspace: {}", workspace_path.display());
 
     // Try to open workspace
-    match Workspace::open(workspace_path) {
+    match Workspace::open_with_config(workspace_path, config) {
         Ok(workspace) => {
             println!("Index path: {}", workspace.index_path().display());
             println!("Indexed: yes");
@@ -29,12 +31,23 @@ pub fn run(workspace_path: &Path, detailed: bool) -> Result<()> {
             }
 
             if detailed {
+                let stats = workspace.indexer_stats()?;
                 println!();
                 println!("Index details:");
-                // TODO: Add more detailed stats from index
-                println!("  (detailed stats coming in future version)");
+                println!("  Documents: {} ({} files, {} chunks)",
+                    stats.total_documents,
+                    stats.total_documents - stats.total_chunks,
+                    stats.total_chunks);
+                println!("  Segments: {}", stats.segment_count);
+                println!("  Vectors: {}", stats.vector_count);
+                println!("  Size: {}", format_size(stats.index_size_bytes));
             }
         }
+        Err(e @ (YgrepError::IncompatibleIndex { .. } | YgrepError::EmbeddingDimensionMismatch { .. })) => {
+            println!("Indexed: yes (incompatible)");
+            println!();
+            println!("{}", e);
+        }
         Err(_) => {
             println!("Indexed: no");
             println!();
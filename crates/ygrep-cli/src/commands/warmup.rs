@@ -0,0 +1,34 @@
+use anyhow::Result;
+use std::path::Path;
+use ygrep_core::Config;
+
+/// Download (if needed) and load the semantic model so later searches don't stall on it
+#[cfg(feature = "embeddings")]
+pub fn run(workspace_path: &Path, config: Config) -> Result<()> {
+    use anyhow::Context;
+    use std::time::Instant;
+    use ygrep_core::embeddings::{EmbeddingModel, ModelType};
+    use ygrep_core::Workspace;
+
+    // Use the model the workspace was indexed with, otherwise the configured one
+    let model_type = match Workspace::open_with_config(workspace_path, config.clone()) {
+        Ok(workspace) => workspace.embedding_model_type(),
+        Err(_) => ModelType::from_config(&config.indexer)?,
+    };
+
+    let start = Instant::now();
+    let model = EmbeddingModel::with_config(model_type, &config.indexer);
+    model.preload().with_context(|| {
+        format!("Failed to download or load {} (are you offline?)", model.name())
+    })?;
+
+    eprintln!("{} ready in {:.1}s", model.name(), start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+/// Semantic search isn't compiled in, so there is no model to load
+#[cfg(not(feature = "embeddings"))]
+pub fn run(_workspace_path: &Path, _config: Config) -> Result<()> {
+    eprintln!("Warning: Semantic search feature not available in this build.");
+    Ok(())
+}
@@ -1,13 +1,36 @@
 use anyhow::{Context, Result};
 use std::path::Path;
-use ygrep_core::{Workspace, WatchEvent};
+use std::time::{Duration, Instant};
+use ygrep_core::{Config, FileWatcher, Workspace, WatchEvent, YgrepError};
 
-pub fn run(workspace_path: &Path) -> Result<()> {
+use crate::WatchMode;
+
+/// Running totals for the watch session
+#[derive(Default)]
+struct WatchCounts {
+    changed: u64,
+    deleted: u64,
+    errors: u64,
+}
+
+/// With `verbose`, every re-indexed file is printed with its indexing time;
+/// otherwise each debounce batch prints a one-line summary
+pub fn run(
+    workspace_path: &Path,
+    mode: Option<WatchMode>,
+    ignore: Vec<String>,
+    verbose: bool,
+    config: Config,
+) -> Result<()> {
     eprintln!("Opening workspace {}...", workspace_path.display());
 
     // Open existing workspace (fails if not indexed)
-    let workspace = match Workspace::open(workspace_path) {
+    let workspace = match Workspace::open_with_config(workspace_path, config) {
         Ok(ws) => ws,
+        Err(e @ (YgrepError::IncompatibleIndex { .. } | YgrepError::EmbeddingDimensionMismatch { .. })) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
         Err(_) => {
             eprintln!("Workspace not indexed: {}", workspace_path.display());
             eprintln!();
@@ -18,8 +41,16 @@ pub fn run(workspace_path: &Path) -> Result<()> {
         }
     };
 
-    // Read the stored semantic flag
-    let use_semantic = workspace.stored_semantic_flag().unwrap_or(false);
+    // Explicit --mode wins, otherwise use the stored semantic flag
+    let use_semantic = match mode {
+        Some(mode) => mode == WatchMode::Semantic,
+        None => workspace.stored_semantic_flag().unwrap_or(false),
+    };
+
+    #[cfg(not(feature = "embeddings"))]
+    if use_semantic {
+        eprintln!("Warning: Semantic search feature not available in this build.");
+    }
 
     let mode = if use_semantic { "semantic" } else { "text" };
     eprintln!("Starting file watcher (mode: {})...", mode);
@@ -28,6 +59,10 @@ pub fn run(workspace_path: &Path) -> Result<()> {
     let mut watcher = workspace.create_watcher()
         .context("Failed to create file watcher")?;
 
+    for pattern in ignore {
+        watcher.add_ignore_pattern(pattern);
+    }
+
     watcher.start()
         .context("Failed to start file watcher")?;
 
@@ -36,67 +71,188 @@ pub fn run(workspace_path: &Path) -> Result<()> {
         .context("Failed to create async runtime")?;
 
     rt.block_on(async {
-        let mut changed_count = 0u64;
-        let mut deleted_count = 0u64;
-        let mut error_count = 0u64;
+        let mut counts = WatchCounts::default();
+        let mut hangup = Hangup::new()?;
 
         loop {
-            match watcher.next_event().await {
-                Some(WatchEvent::Changed(path)) => {
-                    // Check if it's a text file we should index
-                    if is_indexable(&path) {
-                        match workspace.index_file_with_options(&path, use_semantic) {
-                            Ok(()) => {
-                                changed_count += 1;
-                                eprintln!("  [+] {}", path.display());
-                            }
-                            Err(e) => {
-                                error_count += 1;
-                                eprintln!("  [!] {} - {}", path.display(), e);
-                            }
-                        }
-                    }
+            let first = tokio::select! {
+                event = watcher.next_event() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                _ = hangup.recv() => {
+                    reload_ignore_patterns(&watcher);
+                    continue;
                 }
-                Some(WatchEvent::Deleted(path)) => {
-                    match workspace.delete_file(&path) {
-                        Ok(()) => {
-                            deleted_count += 1;
-                            eprintln!("  [-] {}", path.display());
-                        }
-                        Err(e) => {
-                            // File might not have been in index, that's OK
-                            tracing::debug!("Delete error for {}: {}", path.display(), e);
-                        }
-                    }
+            };
+
+            // Handle everything queued from the same debounce batch together
+            let mut batch_files = 0usize;
+            let mut batch_time = Duration::ZERO;
+            let mut next = Some(first);
+            while let Some(event) = next {
+                if let Some(elapsed) = handle_event(&workspace, event, use_semantic, verbose, &mut counts) {
+                    batch_files += 1;
+                    batch_time += elapsed;
                 }
-                Some(WatchEvent::DirCreated(path)) => {
-                    eprintln!("  [d] {} (new directory)", path.display());
+
+                // Print periodic stats
+                let total = counts.changed + counts.deleted;
+                if total.is_multiple_of(100) && total > 0 {
+                    eprintln!("\n--- Stats: {} indexed, {} deleted, {} errors ---\n",
+                        counts.changed, counts.deleted, counts.errors);
                 }
-                Some(WatchEvent::DirDeleted(path)) => {
-                    eprintln!("  [d] {} (directory removed)", path.display());
+
+                next = watcher.try_next_event();
+            }
+
+            if batch_files > 0 {
+                eprintln!("  Indexed {} file{} ({}ms)",
+                    batch_files, if batch_files == 1 { "" } else { "s" }, batch_time.as_millis());
+            }
+        }
+
+        eprintln!("\nWatch stopped. {} indexed, {} deleted, {} errors.",
+            counts.changed, counts.deleted, counts.errors);
+        anyhow::Ok(())
+    })
+}
+
+/// SIGHUP notifications (never fires on other platforms)
+struct Hangup {
+    #[cfg(unix)]
+    signal: tokio::signal::unix::Signal,
+}
+
+impl Hangup {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+                .context("Failed to listen for SIGHUP")?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.signal.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Re-read the workspace's .ygrep.toml and start ignoring any patterns added to it
+/// (patterns removed from it stay ignored until the watcher restarts)
+fn reload_ignore_patterns(watcher: &FileWatcher) {
+    let path = watcher.root().join(".ygrep.toml");
+    let config = match Config::load_from(&path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Couldn't reload ignore patterns: {}", e);
+            return;
+        }
+    };
+
+    let added: Vec<String> = config.indexer.ignore_patterns.into_iter()
+        .filter(|pattern| watcher.add_ignore_pattern(pattern.clone()))
+        .collect();
+    if added.is_empty() {
+        eprintln!("Reloaded {}: no new ignore patterns", path.display());
+    } else {
+        eprintln!("Reloaded {}: now also ignoring {}", path.display(), added.join(", "));
+    }
+}
+
+/// Apply one watch event to the index, returning how long re-indexing took
+/// when a file was indexed
+fn handle_event(
+    workspace: &Workspace,
+    event: WatchEvent,
+    use_semantic: bool,
+    verbose: bool,
+    counts: &mut WatchCounts,
+) -> Option<Duration> {
+    match event {
+        WatchEvent::Changed(path) => {
+            // Check if it's a text file we should index
+            if !is_indexable(&path) {
+                return None;
+            }
+
+            let start = Instant::now();
+            match index_changed_file(workspace, &path, use_semantic) {
+                Ok(()) => {
+                    let elapsed = start.elapsed();
+                    counts.changed += 1;
+                    if verbose {
+                        let display = path.strip_prefix(workspace.root()).unwrap_or(&path);
+                        eprintln!("  ↺ {} ({}ms)", display.display(), elapsed.as_millis());
+                    }
+                    return Some(elapsed);
                 }
-                Some(WatchEvent::Error(e)) => {
-                    error_count += 1;
-                    eprintln!("  [!] Watch error: {}", e);
+                Err(e) => {
+                    counts.errors += 1;
+                    eprintln!("  [!] {} - {}", path.display(), e.chain());
                 }
-                None => {
-                    // Channel closed, exit
-                    break;
+            }
+        }
+        WatchEvent::Deleted(path) => {
+            // Removals don't say whether a file or a directory went away,
+            // so treat the path as a directory first
+            let removed_files = workspace.delete_directory(&path).unwrap_or_else(|e| {
+                tracing::debug!("Delete error for {}: {}", path.display(), e.chain());
+                0
+            });
+            if removed_files > 0 {
+                counts.deleted += removed_files as u64;
+                eprintln!("  [d] {} (directory removed, {} files)", path.display(), removed_files);
+            } else {
+                match workspace.delete_file(&path) {
+                    Ok(()) => {
+                        counts.deleted += 1;
+                        eprintln!("  [-] {}", path.display());
+                    }
+                    Err(e) => {
+                        // File might not have been in index, that's OK
+                        tracing::debug!("Delete error for {}: {}", path.display(), e.chain());
+                    }
                 }
             }
-
-            // Print periodic stats
-            if (changed_count + deleted_count) % 100 == 0 && (changed_count + deleted_count) > 0 {
-                eprintln!("\n--- Stats: {} indexed, {} deleted, {} errors ---\n",
-                    changed_count, deleted_count, error_count);
+        }
+        WatchEvent::DirCreated(path) => {
+            eprintln!("  [d] {} (new directory)", path.display());
+        }
+        WatchEvent::DirDeleted(path) => {
+            match workspace.delete_directory(&path) {
+                Ok(removed_files) => {
+                    counts.deleted += removed_files as u64;
+                    eprintln!("  [d] {} (directory removed, {} files)", path.display(), removed_files);
+                }
+                Err(e) => {
+                    counts.errors += 1;
+                    eprintln!("  [!] {} - {}", path.display(), e.chain());
+                }
             }
         }
+        WatchEvent::Error(e) => {
+            counts.errors += 1;
+            eprintln!("  [!] Watch error: {}", e);
+        }
+    }
 
-        eprintln!("\nWatch stopped. {} indexed, {} deleted, {} errors.",
-            changed_count, deleted_count, error_count);
-    });
+    None
+}
+
+/// Re-index a changed file, updating its embedding in semantic mode
+fn index_changed_file(workspace: &Workspace, path: &Path, use_semantic: bool) -> ygrep_core::Result<()> {
+    #[cfg(feature = "embeddings")]
+    if use_semantic {
+        return workspace.index_file_with_embeddings(path);
+    }
+    #[cfg(not(feature = "embeddings"))]
+    let _ = use_semantic;
 
-    Ok(())
+    workspace.index_file(path)
 }
 
 /// Check if a file should be indexed (simple extension check)
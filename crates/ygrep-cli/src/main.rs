@@ -1,5 +1,5 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 mod commands;
@@ -28,6 +28,9 @@ Match indicators in default output:\n\
     ygrep \"fn main\" -n 10           Limit to 10 results\n\
     ygrep \"->get(\" -e php           Search PHP files only\n\
     ygrep \"fn\\\\s+main\" -r            Regex search\n\
+    ygrep \"TODO\" --all              Search every indexed workspace\n\
+    ygrep \"TODO\" -C api -C web      Search two workspaces together\n\
+    ygrep path:auth                 Search file paths only\n\
     ygrep search \"api\" --json       JSON output\n\
     ygrep install claude-code       Install for Claude Code\n\n\
 For more info: https://github.com/yetidevworks/ygrep")]
@@ -38,13 +41,13 @@ pub struct Cli {
     /// Search query (shorthand for `ygrep search <QUERY>`)
     pub query: Option<String>,
 
-    /// Maximum results
-    #[arg(short = 'n', long, default_value = "100")]
-    pub limit: usize,
+    #[command(flatten)]
+    pub search: SearchArgs,
 
-    /// Workspace root (default: current directory)
+    /// Workspace root (default: current directory); repeat it to search several workspaces
+    /// and merge their results
     #[arg(short = 'C', long, global = true)]
-    pub workspace: Option<PathBuf>,
+    pub workspace: Vec<PathBuf>,
 
     /// Output as JSON
     #[arg(long, global = true, conflicts_with = "pretty")]
@@ -54,25 +57,143 @@ pub struct Cli {
     #[arg(long, global = true, conflicts_with = "json")]
     pub pretty: bool,
 
+    /// Output search results as CSV (path, line_start, line_end, score, snippet)
+    #[arg(long, global = true, conflicts_with_all = ["json", "pretty"])]
+    pub csv: bool,
+
+    /// Output format: ai, json, pretty, csv or grep (`path:line:column:text`, for vim's `:grep` or fzf)
+    #[arg(long, global = true, value_enum, conflicts_with_all = ["json", "pretty", "csv"])]
+    pub format: Option<FormatName>,
+
+    /// Field separator for --csv (e.g. '\t' for TSV)
+    #[arg(long, global = true, default_value = ",", value_parser = parse_csv_separator)]
+    pub csv_sep: char,
+
     /// Verbose output
     #[arg(short, long, global = true)]
     pub verbose: bool,
 
-    /// Treat query as regex pattern
-    #[arg(short = 'r', long)]
-    pub regex: bool,
+    /// Serve searches over stdin/stdout: one JSON request per line in, one JSON response per
+    /// line out (for editor integrations that keep a ygrep process running)
+    #[arg(long, conflicts_with = "query")]
+    pub proto: bool,
+}
+
+/// Search options shared by the shorthand form and the `search` subcommand
+#[derive(Args, Clone, Debug)]
+pub struct SearchArgs {
+    /// Maximum results (raises `search.max_limit` for this search when higher)
+    #[arg(short = 'n', long, default_value = "100")]
+    pub limit: usize,
+
+    /// Skip the first N results (for paging through large result sets)
+    #[arg(long, visible_alias = "skip", default_value = "0")]
+    pub offset: usize,
 
     /// Filter by file extension (e.g., -e rs -e ts)
     #[arg(short = 'e', long = "ext")]
     pub extensions: Vec<String>,
 
-    /// Filter by path pattern
+    /// Filter by path: a glob (`src/auth/**`, `**/*_test.go`; `*` stays within a directory,
+    /// `**` spans directories) or, without wildcards, any part of the path (e.g. -p auth)
     #[arg(short = 'p', long = "path")]
     pub paths: Vec<String>,
 
+    /// Filter by detected language, from the extension or a `#!` line for scripts
+    /// (e.g., --lang rust,python)
+    #[arg(long = "lang", value_name = "LANG", value_delimiter = ',', conflicts_with_all = ["all", "field"])]
+    pub languages: Vec<String>,
+
+    /// Only search files changed since a git ref (`git diff --name-only REF`, plus untracked
+    /// files); ignored with a warning outside a git repository. Uses text search
+    #[arg(long, value_name = "REF", conflicts_with_all = ["all", "field"])]
+    pub since: Option<String>,
+
+    /// Treat query as regex pattern instead of literal text
+    #[arg(short = 'r', long)]
+    pub regex: bool,
+
+    /// Show relevance scores
+    #[arg(long)]
+    pub scores: bool,
+
     /// Text-only search (disable semantic search)
     #[arg(long)]
     pub text_only: bool,
+
+    /// Search a single field: content, path, extension, doc_id
+    /// (`field:value` in the query does the same, e.g. `ygrep path:auth`)
+    #[arg(long, value_name = "FIELD")]
+    pub field: Option<String>,
+
+    /// Search across all indexed workspaces (see `ygrep indexes list`)
+    #[arg(long)]
+    pub all: bool,
+
+    /// Treat the query as a boolean expression: AND, OR, NOT (or -word), parentheses and "phrases"
+    /// (e.g. `error AND (timeout OR deadline) NOT test`)
+    #[arg(short = 'Q', long, conflicts_with_all = ["regex", "field", "fuzzy", "all"])]
+    pub query_syntax: bool,
+
+    /// Show the lines of matching files that don't match the query (like grep -v)
+    #[arg(long, conflicts_with_all = ["multiline", "query_syntax", "field", "fuzzy"])]
+    pub invert_match: bool,
+
+    /// List indexed files that don't match the query at all (like grep -L)
+    #[arg(short = 'L', long, conflicts_with_all = ["invert_match", "query_syntax", "field", "fuzzy", "all"])]
+    pub files_without_match: bool,
+
+    /// Only match definition lines (functions, classes, types, constants), e.g. where
+    /// `parse_config` is defined rather than every call (text search only)
+    #[arg(long, conflicts_with_all = ["invert_match", "files_without_match", "multiline", "query_syntax", "field", "fuzzy"])]
+    pub defs: bool,
+
+    /// Let regex patterns match across lines (`.` matches newlines; reports the whole match span)
+    #[arg(short = 'U', long, requires = "regex")]
+    pub multiline: bool,
+
+    /// Maximum results from a single file
+    #[arg(long, value_name = "N")]
+    pub max_per_file: Option<usize>,
+
+    /// Only fetch documents containing every word of the query (AND instead of OR)
+    #[arg(long)]
+    pub all_terms: bool,
+
+    /// Typo-tolerant search: match query words within `search.fuzzy_distance` edits
+    #[arg(long)]
+    pub fuzzy: bool,
+
+    /// Match the words of a multi-word query with up to N tokens between them, e.g.
+    /// `"fn main" --slop 3` also finds `fn<T> main` (or search.phrase_slop; 0 = exact)
+    #[arg(long, value_name = "N", conflicts_with_all = ["fuzzy", "regex", "query_syntax"])]
+    pub slop: Option<u32>,
+
+    /// Re-rank the top hybrid results with a cross-encoder model (slower, more precise;
+    /// `search.rerank_top_n` sets how many, default 50)
+    #[arg(long, conflicts_with = "text_only")]
+    pub rerank: bool,
+
+    /// Stop searching after MS milliseconds and print the results found so far
+    #[arg(long, value_name = "MS")]
+    pub timeout: Option<u64>,
+
+    /// Print a timing breakdown to stderr (included in --json output)
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Highlight query matches in snippets with colors (ignored when output isn't a terminal)
+    #[arg(long)]
+    pub highlight: bool,
+
+    /// Cut snippet lines after N characters (or output.max_snippet_chars; default 100 for
+    /// AI output, 80 for --pretty)
+    #[arg(long, value_name = "N")]
+    pub snippet_len: Option<usize>,
+
+    /// Include ignored files (warns unless the index was built with `ygrep index --no-ignore`)
+    #[arg(long)]
+    pub no_ignore: bool,
 }
 
 #[derive(Subcommand)]
@@ -82,29 +203,8 @@ pub enum Commands {
         /// Search query (literal text or regex with --regex)
         query: String,
 
-        /// Maximum results
-        #[arg(short = 'n', long, default_value = "100")]
-        limit: usize,
-
-        /// Filter by file extension (e.g., -e rs -e ts)
-        #[arg(short = 'e', long = "ext")]
-        extensions: Vec<String>,
-
-        /// Filter by path pattern
-        #[arg(short = 'p', long = "path")]
-        paths: Vec<String>,
-
-        /// Treat query as regex pattern instead of literal text
-        #[arg(short = 'r', long)]
-        regex: bool,
-
-        /// Show relevance scores
-        #[arg(long)]
-        scores: bool,
-
-        /// Text-only search (disable semantic search)
-        #[arg(long)]
-        text_only: bool,
+        #[command(flatten)]
+        args: SearchArgs,
     },
 
     /// Build search index for a workspace (run before searching)
@@ -123,6 +223,23 @@ pub enum Commands {
         /// Build text-only index (fast, default). Converts semantic to text-only.
         #[arg(long, conflicts_with = "semantic")]
         text: bool,
+
+        /// Show which files would be indexed or skipped, without writing an index
+        #[arg(long, conflicts_with_all = ["rebuild", "semantic", "text", "local"])]
+        dry_run: bool,
+
+        /// Store the index in .ygrep-index inside the workspace (found automatically when searching)
+        #[arg(long)]
+        local: bool,
+
+        /// Show progress bars while indexing and embedding (falls back to plain output when not a terminal)
+        #[arg(long, conflicts_with = "dry_run")]
+        progress: bool,
+
+        /// Index everything: skip no ignore patterns, .gitignore rules or built-in directories
+        /// like node_modules and dist (can index huge trees)
+        #[arg(long)]
+        no_ignore: bool,
     },
 
     /// Show index status for current workspace
@@ -132,36 +249,90 @@ pub enum Commands {
         detailed: bool,
     },
 
+    /// Show detailed index statistics (same as `status --detailed`)
+    Stats,
+
+    /// List files in the index for the current workspace
+    List,
+
+    /// Download and load the semantic model ahead of time (so the first search is fast)
+    Warmup,
+
     /// Watch for file changes and update index automatically
     Watch {
         /// Workspace path (default: current directory)
         path: Option<PathBuf>,
+
+        /// Index mode for changed files (default: the mode the index was built with)
+        #[arg(long, value_enum)]
+        mode: Option<WatchMode>,
+
+        /// Also ignore changes to paths matching GLOB (repeatable). On Unix, sending the
+        /// process SIGHUP picks up patterns added to the workspace's .ygrep.toml
+        #[arg(long, value_name = "GLOB")]
+        ignore: Vec<String>,
     },
 
     /// Install ygrep integration for AI coding tools
-    #[command(subcommand)]
-    Install(InstallTarget),
+    Install {
+        #[command(subcommand)]
+        target: InstallTarget,
+
+        /// Check the installation afterwards: files written, ygrep on PATH and runnable
+        #[arg(long, global = true)]
+        verify: bool,
+    },
 
     /// Remove ygrep integration from AI coding tools
     #[command(subcommand)]
     Uninstall(InstallTarget),
 
-    /// Manage stored indexes (list, clean, remove)
+    /// Manage stored indexes (list, info, clean, remove)
     #[command(subcommand)]
     Indexes(IndexesCommand),
+
+    /// Show the effective config and where it comes from
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand, Clone)]
+pub enum ConfigCommand {
+    /// Print the config in effect as TOML, noting the file it was loaded from (use --json for JSON)
+    Show,
+    /// List the config files ygrep looks for, in order of precedence, and which one is used
+    Path,
+}
+
+/// How `ygrep watch` re-indexes changed files
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchMode {
+    /// Update the text index only
+    Text,
+    /// Update the text index and the semantic (vector) index
+    Semantic,
 }
 
 #[derive(Subcommand, Clone)]
 pub enum IndexesCommand {
-    /// List all indexes with size and type (text/semantic)
+    /// List all indexes with size and type (text/semantic; use --json for machine-readable output)
     List,
     /// Remove orphaned indexes for workspaces that no longer exist
-    Clean,
+    Clean {
+        /// Show what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Remove a specific index by hash or workspace path
     Remove {
         /// Index hash (from `ygrep indexes list`) or workspace path
         identifier: String,
     },
+    /// Show details for a single index (use --json for machine-readable output)
+    Info {
+        /// Index hash (from `ygrep indexes list`) or workspace path
+        identifier: String,
+    },
 }
 
 #[derive(Subcommand, Clone)]
@@ -176,7 +347,17 @@ pub enum InstallTarget {
     Droid,
 }
 
-/// Output format determined by --json or --pretty flags
+/// Values of --format
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatName {
+    Ai,
+    Json,
+    Pretty,
+    Csv,
+    Grep,
+}
+
+/// Output format determined by --json, --pretty, --csv or --format
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum OutputFormat {
     /// AI-optimized minimal output (default)
@@ -186,18 +367,48 @@ pub enum OutputFormat {
     Json,
     /// Human-readable formatted output
     Pretty,
+    /// CSV output with the given field separator
+    Csv(char),
+    /// grep-style `path:line:column:text` lines
+    Grep,
 }
 
 impl OutputFormat {
-    pub fn from_flags(json: bool, pretty: bool) -> Self {
+    pub fn from_flags(json: bool, pretty: bool, csv: Option<char>) -> Self {
         if json {
             OutputFormat::Json
         } else if pretty {
             OutputFormat::Pretty
+        } else if let Some(separator) = csv {
+            OutputFormat::Csv(separator)
         } else {
             OutputFormat::Ai
         }
     }
+
+    /// Format selected by --format (`csv_separator` comes from --csv-sep)
+    pub fn from_name(name: FormatName, csv_separator: char) -> Self {
+        match name {
+            FormatName::Ai => OutputFormat::Ai,
+            FormatName::Json => OutputFormat::Json,
+            FormatName::Pretty => OutputFormat::Pretty,
+            FormatName::Csv => OutputFormat::Csv(csv_separator),
+            FormatName::Grep => OutputFormat::Grep,
+        }
+    }
+}
+
+/// Parse a --csv-sep value, accepting `\t` (or `tab`) for TSV
+fn parse_csv_separator(value: &str) -> Result<char, String> {
+    if value == "\\t" || value == "tab" {
+        return Ok('\t');
+    }
+
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '"' && c != '\n' => Ok(c),
+        _ => Err(format!("separator must be a single character, got '{}'", value)),
+    }
 }
 
 fn main() -> Result<()> {
@@ -215,36 +426,85 @@ fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Determine workspace
-    let workspace = cli.workspace.clone().unwrap_or_else(|| {
+    // Report broken config files instead of silently using the defaults; commands use the
+    // config loaded here (`ygrep config` loads its own so it can help find the broken file)
+    let config = if matches!(cli.command, Some(Commands::Config(_))) {
+        ygrep_core::Config::default()
+    } else {
+        match ygrep_core::Config::load_strict() {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    // Determine workspace (only searches accept more than one)
+    let is_search = matches!(cli.command, Some(Commands::Search { .. }) | None);
+    if cli.workspace.len() > 1 && !is_search {
+        eprintln!("Error: --workspace can only be given more than once when searching");
+        std::process::exit(1);
+    }
+    let workspace = cli.workspace.first().cloned().unwrap_or_else(|| {
         std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
     });
 
     // Determine output format from flags
-    let format = OutputFormat::from_flags(cli.json, cli.pretty);
+    let format = match cli.format {
+        Some(name) => OutputFormat::from_name(name, cli.csv_sep),
+        None => OutputFormat::from_flags(cli.json, cli.pretty, cli.csv.then_some(cli.csv_sep)),
+    };
+
+    if cli.proto {
+        if cli.command.is_some() {
+            eprintln!("Error: --proto can't be combined with a subcommand");
+            std::process::exit(2);
+        }
+        return commands::proto::run(&workspace, config);
+    }
 
     // Handle command
     match cli.command {
-        Some(Commands::Search { query, limit, extensions, paths, regex, scores, text_only }) => {
-            commands::search::run(&workspace, &query, limit, extensions, paths, regex, scores, text_only, format)?;
+        Some(Commands::Search { query, args }) if cli.workspace.len() > 1 => {
+            commands::search::run_multi(&cli.workspace, &query, &args, format, config)?;
+        }
+        Some(Commands::Search { query, args }) => {
+            commands::search::run(&workspace, &query, &args, format, config)?;
         }
-        Some(Commands::Index { path, rebuild, semantic, text }) => {
+        Some(Commands::Index { path, rebuild, semantic, text, dry_run, local, progress, no_ignore }) => {
             let target = path.unwrap_or(workspace);
-            commands::index::run(&target, rebuild, semantic, text)?;
+            if dry_run {
+                commands::index::dry_run(&target, no_ignore, config)?;
+            } else {
+                commands::index::run(&target, rebuild, semantic, text, local, progress, no_ignore, config)?;
+            }
         }
         Some(Commands::Status { detailed }) => {
-            commands::status::run(&workspace, detailed)?;
+            commands::status::run(&workspace, detailed, config)?;
+        }
+        Some(Commands::Stats) => {
+            commands::status::run(&workspace, true, config)?;
         }
-        Some(Commands::Watch { path }) => {
+        Some(Commands::List) => {
+            commands::list::run(&workspace, format, config)?;
+        }
+        Some(Commands::Warmup) => {
+            commands::warmup::run(&workspace, config)?;
+        }
+        Some(Commands::Watch { path, mode, ignore }) => {
             let target = path.unwrap_or(workspace);
-            commands::watch::run(&target)?;
+            commands::watch::run(&target, mode, ignore, cli.verbose, config)?;
         }
-        Some(Commands::Install(target)) => {
-            match target {
-                InstallTarget::ClaudeCode => commands::install::install_claude_code()?,
-                InstallTarget::Opencode => commands::install::install_opencode()?,
-                InstallTarget::Codex => commands::install::install_codex()?,
-                InstallTarget::Droid => commands::install::install_droid()?,
+        Some(Commands::Install { target, verify }) => {
+            let (name, files) = match target {
+                InstallTarget::ClaudeCode => ("claude-code", commands::install::install_claude_code()?),
+                InstallTarget::Opencode => ("opencode", commands::install::install_opencode()?),
+                InstallTarget::Codex => ("codex", commands::install::install_codex()?),
+                InstallTarget::Droid => ("droid", commands::install::install_droid()?),
+            };
+            if verify {
+                commands::install::verify(name, &files)?;
             }
         }
         Some(Commands::Uninstall(target)) => {
@@ -257,15 +517,26 @@ fn main() -> Result<()> {
         }
         Some(Commands::Indexes(cmd)) => {
             match cmd {
-                IndexesCommand::List => commands::indexes::list()?,
-                IndexesCommand::Clean => commands::indexes::clean()?,
+                IndexesCommand::List => commands::indexes::list(format)?,
+                IndexesCommand::Clean { dry_run } => commands::indexes::clean(dry_run)?,
                 IndexesCommand::Remove { identifier } => commands::indexes::remove(&identifier)?,
+                IndexesCommand::Info { identifier } => commands::indexes::info(&identifier, format)?,
+            }
+        }
+        Some(Commands::Config(cmd)) => {
+            match cmd {
+                ConfigCommand::Show => commands::config::show(format)?,
+                ConfigCommand::Path => commands::config::path(format)?,
             }
         }
         None => {
             // Default: treat as search if query provided
             if let Some(query) = cli.query {
-                commands::search::run(&workspace, &query, cli.limit, cli.extensions, cli.paths, cli.regex, false, cli.text_only, format)?;
+                if cli.workspace.len() > 1 {
+                    commands::search::run_multi(&cli.workspace, &query, &cli.search, format, config)?;
+                } else {
+                    commands::search::run(&workspace, &query, &cli.search, format, config)?;
+                }
             } else {
                 // No query, show help
                 use clap::CommandFactory;
@@ -1,8 +1,19 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Data dir used by `ygrep index --local`, inside the workspace
+/// (hidden, so the walker and watcher skip it)
+pub const LOCAL_DATA_DIR: &str = ".ygrep-index";
+
+/// Candidates re-ranked by `--rerank` when `search.rerank_top_n` isn't set
+pub const DEFAULT_RERANK_TOP_N: usize = 50;
+
+/// Largest `indexer.max_token_len` accepted: Tantivy rejects terms over 65530 bytes,
+/// and a character takes up to 4
+const MAX_TOKEN_LEN_LIMIT: usize = 16_382;
 
 /// Global ygrep configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
     /// Daemon configuration
@@ -34,34 +45,81 @@ pub struct DaemonConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct IndexerConfig {
-    /// Base directory for all index data
+    /// Base directory for all index data (relative paths are resolved against the workspace root)
     pub data_dir: PathBuf,
 
+    /// More directories to index into the same workspace, e.g. repos kept elsewhere on disk
+    /// (relative paths are resolved against the workspace root)
+    pub extra_roots: Vec<PathBuf>,
+
     /// Maximum file size to index (bytes)
     pub max_file_size: u64,
 
+    /// Longest token indexed, in characters; longer ones are truncated to this length
+    /// Raising it matches long identifiers and minified code more precisely but grows the index;
+    /// changing it requires `ygrep index --rebuild`
+    pub max_token_len: usize,
+
+    /// Maximum number of lines per file to index (None = no limit)
+    /// Useful for skipping large generated files that slip under `max_file_size`
+    pub max_lines_per_file: Option<usize>,
+
     /// File extensions to include (empty = all text files)
+    /// Mutually exclusive with `exclude_extensions`
     pub include_extensions: Vec<String>,
 
+    /// File extensions to skip, e.g. `["min.js", "bundle.js"]` (matched against the end of the file name)
+    /// Mutually exclusive with `include_extensions`
+    pub exclude_extensions: Vec<String>,
+
     /// Additional ignore patterns (glob syntax)
     pub ignore_patterns: Vec<String>,
 
+    /// Directory names skipped wherever they appear, before .gitignore and `ignore_patterns`
+    /// are checked (e.g. `node_modules`, `build`); remove a name to index such directories
+    pub skip_dirs: Vec<String>,
+
     /// Follow symlinks
     pub follow_symlinks: bool,
 
     /// Respect .gitignore files (default: false for code search)
     pub respect_gitignore: bool,
 
+    /// Ignore nothing: no `ignore_patterns`, no .gitignore and no `skip_dirs`, which can mean
+    /// indexing huge trees
+    /// (hidden files are still skipped)
+    pub no_ignore: bool,
+
     /// Enable content deduplication
     pub deduplicate: bool,
 
+    /// Index files with identical content only once, under the shortest path
+    /// (the other copies don't appear in search results)
+    pub content_hash_dedup: bool,
+
+    /// Also index overlapping `chunk_size`-line chunks of longer files, for finer-grained
+    /// semantic matches; turning it off stores only whole files, which shrinks the index
+    /// (takes effect on files indexed afterwards, so run `ygrep index --rebuild`)
+    pub index_chunks: bool,
+
     /// Chunk size for semantic indexing (lines)
     pub chunk_size: usize,
 
     /// Chunk overlap (lines)
     pub chunk_overlap: usize,
 
-    /// Number of indexing threads
+    /// Embedding model for semantic indexing (None = sentence-transformers/all-MiniLM-L6-v2)
+    /// Existing indexes keep the model they were built with until rebuilt
+    pub embedding_model: Option<String>,
+
+    /// Directory for downloaded model weights (None = fastembed default, `FASTEMBED_CACHE_DIR` or `.fastembed_cache`)
+    pub model_cache_dir: Option<PathBuf>,
+
+    /// Never download model weights; fail if they aren't already in the cache directory
+    pub offline: bool,
+
+    /// Number of indexing threads (Tantivy uses at most 3; `--semantic` embeds up to this
+    /// many batches at once, at most 2, since each batch already uses every CPU)
     pub threads: usize,
 }
 
@@ -74,20 +132,62 @@ pub struct SearchConfig {
     /// Vector weight in hybrid search (0.0-1.0)
     pub vector_weight: f32,
 
-    /// Default result limit
+    /// Number of results when a search doesn't ask for a limit
     pub default_limit: usize,
 
-    /// Maximum results
+    /// Most results a search returns, even when it asks for more (`ygrep -n` raises it for that search)
     pub max_limit: usize,
 
     /// Minimum score threshold (0.0-1.0)
     pub min_score: f32,
 
-    /// Enable fuzzy matching for BM25
+    /// Enable fuzzy matching for BM25 (query words match within `fuzzy_distance` edits,
+    /// instead of the literal query text)
     pub fuzzy_enabled: bool,
 
     /// Fuzzy distance (1-2)
     pub fuzzy_distance: u8,
+
+    /// Whether multi-word queries pre-fetch documents with any or all of the words
+    pub multi_term_mode: MultiTermMode,
+
+    /// Maximum hits from a single file (None = no per-file limit)
+    pub max_results_per_file: Option<usize>,
+
+    /// Let regex patterns span lines (`.` matches newlines, `^`/`$` match at line boundaries)
+    pub multiline: bool,
+
+    /// Show the lines of matching documents that don't match the query (`--invert-match`)
+    pub invert_match: bool,
+
+    /// Only match lines that look like definitions (`--defs`), e.g. `fn parse_config`
+    /// rather than every call of `parse_config`
+    pub definitions_only: bool,
+
+    /// Stop collecting hits after this many milliseconds and return the partial results
+    /// (None = no limit)
+    pub timeout_ms: Option<u64>,
+
+    /// Fail with a timeout error instead of returning partial results
+    pub strict_timeout: bool,
+
+    /// Re-rank this many hybrid search candidates with a cross-encoder (0 = disabled)
+    pub rerank_top_n: usize,
+
+    /// Let the words of a multi-word query match with up to this many tokens between them
+    /// instead of as the exact text (0 = exact)
+    pub phrase_slop: u32,
+}
+
+/// How the words of a multi-word query are combined when fetching candidates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiTermMode {
+    /// Documents containing any of the words (OR)
+    #[default]
+    Any,
+    /// Only documents containing every word (AND)
+    All,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,22 +202,18 @@ pub struct OutputConfig {
     /// Context lines around matches
     pub context_lines: usize,
 
-    /// Maximum output lines per result
+    /// Maximum snippet lines shown per result (the AI format shows at most one; 0 hides snippets)
     pub max_lines_per_result: usize,
 
+    /// Longest snippet line shown, in characters, before it's cut off with `...`
+    /// (unset uses each format's own width: 100 for AI output, 80 for pretty)
+    pub max_snippet_chars: Option<usize>,
+
     /// Show scores in output
     pub show_scores: bool,
-}
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            daemon: DaemonConfig::default(),
-            indexer: IndexerConfig::default(),
-            search: SearchConfig::default(),
-            output: OutputConfig::default(),
-        }
-    }
+    /// Highlight query matches in snippets with ANSI colors (only when writing to a terminal)
+    pub highlight: bool,
 }
 
 impl Default for DaemonConfig {
@@ -134,8 +230,12 @@ impl Default for IndexerConfig {
     fn default() -> Self {
         Self {
             data_dir: default_data_dir(),
+            extra_roots: vec![],
             max_file_size: 10 * 1024 * 1024, // 10MB
+            max_token_len: crate::index::DEFAULT_MAX_TOKEN_CHARS,
+            max_lines_per_file: None,
             include_extensions: vec![],
+            exclude_extensions: vec![],
             ignore_patterns: vec![
                 // Package managers & dependencies
                 "**/node_modules/**".into(),
@@ -269,11 +369,35 @@ impl Default for IndexerConfig {
                 // Source maps
                 "**/*.map".into(),
             ],
+            skip_dirs: vec![
+                "cache".into(),
+                "node_modules".into(),
+                "vendor".into(),
+                "target".into(),
+                "dist".into(),
+                "build".into(),
+                "logs".into(),
+                "log".into(),
+                "tmp".into(),
+                "temp".into(),
+                "var".into(),
+                "__pycache__".into(),
+                ".git".into(),
+                ".svn".into(),
+                "coverage".into(),
+                "htmlcov".into(),
+            ],
             follow_symlinks: true,
             respect_gitignore: false,
+            no_ignore: false,
             deduplicate: true,
+            content_hash_dedup: false,
+            index_chunks: true,
             chunk_size: 50,
             chunk_overlap: 10,
+            embedding_model: None,
+            model_cache_dir: None,
+            offline: false,
             threads: std::thread::available_parallelism()
                 .map(|n| n.get().min(4))
                 .unwrap_or(2),
@@ -289,20 +413,39 @@ impl Default for SearchConfig {
             default_limit: 10,
             max_limit: 100,
             min_score: 0.1,
-            fuzzy_enabled: true,
+            fuzzy_enabled: false,
             fuzzy_distance: 1,
+            multi_term_mode: MultiTermMode::Any,
+            max_results_per_file: None,
+            multiline: false,
+            invert_match: false,
+            definitions_only: false,
+            timeout_ms: None,
+            strict_timeout: false,
+            rerank_top_n: 0,
+            phrase_slop: 0,
         }
     }
 }
 
+impl SearchConfig {
+    /// Number of results to return for a requested `limit`: `default_limit` when there is
+    /// none, capped at `max_limit`
+    pub fn effective_limit(&self, limit: Option<usize>) -> usize {
+        limit.unwrap_or(self.default_limit).min(self.max_limit)
+    }
+}
+
 impl Default for OutputConfig {
     fn default() -> Self {
         Self {
             ai_mode: true,
             show_content: true,
             context_lines: 2,
-            max_lines_per_result: 10,
+            max_lines_per_result: 3,
+            max_snippet_chars: None,
             show_scores: false,
+            highlight: false,
         }
     }
 }
@@ -319,35 +462,101 @@ impl Config {
     /// 2. $XDG_CONFIG_HOME/ygrep/config.toml
     /// 3. ~/.config/ygrep/config.toml
     /// 4. Built-in defaults
+    ///
+    /// Files that fail to parse are skipped; see `load_strict` to report them.
     pub fn load() -> Self {
-        // Try project-level config
-        if let Ok(content) = std::fs::read_to_string(".ygrep.toml") {
-            if let Ok(config) = toml::from_str(&content) {
-                return config;
-            }
-        }
+        Self::load_with_source().0
+    }
 
-        // Try user-level config
-        if let Some(config_dir) = dirs::config_dir() {
-            let config_path = config_dir.join("ygrep").join("config.toml");
-            if let Ok(content) = std::fs::read_to_string(&config_path) {
+    /// Load config like `load`, also returning the file it came from (None = built-in defaults)
+    pub fn load_with_source() -> (Self, Option<PathBuf>) {
+        for path in Self::search_paths() {
+            if let Ok(content) = std::fs::read_to_string(&path) {
                 if let Ok(config) = toml::from_str(&content) {
-                    return config;
+                    return (config, Some(path));
                 }
             }
         }
 
         // Fall back to defaults
-        Self::default()
+        (Self::default(), None)
+    }
+
+    /// Config files checked by `load`, in order of precedence (project, then user)
+    ///
+    /// Only the first usable file is read; settings are not merged across files.
+    pub fn search_paths() -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(".ygrep.toml")];
+        if let Some(config_dir) = dirs::config_dir() {
+            paths.push(config_dir.join("ygrep").join("config.toml"));
+        }
+        paths
+    }
+
+    /// Load config like `load`, but fail when the first config file found
+    /// can't be parsed or has invalid values instead of using the defaults
+    pub fn load_strict() -> Result<Self, ConfigError> {
+        match Self::search_paths().into_iter().find(|path| path.is_file()) {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
     }
 
     /// Load config from a specific file
-    pub fn load_from(path: &std::path::Path) -> Result<Self, ConfigError> {
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
         let content = std::fs::read_to_string(path)?;
-        let config = toml::from_str(&content)?;
+        let config: Self = toml::from_str(&content).map_err(|source| ConfigError::ParseFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        config.validate().map_err(|e| match e {
+            ConfigError::Invalid(msg) => ConfigError::Invalid(format!("{} (in {})", msg, path.display())),
+            e => e,
+        })?;
         Ok(config)
     }
 
+    /// Check for out-of-range values and settings that can't be combined
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !self.indexer.include_extensions.is_empty() && !self.indexer.exclude_extensions.is_empty() {
+            return Err(ConfigError::Invalid(
+                "include_extensions and exclude_extensions can't both be set".to_string(),
+            ));
+        }
+
+        let unit_ranges = [
+            ("search.bm25_weight", self.search.bm25_weight),
+            ("search.vector_weight", self.search.vector_weight),
+            ("search.min_score", self.search.min_score),
+        ];
+        for (name, value) in unit_ranges {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(ConfigError::Invalid(format!(
+                    "{} must be between 0.0 and 1.0, got {}", name, value
+                )));
+            }
+        }
+
+        if !(1..=MAX_TOKEN_LEN_LIMIT).contains(&self.indexer.max_token_len) {
+            return Err(ConfigError::Invalid(format!(
+                "indexer.max_token_len must be between 1 and {}, got {}", MAX_TOKEN_LEN_LIMIT, self.indexer.max_token_len
+            )));
+        }
+
+        if !(1..=2).contains(&self.search.fuzzy_distance) {
+            return Err(ConfigError::Invalid(format!(
+                "search.fuzzy_distance must be 1 or 2, got {}", self.search.fuzzy_distance
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Serialize the config in the same TOML format it is loaded from
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
     /// Get the socket path, using default if not specified
     pub fn socket_path(&self) -> PathBuf {
         self.daemon.socket_path.clone().unwrap_or_else(default_socket_path)
@@ -371,4 +580,71 @@ pub enum ConfigError {
 
     #[error("Failed to parse config: {0}")]
     Parse(#[from] toml::de::Error),
+
+    #[error("Failed to parse config file {}: {source}", path.display())]
+    ParseFile {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("Invalid config: {0}")]
+    Invalid(String),
+
+    #[error("Failed to serialize config: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_extension_filters() {
+        let mut config = Config::default();
+        config.indexer.exclude_extensions = vec!["min.js".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.indexer.include_extensions = vec!["js".to_string()];
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_validate_ranges() {
+        let mut config = Config::default();
+        config.search.bm25_weight = 1.5;
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("bm25_weight")));
+
+        let mut config = Config::default();
+        config.search.fuzzy_distance = 3;
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("fuzzy_distance")));
+
+        let mut config = Config::default();
+        config.indexer.max_token_len = 0;
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("max_token_len")));
+    }
+
+    #[test]
+    fn test_to_toml_round_trip() {
+        let mut config = Config::default();
+        config.search.multi_term_mode = MultiTermMode::All;
+        config.indexer.ignore_patterns = vec!["**/fixtures/**".to_string()];
+
+        let parsed: Config = toml::from_str(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(parsed.search.multi_term_mode, MultiTermMode::All);
+        assert_eq!(parsed.indexer.ignore_patterns, config.indexer.ignore_patterns);
+    }
+
+    #[test]
+    fn test_load_from_reports_parse_errors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(".ygrep.toml");
+        std::fs::write(&path, "[search]\nbm25_weight = \"high\"\n").unwrap();
+
+        let err = Config::load_from(&path).unwrap_err();
+        assert!(matches!(err, ConfigError::ParseFile { .. }));
+        // The message names the file and the offending line
+        let msg = err.to_string();
+        assert!(msg.contains(".ygrep.toml"));
+        assert!(msg.contains("line 2"));
+    }
 }
@@ -1,13 +1,20 @@
 //! LRU cache for embeddings to avoid re-computation
 
-use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use lru::LruCache;
 use parking_lot::Mutex;
 use xxhash_rust::xxh3::xxh3_64;
 
-/// LRU cache for computed embeddings
+use crate::error::Result;
+
+/// LRU cache for computed embeddings, bounded by the bytes of the stored vectors
+///
+/// Entries may have different dimensions; least recently used entries are
+/// evicted until the stored vectors fit in the byte budget again.
 pub struct EmbeddingCache {
     cache: Mutex<LruCache<u64, Vec<f32>>>,
+    capacity_bytes: usize,
+    stored_bytes: AtomicUsize,
     hits: std::sync::atomic::AtomicU64,
     misses: std::sync::atomic::AtomicU64,
 }
@@ -17,16 +24,19 @@ impl EmbeddingCache {
     ///
     /// # Arguments
     /// * `capacity_mb` - Maximum cache size in megabytes
-    /// * `dimension` - Embedding dimension (to calculate entry size)
+    /// * `dimension` - Embedding dimension (the cache always fits at least 100 embeddings of it)
     pub fn new(capacity_mb: usize, dimension: usize) -> Self {
-        // Calculate number of embeddings that fit in cache
         // Each embedding is dimension * 4 bytes (f32)
         let embedding_size = dimension * std::mem::size_of::<f32>();
-        let capacity = (capacity_mb * 1024 * 1024) / embedding_size;
-        let capacity = NonZeroUsize::new(capacity.max(100)).unwrap();
+        Self::new_with_bytes((capacity_mb * 1024 * 1024).max(100 * embedding_size))
+    }
 
+    /// Create a new embedding cache holding at most `capacity_bytes` of embedding data
+    pub fn new_with_bytes(capacity_bytes: usize) -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(capacity)),
+            cache: Mutex::new(LruCache::unbounded()),
+            capacity_bytes,
+            stored_bytes: AtomicUsize::new(0),
             hits: std::sync::atomic::AtomicU64::new(0),
             misses: std::sync::atomic::AtomicU64::new(0),
         }
@@ -50,7 +60,19 @@ impl EmbeddingCache {
     pub fn insert(&self, text: &str, embedding: Vec<f32>) {
         let key = xxh3_64(text.as_bytes());
         let mut cache = self.cache.lock();
-        cache.put(key, embedding);
+
+        self.stored_bytes.fetch_add(embedding_bytes(&embedding), Ordering::Relaxed);
+        if let Some(replaced) = cache.put(key, embedding) {
+            self.stored_bytes.fetch_sub(embedding_bytes(&replaced), Ordering::Relaxed);
+        }
+
+        // Evict least recently used entries until back under budget
+        while self.stored_bytes.load(Ordering::Relaxed) > self.capacity_bytes {
+            let Some((_, evicted)) = cache.pop_lru() else {
+                break;
+            };
+            self.stored_bytes.fetch_sub(embedding_bytes(&evicted), Ordering::Relaxed);
+        }
     }
 
     /// Get or compute an embedding
@@ -69,6 +91,22 @@ impl EmbeddingCache {
         embedding
     }
 
+    /// Get or compute an embedding with a fallible function
+    ///
+    /// Failures are returned as-is and nothing is cached for the text
+    pub fn try_get_or_insert<F>(&self, text: &str, compute: F) -> Result<Vec<f32>>
+    where
+        F: FnOnce() -> Result<Vec<f32>>,
+    {
+        if let Some(embedding) = self.get(text) {
+            return Ok(embedding);
+        }
+
+        let embedding = compute()?;
+        self.insert(text, embedding.clone());
+        Ok(embedding)
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> CacheStats {
         let hits = self.hits.load(std::sync::atomic::Ordering::Relaxed);
@@ -84,15 +122,24 @@ impl EmbeddingCache {
                 0.0
             },
             size: self.cache.lock().len(),
+            stored_bytes: self.stored_bytes.load(Ordering::Relaxed),
+            capacity_bytes: self.capacity_bytes,
         }
     }
 
     /// Clear the cache
     pub fn clear(&self) {
-        self.cache.lock().clear();
+        let mut cache = self.cache.lock();
+        cache.clear();
+        self.stored_bytes.store(0, Ordering::Relaxed);
     }
 }
 
+/// Bytes of embedding data held by one cache entry
+fn embedding_bytes(embedding: &[f32]) -> usize {
+    std::mem::size_of_val(embedding)
+}
+
 /// Cache statistics
 #[derive(Debug, Clone)]
 pub struct CacheStats {
@@ -100,6 +147,10 @@ pub struct CacheStats {
     pub misses: u64,
     pub hit_rate: f64,
     pub size: usize,
+    /// Bytes of embedding data currently cached
+    pub stored_bytes: usize,
+    /// Byte budget the cache evicts down to
+    pub capacity_bytes: usize,
 }
 
 #[cfg(test)]
@@ -128,6 +179,29 @@ mod tests {
         assert_eq!(stats.hits, 1);
         assert_eq!(stats.misses, 1);
         assert_eq!(stats.size, 1);
+        assert_eq!(stats.stored_bytes, 384 * 4);
+    }
+
+    #[test]
+    fn test_evicts_by_bytes() {
+        // Room for two 384-dim embeddings, or one 768-dim embedding
+        let cache = EmbeddingCache::new_with_bytes(384 * 4 * 2);
+
+        cache.insert("a", vec![0.1f32; 384]);
+        cache.insert("b", vec![0.2f32; 384]);
+        assert_eq!(cache.stats().size, 2);
+
+        // A larger embedding evicts both least recently used entries
+        cache.insert("c", vec![0.3f32; 768]);
+        let stats = cache.stats();
+        assert_eq!(stats.size, 1);
+        assert_eq!(stats.stored_bytes, 768 * 4);
+        assert_eq!(stats.capacity_bytes, 384 * 4 * 2);
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("c").is_some());
+
+        cache.clear();
+        assert_eq!(cache.stats().stored_bytes, 0);
     }
 
     #[test]
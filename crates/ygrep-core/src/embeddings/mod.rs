@@ -1,5 +1,7 @@
 mod model;
 mod cache;
+mod reranker;
 
 pub use model::{EmbeddingModel, ModelType};
 pub use cache::EmbeddingCache;
+pub use reranker::CrossEncoderModel;
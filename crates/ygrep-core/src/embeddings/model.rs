@@ -2,19 +2,24 @@
 //!
 //! Provides lazy-loaded embedding generation using local models.
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use parking_lot::RwLock;
 use fastembed::{TextEmbedding, InitOptions, EmbeddingModel as FastEmbedModel};
 
-use crate::error::{Result, YgrepError};
+use crate::config::IndexerConfig;
+use crate::error::{Result, ResultExt, YgrepError};
 
 /// Supported embedding models
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum ModelType {
     /// BGE Small - Fast, ~50MB, 384 dimensions
     BgeSmall,
     /// All-MiniLM-L6 - Very fast, ~25MB, 384 dimensions
+    #[default]
     AllMiniLmL6,
+    /// BGE Base - Higher quality, ~440MB, 768 dimensions
+    BgeBaseEnV15,
 }
 
 impl ModelType {
@@ -22,6 +27,7 @@ impl ModelType {
         match self {
             ModelType::BgeSmall => 384,
             ModelType::AllMiniLmL6 => 384,
+            ModelType::BgeBaseEnV15 => 768,
         }
     }
 
@@ -29,27 +35,43 @@ impl ModelType {
         match self {
             ModelType::BgeSmall => "BAAI/bge-small-en-v1.5",
             ModelType::AllMiniLmL6 => "sentence-transformers/all-MiniLM-L6-v2",
+            ModelType::BgeBaseEnV15 => "BAAI/bge-base-en-v1.5",
         }
     }
 
-    fn to_fastembed(&self) -> FastEmbedModel {
+    /// Model selected by `indexer.embedding_model` in the config (default if unset)
+    pub fn from_config(config: &IndexerConfig) -> Result<Self> {
+        match config.embedding_model.as_deref() {
+            Some(name) => Self::from_name(name)
+                .ok_or_else(|| YgrepError::Config(format!("Unknown embedding model: {}", name).into())),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Look up a model by its name (as stored in workspace.json)
+    pub fn from_name(name: &str) -> Option<Self> {
+        [ModelType::BgeSmall, ModelType::AllMiniLmL6, ModelType::BgeBaseEnV15]
+            .into_iter()
+            .find(|model| model.name() == name)
+    }
+
+    fn to_fastembed(self) -> FastEmbedModel {
         match self {
             ModelType::BgeSmall => FastEmbedModel::BGESmallENV15,
             ModelType::AllMiniLmL6 => FastEmbedModel::AllMiniLML6V2,
+            ModelType::BgeBaseEnV15 => FastEmbedModel::BGEBaseENV15,
         }
     }
 }
 
-impl Default for ModelType {
-    fn default() -> Self {
-        ModelType::AllMiniLmL6
-    }
-}
-
 /// Lazy-loaded embedding model
 pub struct EmbeddingModel {
     model_type: ModelType,
     model: RwLock<Option<Arc<TextEmbedding>>>,
+    /// Where model weights are downloaded to / loaded from
+    cache_dir: PathBuf,
+    /// Fail instead of downloading missing weights
+    offline: bool,
 }
 
 impl EmbeddingModel {
@@ -58,7 +80,19 @@ impl EmbeddingModel {
         Self {
             model_type,
             model: RwLock::new(None),
+            cache_dir: PathBuf::from(fastembed::get_cache_dir()),
+            offline: false,
+        }
+    }
+
+    /// Create a new embedding model using the cache directory and offline setting from config
+    pub fn with_config(model_type: ModelType, config: &IndexerConfig) -> Self {
+        let mut model = Self::new(model_type);
+        if let Some(cache_dir) = &config.model_cache_dir {
+            model.cache_dir = cache_dir.clone();
         }
+        model.offline = config.offline;
+        model
     }
 
     /// Get the embedding dimension
@@ -71,6 +105,11 @@ impl EmbeddingModel {
         self.model_type.name()
     }
 
+    /// Get the model type
+    pub fn model_type(&self) -> ModelType {
+        self.model_type
+    }
+
     /// Load the model if not already loaded
     fn ensure_loaded(&self) -> Result<Arc<TextEmbedding>> {
         // Fast path: model already loaded
@@ -89,12 +128,25 @@ impl EmbeddingModel {
             return Ok(Arc::clone(model));
         }
 
+        if self.cache_dir.exists() && !self.cache_dir.is_dir() {
+            return Err(YgrepError::Config(format!(
+                "Model cache directory is not a directory: {}", self.cache_dir.display()
+            ).into()));
+        }
+        if self.offline && !self.is_cached() {
+            return Err(YgrepError::Config(format!(
+                "Semantic model {} not found in {} (offline mode is enabled, so it won't be downloaded)",
+                self.name(), self.cache_dir.display()
+            ).into()));
+        }
+
         eprint!("  Loading semantic model...");
 
         let model = TextEmbedding::try_new(
             InitOptions::new(self.model_type.to_fastembed())
-                .with_show_download_progress(true)
-        ).map_err(|e| YgrepError::Config(format!("Failed to load semantic model: {}", e)))?;
+                .with_cache_dir(self.cache_dir.clone())
+                .with_show_download_progress(!self.offline)
+        ).map_err(YgrepError::from).context("Failed to load semantic model")?;
 
         let model = Arc::new(model);
         *guard = Some(Arc::clone(&model));
@@ -104,14 +156,26 @@ impl EmbeddingModel {
         Ok(model)
     }
 
+    /// Check whether the model weights are already in the cache directory
+    /// (fastembed uses the Hugging Face hub layout: `models--{org}--{name}/snapshots/{revision}`)
+    fn is_cached(&self) -> bool {
+        let fastembed_model = self.model_type.to_fastembed();
+        let Ok(info) = TextEmbedding::get_model_info(&fastembed_model) else {
+            return false;
+        };
+        let snapshots = self.cache_dir
+            .join(format!("models--{}", info.model_code.replace('/', "--")))
+            .join("snapshots");
+        has_entries(&snapshots)
+    }
+
     /// Generate embedding for a single text
     pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
         let model = self.ensure_loaded()?;
-        let embeddings = model.embed(vec![text], None)
-            .map_err(|e| YgrepError::Config(format!("Embedding failed: {}", e)))?;
+        let embeddings = model.embed(vec![text], None)?;
 
         embeddings.into_iter().next()
-            .ok_or_else(|| YgrepError::Config("No embedding returned".to_string()))
+            .ok_or_else(|| YgrepError::Config("No embedding returned".into()))
     }
 
     /// Generate embeddings for multiple texts (batched)
@@ -120,8 +184,7 @@ impl EmbeddingModel {
             return Ok(vec![]);
         }
         let model = self.ensure_loaded()?;
-        model.embed(texts.to_vec(), None)
-            .map_err(|e| YgrepError::Config(format!("Batch embedding failed: {}", e)))
+        Ok(model.embed(texts.to_vec(), None)?)
     }
 
     /// Check if the model is loaded
@@ -136,6 +199,11 @@ impl EmbeddingModel {
     }
 }
 
+/// Check that a directory exists and isn't empty
+fn has_entries(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some())
+}
+
 impl Default for EmbeddingModel {
     fn default() -> Self {
         Self::new(ModelType::default())
@@ -150,6 +218,39 @@ mod tests {
     fn test_model_dimensions() {
         assert_eq!(ModelType::BgeSmall.dimension(), 384);
         assert_eq!(ModelType::AllMiniLmL6.dimension(), 384);
+        assert_eq!(ModelType::BgeBaseEnV15.dimension(), 768);
+    }
+
+    #[test]
+    fn test_model_from_name() {
+        assert_eq!(ModelType::from_name("BAAI/bge-base-en-v1.5"), Some(ModelType::BgeBaseEnV15));
+        assert_eq!(ModelType::from_name(ModelType::AllMiniLmL6.name()), Some(ModelType::AllMiniLmL6));
+        assert_eq!(ModelType::from_name("unknown/model"), None);
+
+        let mut config = IndexerConfig::default();
+        assert_eq!(ModelType::from_config(&config).unwrap(), ModelType::AllMiniLmL6);
+        config.embedding_model = Some("unknown/model".to_string());
+        assert!(ModelType::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_invalid_cache_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let not_a_dir = temp_dir.path().join("weights");
+        std::fs::write(&not_a_dir, "").unwrap();
+
+        let config = IndexerConfig { model_cache_dir: Some(not_a_dir), ..IndexerConfig::default() };
+        let model = EmbeddingModel::with_config(ModelType::AllMiniLmL6, &config);
+        assert!(matches!(model.preload(), Err(YgrepError::Config(_))));
+
+        // Offline with an empty cache fails instead of downloading
+        let config = IndexerConfig {
+            model_cache_dir: Some(temp_dir.path().to_path_buf()),
+            offline: true,
+            ..IndexerConfig::default()
+        };
+        let model = EmbeddingModel::with_config(ModelType::AllMiniLmL6, &config);
+        assert!(matches!(model.preload(), Err(YgrepError::Config(_))));
     }
 
     // Note: Full embedding tests require model download
@@ -0,0 +1,129 @@
+//! Cross-encoder re-ranking using fastembed
+//!
+//! Scores (query, passage) pairs jointly, which is slower than comparing embeddings
+//! but more precise, so it's only applied to the top candidates of a hybrid search.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use parking_lot::RwLock;
+use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+
+use crate::config::IndexerConfig;
+use crate::error::{Result, ResultExt, YgrepError};
+
+/// Model used for re-ranking
+const RERANKER_MODEL: RerankerModel = RerankerModel::BGERerankerBase;
+
+/// Lazy-loaded cross-encoder model
+pub struct CrossEncoderModel {
+    model: RwLock<Option<Arc<TextRerank>>>,
+    /// Where model weights are downloaded to / loaded from
+    cache_dir: PathBuf,
+    /// Fail instead of downloading missing weights
+    offline: bool,
+}
+
+impl CrossEncoderModel {
+    /// Create a new cross-encoder (lazy-loaded)
+    pub fn new() -> Self {
+        Self {
+            model: RwLock::new(None),
+            cache_dir: PathBuf::from(fastembed::get_cache_dir()),
+            offline: false,
+        }
+    }
+
+    /// Create a new cross-encoder using the cache directory and offline setting from config
+    pub fn with_config(config: &IndexerConfig) -> Self {
+        let mut model = Self::new();
+        if let Some(cache_dir) = &config.model_cache_dir {
+            model.cache_dir = cache_dir.clone();
+        }
+        model.offline = config.offline;
+        model
+    }
+
+    /// Get the model name
+    pub fn name(&self) -> String {
+        TextRerank::get_model_info(&RERANKER_MODEL).model_code
+    }
+
+    /// Load the model if not already loaded
+    fn ensure_loaded(&self) -> Result<Arc<TextRerank>> {
+        if let Some(ref model) = *self.model.read() {
+            return Ok(Arc::clone(model));
+        }
+
+        let mut guard = self.model.write();
+        if let Some(ref model) = *guard {
+            return Ok(Arc::clone(model));
+        }
+
+        if self.offline && !self.is_cached() {
+            return Err(YgrepError::Config(format!(
+                "Re-ranking model {} not found in {} (offline mode is enabled, so it won't be downloaded)",
+                self.name(), self.cache_dir.display()
+            ).into()));
+        }
+
+        let model = TextRerank::try_new(
+            RerankInitOptions::new(RERANKER_MODEL)
+                .with_cache_dir(self.cache_dir.clone())
+                .with_show_download_progress(!self.offline)
+        ).map_err(YgrepError::from).context("Failed to load re-ranking model")?;
+
+        let model = Arc::new(model);
+        *guard = Some(Arc::clone(&model));
+        Ok(model)
+    }
+
+    /// Check whether the model weights are already in the cache directory
+    fn is_cached(&self) -> bool {
+        let snapshots = self.cache_dir
+            .join(format!("models--{}", self.name().replace('/', "--")))
+            .join("snapshots");
+        std::fs::read_dir(snapshots).is_ok_and(|mut entries| entries.next().is_some())
+    }
+
+    /// Score each passage against the query (higher is more relevant), in input order
+    pub fn score_pairs(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        if passages.is_empty() {
+            return Ok(vec![]);
+        }
+        let model = self.ensure_loaded()?;
+        let ranked = model.rerank(query, passages.to_vec(), false, None)?;
+
+        // fastembed returns results sorted by score; put them back in input order
+        let mut scores = vec![f32::MIN; passages.len()];
+        for result in ranked {
+            scores[result.index] = result.score;
+        }
+        Ok(scores)
+    }
+}
+
+impl Default for CrossEncoderModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_without_weights() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = IndexerConfig {
+            model_cache_dir: Some(temp_dir.path().to_path_buf()),
+            offline: true,
+            ..IndexerConfig::default()
+        };
+        let model = CrossEncoderModel::with_config(&config);
+
+        // No model needed for nothing to score
+        assert!(model.score_pairs("query", &[]).unwrap().is_empty());
+        assert!(matches!(model.score_pairs("query", &["passage"]), Err(YgrepError::Config(_))));
+    }
+}
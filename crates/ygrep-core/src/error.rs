@@ -1,6 +1,13 @@
 use std::path::PathBuf;
 use thiserror::Error;
 
+/// Boxed underlying error, kept as the `source()` of wrapping variants
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors from ygrep
+///
+/// Variants wrapping a `#[source]` only describe what failed, so reporters that walk
+/// `source()` (like anyhow) don't print the cause twice; `chain` gives the full message.
 #[derive(Debug, Error)]
 pub enum YgrepError {
     #[error("IO error: {0}")]
@@ -12,8 +19,8 @@ pub enum YgrepError {
     #[error("Query parse error: {0}")]
     QueryParse(#[from] tantivy::query::QueryParserError),
 
-    #[error("Configuration error: {0}")]
-    Config(String),
+    #[error("Configuration error")]
+    Config(#[source] BoxError),
 
     #[error("Workspace not found: {0}")]
     WorkspaceNotFound(PathBuf),
@@ -21,6 +28,12 @@ pub enum YgrepError {
     #[error("Workspace not indexed: {0}")]
     WorkspaceNotIndexed(PathBuf),
 
+    #[error("Index for {path} was built by an incompatible ygrep version ({version}), please rebuild with `ygrep index --rebuild`")]
+    IncompatibleIndex { path: PathBuf, version: String },
+
+    #[error("Semantic index for {path} has {index}-dimension embeddings but the model produces {model}, please rebuild with `ygrep index --rebuild --semantic`")]
+    EmbeddingDimensionMismatch { path: PathBuf, index: usize, model: usize },
+
     #[error("Invalid path: {0}")]
     InvalidPath(PathBuf),
 
@@ -30,11 +43,11 @@ pub enum YgrepError {
     #[error("Circular symlink detected: {0}")]
     CircularSymlink(PathBuf),
 
-    #[error("Daemon connection failed: {0}")]
-    DaemonConnection(String),
+    #[error("Daemon connection failed")]
+    DaemonConnection(#[source] BoxError),
 
-    #[error("Protocol error: {0}")]
-    Protocol(String),
+    #[error("Protocol error")]
+    Protocol(#[source] BoxError),
 
     #[error("Search timeout")]
     Timeout,
@@ -53,6 +66,95 @@ pub enum YgrepError {
 
     #[error("Search error: {0}")]
     Search(String),
+
+    #[cfg(feature = "embeddings")]
+    #[error("Embedding error: {0}")]
+    Embedding(#[from] fastembed::Error),
+
+    /// What failed, with the underlying error as the source (see `YgrepError::context`)
+    #[error("{message}")]
+    Context {
+        message: String,
+        #[source]
+        source: BoxError,
+    },
+}
+
+impl YgrepError {
+    /// Wrap this error with a message describing what failed, keeping it as the source
+    pub fn context(self, message: impl Into<String>) -> YgrepError {
+        YgrepError::Context {
+            message: message.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// This error followed by its sources, joined with `: `, for logs and messages
+    ///
+    /// Sources a message already ends with (the `#[from]` variants show theirs) aren't repeated.
+    pub fn chain(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            let text = error.to_string();
+            if !message.ends_with(&text) {
+                message.push_str(": ");
+                message.push_str(&text);
+            }
+            source = error.source();
+        }
+        message
+    }
+}
+
+/// Add a message describing what failed to any error, keeping it as the source
+pub trait ResultExt<T> {
+    fn context(self, message: impl Into<String>) -> Result<T>;
+}
+
+impl<T, E: Into<BoxError>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, message: impl Into<String>) -> Result<T> {
+        self.map_err(|e| YgrepError::Context { message: message.into(), source: e.into() })
+    }
 }
 
 pub type Result<T> = std::result::Result<T, YgrepError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_context_keeps_source() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "doc_ids.bin missing");
+        let err = YgrepError::from(io).context("Failed to load vector index");
+
+        // The message leaves the cause to `source()`, and `chain` shows both once
+        assert_eq!(err.to_string(), "Failed to load vector index");
+        assert_eq!(err.chain(), "Failed to load vector index: IO error: doc_ids.bin missing");
+        let source = err.source().unwrap();
+        assert!(source.downcast_ref::<YgrepError>().is_some_and(|e| matches!(e, YgrepError::Io(_))));
+
+        // Plain messages still work as config errors
+        let err = YgrepError::Config("unknown field".into());
+        assert_eq!(err.to_string(), "Configuration error");
+        assert_eq!(err.chain(), "Configuration error: unknown field");
+
+        // Any error can be wrapped on its way out of a function
+        let result: std::result::Result<(), std::io::Error> = Err(std::io::Error::other("disk full"));
+        let err = result.context("Failed to save HNSW index").unwrap_err();
+        assert_eq!(err.chain(), "Failed to save HNSW index: disk full");
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[test]
+    fn test_from_fastembed_error() {
+        let err: YgrepError = fastembed::Error::msg("tokenizer.json missing").into();
+        assert!(matches!(err, YgrepError::Embedding(_)));
+        assert_eq!(err.to_string(), "Embedding error: tokenizer.json missing");
+
+        let err = err.context("Failed to load semantic model");
+        assert_eq!(err.chain(), "Failed to load semantic model: Embedding error: tokenizer.json missing");
+    }
+}
@@ -0,0 +1,94 @@
+//! Changed-file lists from git, for searching only what a branch touched
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::{Result, YgrepError};
+
+/// Files under `root` that differ from `git_ref` (`git diff --name-only <ref>`), plus untracked
+/// files, as paths relative to `root` with `/` separators
+///
+/// Returns `None` when `root` isn't in a git work tree or git isn't installed.
+pub fn changed_files(root: &Path, git_ref: &str) -> Result<Option<Vec<String>>> {
+    let in_work_tree = git(root, &["rev-parse", "--is-inside-work-tree"])
+        .is_ok_and(|output| output.status.success());
+    if !in_work_tree {
+        return Ok(None);
+    }
+
+    // Keep the ref from being read as an option
+    if git_ref.starts_with('-') {
+        return Err(YgrepError::Search(format!("Invalid git ref: {}", git_ref)));
+    }
+
+    let diff = git(root, &["diff", "--name-only", "--relative", "-z", git_ref, "--"])?;
+    if !diff.status.success() {
+        return Err(YgrepError::Search(format!(
+            "git diff against {} failed: {}",
+            git_ref,
+            String::from_utf8_lossy(&diff.stderr).trim()
+        )));
+    }
+    let untracked = git(root, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+
+    let mut files: Vec<String> = diff.stdout.split(|&b| b == 0)
+        .chain(untracked.stdout.split(|&b| b == 0))
+        .filter(|path| !path.is_empty())
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(Some(files))
+}
+
+/// Run git in `dir`
+fn git(dir: &Path, args: &[&str]) -> std::io::Result<std::process::Output> {
+    Command::new("git").arg("-C").arg(dir).args(args).output()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_files_outside_git() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(changed_files(temp_dir.path(), "main").unwrap().is_none());
+    }
+
+    #[cfg(feature = "git-tests")]
+    #[test]
+    fn test_changed_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let run = |args: &[&str]| {
+            let output = Command::new("git").arg("-C").arg(root).args(args).output().unwrap();
+            assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        };
+
+        run(&["init", "-q", "-b", "main"]);
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "// TODO: old").unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        run(&["add", "."]);
+        run(&["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-q", "-m", "init"]);
+
+        assert_eq!(changed_files(root, "main").unwrap(), Some(vec![]));
+
+        std::fs::write(root.join("src/main.rs"), "// TODO: new\nfn main() {}").unwrap();
+        std::fs::write(root.join("src/new file.rs"), "// TODO: added").unwrap();
+        assert_eq!(
+            changed_files(root, "main").unwrap(),
+            Some(vec!["src/main.rs".to_string(), "src/new file.rs".to_string()])
+        );
+
+        // Paths are relative to the directory asked about
+        assert_eq!(
+            changed_files(&root.join("src"), "main").unwrap(),
+            Some(vec!["main.rs".to_string(), "new file.rs".to_string()])
+        );
+
+        assert!(changed_files(root, "no-such-branch").is_err());
+        assert!(changed_files(root, "--output=x").is_err());
+    }
+}
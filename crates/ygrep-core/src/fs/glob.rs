@@ -0,0 +1,227 @@
+//! Simple glob matching for ignore patterns and search path filters
+//!
+//! Supports the pattern shapes used in `ignore_patterns`:
+//! `**/dir/**`, `**/*.ext`, `**/name`, `dir/**`, `*.ext` and plain names.
+//! Any other pattern with a `*` is matched in full: `*` matches within one path
+//! component, `**` across components (`**/` also matches no directory), and a pattern
+//! without a `/` or `**` is matched against the file name (e.g. `*_test.go`).
+//! `?` matches exactly one character other than a path separator.
+//! Backslashes are treated as path separators, so Windows paths match too.
+
+/// Check if a file path matches an ignore pattern
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    if pattern.is_empty() {
+        return false;
+    }
+
+    let pattern = pattern.replace('\\', "/");
+    let path: Vec<char> = path.replace('\\', "/").chars().collect();
+
+    // Wildcards beyond the leading/trailing shapes below need the full matcher
+    let inner = pattern.trim_start_matches("**/").trim_end_matches("/**").trim_start_matches("*.");
+    if inner.contains('*') {
+        let name_only = !pattern.contains('/') && !pattern.contains("**");
+        let pattern: Vec<char> = pattern.chars().collect();
+        if name_only {
+            let name_start = path.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+            return wildcard_match(&pattern, &path[name_start..]);
+        }
+        return wildcard_match(&pattern, &path);
+    }
+
+    // Handle **/dir/** patterns (match dir anywhere in path)
+    if let Some(dir_name) = pattern
+        .strip_prefix("**/")
+        .and_then(|p| p.strip_suffix("/**"))
+    {
+        // Check if this directory name appears as a complete path component
+        return contains(&path, &format!("/{}/", dir_name))
+            || starts_with(&path, &format!("{}/", dir_name))
+            || ends_with(&path, &format!("/{}", dir_name)); // At end of path (exact match)
+    }
+
+    // Handle **/*.ext patterns (match extension anywhere)
+    if let Some(ext) = pattern.strip_prefix("**/*.") {
+        return ends_with(&path, &format!(".{}", ext));
+    }
+
+    // Handle **/something patterns (match at end)
+    if let Some(suffix) = pattern.strip_prefix("**/") {
+        return ends_with(&path, suffix) || ends_with(&path, &format!("/{}", suffix));
+    }
+
+    // Handle something/** patterns (match at start)
+    if let Some(prefix) = pattern.strip_suffix("/**") {
+        return starts_with(&path, prefix) || contains(&path, &format!("/{}", prefix));
+    }
+
+    // Handle simple * patterns (*.ext)
+    if let Some(ext) = pattern.strip_prefix("*.") {
+        return ends_with(&path, &format!(".{}", ext));
+    }
+
+    // Exact match or path component match
+    (path.len() == pattern.chars().count() && starts_with(&path, &pattern))
+        || ends_with(&path, &format!("/{}", pattern))
+        || contains(&path, &format!("/{}/", pattern))
+}
+
+/// Check if a directory path matches an ignore pattern,
+/// either as the directory itself or as the parent of everything beneath it
+pub fn glob_match_dir(pattern: &str, path: &str) -> bool {
+    let path = path.trim_end_matches(['/', '\\']);
+    glob_match(pattern, path) || glob_match(pattern, &format!("{}/", path))
+}
+
+/// Match a whole path against a pattern with `*`, `**` and `?` wildcards
+fn wildcard_match(pattern: &[char], path: &[char]) -> bool {
+    // Every (pattern, path) position that failed once, so several stars reaching it don't retry it
+    let mut failed = vec![false; (pattern.len() + 1) * (path.len() + 1)];
+    wildcard_match_at(pattern, path, 0, 0, &mut failed)
+}
+
+/// `wildcard_match` of `pattern[p..]` against `path[t..]`
+fn wildcard_match_at(pattern: &[char], path: &[char], p: usize, t: usize, failed: &mut [bool]) -> bool {
+    let key = p * (path.len() + 1) + t;
+    if failed[key] {
+        return false;
+    }
+
+    let matched = match &pattern[p..] {
+        [] => t == path.len(),
+        ['*', '*', rest @ ..] => {
+            // `**/` may also match no directory at all
+            (rest.first() == Some(&'/') && wildcard_match_at(pattern, path, p + 3, t, failed))
+                || (t..=path.len()).any(|i| wildcard_match_at(pattern, path, p + 2, i, failed))
+        }
+        ['*', ..] => (t..=path.len())
+            .take_while(|&i| i == t || path[i - 1] != '/')
+            .any(|i| wildcard_match_at(pattern, path, p + 1, i, failed)),
+        ['?', ..] => matches!(path.get(t), Some(&c) if c != '/') && wildcard_match_at(pattern, path, p + 1, t + 1, failed),
+        [c, ..] => path.get(t) == Some(c) && wildcard_match_at(pattern, path, p + 1, t + 1, failed),
+    };
+    if !matched {
+        failed[key] = true;
+    }
+    matched
+}
+
+/// Compare equal-length runs, with `?` in the pattern matching any non-separator character
+fn segment_matches(text: &[char], pattern: &[char]) -> bool {
+    text.len() == pattern.len()
+        && text.iter().zip(pattern).all(|(&t, &p)| p == t || (p == '?' && t != '/'))
+}
+
+fn starts_with(text: &[char], pattern: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    text.len() >= pattern.len() && segment_matches(&text[..pattern.len()], &pattern)
+}
+
+fn ends_with(text: &[char], pattern: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    text.len() >= pattern.len() && segment_matches(&text[text.len() - pattern.len()..], &pattern)
+}
+
+fn contains(text: &[char], pattern: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    text.windows(pattern.len()).any(|window| segment_matches(window, &pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match_patterns() {
+        // **/dir/**
+        assert!(glob_match("**/node_modules/**", "foo/node_modules/bar/baz.js"));
+        assert!(glob_match("**/.git/**", ".git/config"));
+        assert!(glob_match("**/build/**", "src/build"));
+        assert!(!glob_match("**/build/**", "src/builder/main.rs"));
+
+        // **/*.ext
+        assert!(glob_match("**/*.min.js", "public/js/app.min.js"));
+        assert!(!glob_match("**/*.min.js", "public/js/app.js"));
+
+        // **/name
+        assert!(glob_match("**/Cargo.lock", "crates/core/Cargo.lock"));
+        assert!(!glob_match("**/Cargo.lock", "Cargo.toml"));
+
+        // dir/**
+        assert!(glob_match("target/**", "target/debug/ygrep"));
+        assert!(glob_match("target/**", "crates/target/x"));
+        assert!(!glob_match("target/**", "src/main.rs"));
+
+        // *.ext
+        assert!(glob_match("*.log", "debug.log"));
+        assert!(!glob_match("*.log", "debug.txt"));
+
+        // Exact name or path component
+        assert!(glob_match("secrets.env", "secrets.env"));
+        assert!(glob_match("secrets.env", "config/secrets.env"));
+        assert!(glob_match("fixtures", "tests/fixtures/a.json"));
+        assert!(!glob_match("fixtures", "tests/fixtures_old.json"));
+    }
+
+    #[test]
+    fn test_glob_match_edge_cases() {
+        // Empty pattern matches nothing
+        assert!(!glob_match("", ""));
+        assert!(!glob_match("", "src/main.rs"));
+
+        // Inner ** spans any number of directories
+        assert!(glob_match("**/a/**/b/**", "x/a/c/b/y"));
+        assert!(glob_match("**/a/**/b/**", "a/b/y"));
+        assert!(!glob_match("**/a/**/b/**", "x/a/c/y"));
+
+        // Windows separators in the path or the pattern
+        assert!(glob_match("**/node_modules/**", "C:\\repo\\node_modules\\pkg\\index.js"));
+        assert!(glob_match("target\\**", "target/debug/ygrep"));
+        assert!(glob_match("*.log", "logs\\debug.log"));
+
+        // ? matches exactly one non-separator character
+        assert!(glob_match("*.?s", "main.rs"));
+        assert!(glob_match("*.?s", "index.ts"));
+        assert!(!glob_match("*.?s", "main.css"));
+        assert!(glob_match("**/log?/**", "var/logs/today.txt"));
+        assert!(!glob_match("**/log?/**", "var/log/today.txt"));
+        assert!(glob_match("file?.txt", "docs/file1.txt"));
+        assert!(!glob_match("a?b", "a/b"));
+    }
+
+    #[test]
+    fn test_glob_match_wildcards() {
+        // Without a `/` the pattern applies to the file name
+        assert!(glob_match("*_test.go", "pkg/auth/login_test.go"));
+        assert!(!glob_match("*_test.go", "pkg/auth_test.go/login.go"));
+        assert!(glob_match("**/*_test.go", "login_test.go"));
+        assert!(glob_match("**/*_test.go", "pkg/auth/login_test.go"));
+
+        // `*` stays within one component, `**` doesn't
+        assert!(glob_match("src/*/mod.rs", "src/search/mod.rs"));
+        assert!(!glob_match("src/*/mod.rs", "src/search/deep/mod.rs"));
+        assert!(glob_match("src/**/mod.rs", "src/search/deep/mod.rs"));
+        assert!(glob_match("src/auth/**/*.rs", "src/auth/handler.rs"));
+        assert!(glob_match("**auth**", "src/auth/handler.rs"));
+    }
+
+    #[test]
+    fn test_glob_match_many_double_stars() {
+        // Each `**` could start at any of the path's positions; matching must not try every combination
+        let pattern = "**/a/**/a/**/a/**/a/**/a/**/a/**/*.rs";
+        let path = "a/".repeat(100);
+        assert!(glob_match(pattern, &format!("{}main.rs", path)));
+        assert!(!glob_match(pattern, &format!("{}main.txt", path)));
+        assert!(!glob_match(pattern, &format!("{}main.rs", "b/".repeat(100))));
+    }
+
+    #[test]
+    fn test_glob_match_dir() {
+        assert!(glob_match_dir("**/node_modules/**", "node_modules"));
+        assert!(glob_match_dir("**/node_modules/**", "web/node_modules/"));
+        assert!(glob_match_dir("target/**", "target"));
+        assert!(glob_match_dir("fixtures", "tests/fixtures"));
+        assert!(!glob_match_dir("**/node_modules/**", "src"));
+        assert!(!glob_match_dir("*.log", "logs"));
+    }
+}
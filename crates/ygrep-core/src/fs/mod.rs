@@ -1,5 +1,7 @@
+pub mod git;
+pub mod glob;
 mod symlink;
 mod walker;
 
 pub use symlink::{SymlinkResolver, ResolvedPath, SkipReason};
-pub use walker::{FileWalker, WalkEntry, WalkStats};
+pub use walker::{FileWalker, SkippedPath, WalkEntry, WalkSkipReason, WalkStats};
@@ -1,26 +1,51 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use parking_lot::Mutex;
 use walkdir::WalkDir;
 
 use crate::config::IndexerConfig;
-use crate::error::Result;
-use super::symlink::{SymlinkResolver, ResolvedPath};
+use crate::error::{Result, YgrepError};
+use super::glob::glob_match;
+use super::symlink::{SymlinkResolver, ResolvedPath, SkipReason};
 
-/// Walks a directory tree, respecting gitignore and handling symlinks
+/// Walks one or more directory trees, respecting gitignore and handling symlinks
 pub struct FileWalker {
-    root: PathBuf,
+    /// Directories to walk, in order (the first is the main root)
+    roots: Vec<PathBuf>,
     config: IndexerConfig,
-    gitignore: Option<Gitignore>,
+    /// Each root's .gitignore, by root index
+    gitignores: Vec<Option<Gitignore>>,
     symlink_resolver: SymlinkResolver,
+    size_skipped: usize,
+    /// Skipped paths with reasons, only collected after `record_skipped()`
+    skipped: Option<Arc<Mutex<Vec<SkippedPath>>>>,
 }
 
 impl FileWalker {
     pub fn new(root: PathBuf, config: IndexerConfig) -> Result<Self> {
-        let gitignore = if config.respect_gitignore {
-            load_gitignore(&root)
-        } else {
-            None
-        };
+        Self::new_multi(vec![root], config)
+    }
+
+    /// Walk several directory trees in one pass, one after the other
+    /// Files reachable from more than one root (e.g. through symlinks) are only returned once
+    ///
+    /// With `config.no_ignore`, ignore patterns, .gitignore files and `skip_dirs` are all
+    /// disregarded.
+    pub fn new_multi(roots: Vec<PathBuf>, mut config: IndexerConfig) -> Result<Self> {
+        if roots.is_empty() {
+            return Err(YgrepError::Config("FileWalker needs at least one root directory".into()));
+        }
+        if config.no_ignore {
+            config.ignore_patterns.clear();
+            config.skip_dirs.clear();
+            config.respect_gitignore = false;
+        }
+
+        let gitignores = roots.iter()
+            .map(|root| if config.respect_gitignore { load_gitignore(root) } else { None })
+            .collect();
+        // One resolver for all roots, so cross-root duplicates are detected
         let symlink_resolver = SymlinkResolver::new(config.follow_symlinks, 20);
 
         tracing::debug!("FileWalker initialized with {} ignore patterns", config.ignore_patterns.len());
@@ -29,47 +54,67 @@ impl FileWalker {
         }
 
         Ok(Self {
-            root,
+            roots,
             config,
-            gitignore,
+            gitignores,
             symlink_resolver,
+            size_skipped: 0,
+            skipped: None,
         })
     }
 
-    /// Iterate over all indexable files in the directory tree
-    pub fn walk(&mut self) -> impl Iterator<Item = WalkEntry> + '_ {
-        let follow_links = self.config.follow_symlinks;
-
-        WalkDir::new(&self.root)
-            .follow_links(follow_links)
-            .into_iter()
-            .filter_entry(move |e| {
-                // Skip hidden files/directories
-                if is_hidden(e) {
-                    return false;
-                }
-
-                // Skip directories matching ignore patterns
-                if e.file_type().is_dir() {
-                    let dir_name = e.file_name().to_string_lossy();
+    /// Collect every skipped path with the reason it was left out (see `skipped()`)
+    pub fn record_skipped(&mut self) {
+        self.skipped.get_or_insert_with(Default::default);
+    }
 
-                    // Quick check for common ignored directories
-                    let dominated = matches!(
-                        dir_name.as_ref(),
-                        "cache" | "node_modules" | "vendor" | "target" | "dist" |
-                        "build" | "logs" | "log" | "tmp" | "temp" | "var" |
-                        "__pycache__" | ".git" | ".svn" | "coverage" | "htmlcov"
-                    );
+    /// Paths skipped so far, in walk order (empty unless `record_skipped()` was called)
+    pub fn skipped(&self) -> Vec<SkippedPath> {
+        self.skipped.as_ref().map(|s| s.lock().clone()).unwrap_or_default()
+    }
 
-                    if dominated {
-                        return false;
-                    }
-                }
+    /// Remember a skipped path when recording is enabled
+    fn skip(&self, path: &Path, reason: WalkSkipReason) {
+        record_skip(self.skipped.as_ref(), path, reason);
+    }
 
-                true
+    /// Iterate over all indexable files in the directory trees, root by root
+    pub fn walk(&mut self) -> impl Iterator<Item = WalkEntry> + '_ {
+        let follow_links = self.config.follow_symlinks;
+        let skip_dirs = self.config.skip_dirs.clone();
+        let skipped = self.skipped.clone();
+        let roots = self.roots.clone();
+
+        roots.into_iter()
+            .enumerate()
+            .flat_map(move |(root_index, root)| {
+                let skipped = skipped.clone();
+                let skip_dirs = skip_dirs.clone();
+                WalkDir::new(root)
+                    .follow_links(follow_links)
+                    .into_iter()
+                    .filter_entry(move |e| {
+                        // Skip hidden files/directories (but never the root itself)
+                        if e.depth() > 0 && is_hidden(e) {
+                            record_skip(skipped.as_ref(), e.path(), WalkSkipReason::Hidden);
+                            return false;
+                        }
+
+                        // Skip directories named in skip_dirs, before the slower pattern checks
+                        if e.depth() > 0 && e.file_type().is_dir() {
+                            let dir_name = e.file_name().to_string_lossy();
+                            if skip_dirs.iter().any(|d| *d == dir_name) {
+                                record_skip(skipped.as_ref(), e.path(), WalkSkipReason::IgnoredDirectory);
+                                return false;
+                            }
+                        }
+
+                        true
+                    })
+                    .filter_map(|entry| entry.ok())
+                    .map(move |entry| (root_index, entry))
             })
-            .filter_map(|entry| entry.ok())
-            .filter_map(move |entry| {
+            .filter_map(move |(root_index, entry)| {
                 let path = entry.path();
 
                 // Skip directories
@@ -78,17 +123,32 @@ impl FileWalker {
                 }
 
                 // Check gitignore
-                if self.is_ignored(path) {
+                if self.is_ignored(root_index, path) {
+                    self.skip(path, WalkSkipReason::Gitignore);
                     return None;
                 }
 
                 // Check custom ignore patterns
-                if self.matches_ignore_pattern(path) {
+                if let Some(pattern) = self.matching_ignore_pattern(root_index, path) {
+                    let pattern = pattern.to_string();
+                    self.skip(path, WalkSkipReason::IgnorePattern(pattern));
                     return None;
                 }
 
+                // Skip files over the size limit before sniffing their content
+                // (metadata follows symlinks, so this is the target's size)
+                if let Ok(metadata) = std::fs::metadata(path) {
+                    if metadata.len() > self.config.max_file_size {
+                        tracing::debug!("Skipping {}: {} bytes exceeds size limit", path.display(), metadata.len());
+                        self.size_skipped += 1;
+                        self.skip(path, WalkSkipReason::TooLarge(metadata.len()));
+                        return None;
+                    }
+                }
+
                 // Check if file is indexable (text file, right extension)
                 if !self.is_indexable(path) {
+                    self.skip(path, WalkSkipReason::NotIndexable);
                     return None;
                 }
 
@@ -103,6 +163,7 @@ impl FileWalker {
                     }
                     Ok(ResolvedPath::Skipped(reason)) => {
                         tracing::debug!("Skipping {}: {}", path.display(), reason);
+                        self.skip(path, WalkSkipReason::Symlink(reason));
                         None
                     }
                     Err(e) => {
@@ -113,9 +174,9 @@ impl FileWalker {
             })
     }
 
-    /// Check if a path should be ignored by gitignore
-    fn is_ignored(&self, path: &Path) -> bool {
-        if let Some(ref gitignore) = self.gitignore {
+    /// Check if a path should be ignored by its root's gitignore
+    fn is_ignored(&self, root_index: usize, path: &Path) -> bool {
+        if let Some(ref gitignore) = self.gitignores[root_index] {
             let is_dir = path.is_dir();
             gitignore.matched(path, is_dir).is_ignore()
         } else {
@@ -123,21 +184,29 @@ impl FileWalker {
         }
     }
 
-    /// Check if path matches custom ignore patterns
-    fn matches_ignore_pattern(&self, path: &Path) -> bool {
-        let path_str = path.to_string_lossy();
-
-        for pattern in &self.config.ignore_patterns {
-            if glob_match(pattern, &path_str) {
-                return true;
-            }
-        }
+    /// Find the first custom ignore pattern matching a path
+    /// (matched relative to its root, so the root's own location doesn't count)
+    fn matching_ignore_pattern(&self, root_index: usize, path: &Path) -> Option<&str> {
+        let path_str = path.strip_prefix(&self.roots[root_index]).unwrap_or(path).to_string_lossy();
 
-        false
+        self.config.ignore_patterns.iter()
+            .find(|pattern| glob_match(pattern, &path_str))
+            .map(String::as_str)
     }
 
     /// Check if a file should be indexed
     fn is_indexable(&self, path: &Path) -> bool {
+        // Check excluded extensions (by file name suffix, so "min.js" works)
+        if !self.config.exclude_extensions.is_empty() {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+            let excluded = self.config.exclude_extensions.iter().any(|ext| {
+                name.ends_with(&format!(".{}", ext.trim_start_matches('.').to_lowercase()))
+            });
+            if excluded {
+                return false;
+            }
+        }
+
         // Check extension filter if set
         if !self.config.include_extensions.is_empty() {
             if let Some(ext) = path.extension() {
@@ -154,15 +223,21 @@ impl FileWalker {
         is_text_file(path)
     }
 
-    /// Get the root directory
+    /// Get the (first) root directory
     pub fn root(&self) -> &Path {
-        &self.root
+        &self.roots[0]
+    }
+
+    /// Get every root directory, in walk order
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
     }
 
     /// Get statistics about the walk
     pub fn stats(&self) -> WalkStats {
         WalkStats {
             visited_paths: self.symlink_resolver.visited_count(),
+            size_skipped: self.size_skipped,
         }
     }
 }
@@ -182,6 +257,55 @@ pub struct WalkEntry {
 #[derive(Debug, Clone, Default)]
 pub struct WalkStats {
     pub visited_paths: usize,
+    /// Files skipped for exceeding `max_file_size`
+    pub size_skipped: usize,
+}
+
+/// A path the walker left out, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedPath {
+    pub path: PathBuf,
+    pub reason: WalkSkipReason,
+}
+
+/// Reason the walker left a path out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalkSkipReason {
+    /// Hidden file or directory (name starts with `.`)
+    Hidden,
+    /// Commonly generated directory such as `node_modules` or `target`
+    IgnoredDirectory,
+    /// Matched by `.gitignore`
+    Gitignore,
+    /// Matched by this `ignore_patterns` entry
+    IgnorePattern(String),
+    /// Larger than `max_file_size` (size in bytes)
+    TooLarge(u64),
+    /// Excluded extension, or not a text file
+    NotIndexable,
+    /// Symlink that wasn't followed
+    Symlink(SkipReason),
+}
+
+impl std::fmt::Display for WalkSkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WalkSkipReason::Hidden => write!(f, "hidden"),
+            WalkSkipReason::IgnoredDirectory => write!(f, "ignored directory"),
+            WalkSkipReason::Gitignore => write!(f, "gitignore"),
+            WalkSkipReason::IgnorePattern(pattern) => write!(f, "ignore pattern {}", pattern),
+            WalkSkipReason::TooLarge(size) => write!(f, "too large ({} bytes)", size),
+            WalkSkipReason::NotIndexable => write!(f, "excluded extension or not text"),
+            WalkSkipReason::Symlink(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Add a skipped path to the recording list (no-op when not recording)
+fn record_skip(skipped: Option<&Arc<Mutex<Vec<SkippedPath>>>>, path: &Path, reason: WalkSkipReason) {
+    if let Some(skipped) = skipped {
+        skipped.lock().push(SkippedPath { path: path.to_path_buf(), reason });
+    }
 }
 
 /// Load .gitignore from a directory
@@ -207,47 +331,6 @@ fn is_hidden(entry: &walkdir::DirEntry) -> bool {
         .unwrap_or(false)
 }
 
-/// Simple glob matching for ignore patterns (for files)
-fn glob_match(pattern: &str, path: &str) -> bool {
-    // Handle **/dir/** patterns (match dir anywhere in path)
-    if pattern.starts_with("**/") && pattern.ends_with("/**") {
-        let dir_name = &pattern[3..pattern.len()-3];
-        // Check if this directory name appears as a complete path component
-        return path.contains(&format!("/{}/", dir_name))
-            || path.starts_with(&format!("{}/", dir_name))
-            || path.ends_with(&format!("/{}", dir_name));  // At end of path (exact match)
-    }
-
-    // Handle **/*.ext patterns (match extension anywhere)
-    if pattern.starts_with("**/*.") {
-        let ext = &pattern[5..]; // Get everything after "**/*." (index 5 skips the dot)
-        return path.ends_with(&format!(".{}", ext));
-    }
-
-    // Handle **/something patterns (match at end)
-    if pattern.starts_with("**/") {
-        let suffix = &pattern[3..];
-        return path.ends_with(suffix) || path.ends_with(&format!("/{}", suffix));
-    }
-
-    // Handle something/** patterns (match at start)
-    if pattern.ends_with("/**") {
-        let prefix = &pattern[..pattern.len()-3];
-        return path.starts_with(prefix) || path.contains(&format!("/{}", prefix));
-    }
-
-    // Handle simple * patterns (*.ext)
-    if pattern.starts_with("*.") {
-        let ext = &pattern[2..];
-        return path.ends_with(&format!(".{}", ext));
-    }
-
-    // Exact match or path component match
-    path == pattern
-        || path.ends_with(&format!("/{}", pattern))
-        || path.contains(&format!("/{}/", pattern))
-}
-
 /// Check if a file is likely a text file
 fn is_text_file(path: &Path) -> bool {
     // Known text extensions
@@ -299,14 +382,42 @@ fn is_text_file(path: &Path) -> bool {
 
     // Fall back to checking first bytes for binary content
     if let Ok(bytes) = std::fs::read(path) {
-        // Check first 8KB for null bytes
+        // Check first 8KB
         let check_len = bytes.len().min(8192);
-        !bytes[..check_len].contains(&0)
+        is_text_content(&bytes[..check_len])
     } else {
         false
     }
 }
 
+/// Sniff the leading bytes of a file to decide whether it's indexable text
+fn is_text_content(bytes: &[u8]) -> bool {
+    // UTF-16 (either byte order) needs transcoding we don't do yet
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return false;
+    }
+
+    // Known binary formats can lack null bytes in their header
+    if let Some(kind) = infer::get(bytes) {
+        let is_binary = matches!(
+            kind.matcher_type(),
+            infer::MatcherType::Image
+                | infer::MatcherType::Audio
+                | infer::MatcherType::Video
+                | infer::MatcherType::Archive
+                | infer::MatcherType::App
+                | infer::MatcherType::Font
+                | infer::MatcherType::Doc
+                | infer::MatcherType::Book
+        );
+        if is_binary {
+            return false;
+        }
+    }
+
+    !bytes.contains(&0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -329,6 +440,144 @@ mod tests {
         assert!(entries.len() >= 3);
     }
 
+    #[test]
+    fn test_walk_skips_large_files() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("small.rs"), "fn main() {}").unwrap();
+        std::fs::write(temp_dir.path().join("large.rs"), "x".repeat(2048)).unwrap();
+
+        let config = IndexerConfig {
+            max_file_size: 1024,
+            ..IndexerConfig::default()
+        };
+        let mut walker = FileWalker::new(temp_dir.path().to_path_buf(), config).unwrap();
+
+        let entries: Vec<_> = walker.walk().collect();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].path.ends_with("small.rs"));
+        assert_eq!(walker.stats().size_skipped, 1);
+    }
+
+    #[test]
+    fn test_walk_records_skipped() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("node_modules/pkg")).unwrap();
+        std::fs::write(temp_dir.path().join("node_modules/pkg/index.js"), "run();").unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(temp_dir.path().join("large.rs"), "x".repeat(2048)).unwrap();
+        std::fs::write(temp_dir.path().join("debug.log"), "started").unwrap();
+
+        let config = IndexerConfig {
+            max_file_size: 1024,
+            ignore_patterns: vec!["*.log".to_string()],
+            ..IndexerConfig::default()
+        };
+        let mut walker = FileWalker::new(temp_dir.path().to_path_buf(), config).unwrap();
+        walker.record_skipped();
+
+        assert_eq!(walker.walk().count(), 1);
+
+        let mut skipped: Vec<_> = walker.skipped().into_iter()
+            .map(|s| (s.path.file_name().unwrap().to_string_lossy().to_string(), s.reason))
+            .collect();
+        skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(skipped, [
+            ("debug.log".to_string(), WalkSkipReason::IgnorePattern("*.log".to_string())),
+            ("large.rs".to_string(), WalkSkipReason::TooLarge(2048)),
+            ("node_modules".to_string(), WalkSkipReason::IgnoredDirectory),
+        ]);
+    }
+
+    #[test]
+    fn test_walk_exclude_extensions() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("app.js"), "run();").unwrap();
+        std::fs::write(temp_dir.path().join("app.min.js"), "run();").unwrap();
+        std::fs::write(temp_dir.path().join("debug.LOG"), "started").unwrap();
+
+        let config = IndexerConfig {
+            exclude_extensions: vec!["min.js".to_string(), ".log".to_string()],
+            ..IndexerConfig::default()
+        };
+        let mut walker = FileWalker::new(temp_dir.path().to_path_buf(), config).unwrap();
+
+        let entries: Vec<_> = walker.walk().collect();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].path.ends_with("app.js"));
+    }
+
+    #[test]
+    fn test_walk_no_ignore() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("node_modules/left-pad")).unwrap();
+        std::fs::write(temp_dir.path().join("node_modules/left-pad/index.js"), "module.exports = pad;").unwrap();
+        std::fs::write(temp_dir.path().join("app.js"), "run();").unwrap();
+        std::fs::write(temp_dir.path().join("generated.js"), "run();").unwrap();
+        std::fs::write(temp_dir.path().join(".gitignore"), "generated.js\n").unwrap();
+        std::fs::write(temp_dir.path().join("debug.log"), "started").unwrap();
+
+        let walk = |no_ignore| {
+            let config = IndexerConfig {
+                ignore_patterns: vec!["*.log".to_string()],
+                respect_gitignore: true,
+                no_ignore,
+                ..IndexerConfig::default()
+            };
+            let mut walker = FileWalker::new(temp_dir.path().to_path_buf(), config).unwrap();
+            let mut paths: Vec<_> = walker.walk()
+                .map(|e| e.path.strip_prefix(temp_dir.path()).unwrap().to_string_lossy().to_string())
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(walk(false), ["app.js"]);
+        // Hidden files like .gitignore are still skipped
+        assert_eq!(walk(true), ["app.js", "debug.log", "generated.js", "node_modules/left-pad/index.js"]);
+    }
+
+    #[test]
+    fn test_walk_multiple_roots() {
+        let frontend = tempdir().unwrap();
+        let backend = tempdir().unwrap();
+        std::fs::write(frontend.path().join("app.ts"), "render();").unwrap();
+        std::fs::write(frontend.path().join(".gitignore"), "generated.ts\n").unwrap();
+        std::fs::write(frontend.path().join("generated.ts"), "render();").unwrap();
+        std::fs::write(backend.path().join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(backend.path().join("generated.ts"), "serve();").unwrap();
+
+        let config = IndexerConfig {
+            respect_gitignore: true,
+            ..IndexerConfig::default()
+        };
+        // The backend is listed twice; its files are still only returned once
+        let roots = vec![frontend.path().to_path_buf(), backend.path().to_path_buf(), backend.path().to_path_buf()];
+        let mut walker = FileWalker::new_multi(roots, config).unwrap();
+
+        let mut paths: Vec<_> = walker.walk().map(|e| e.path).collect();
+        paths.sort();
+        let mut expected = vec![
+            frontend.path().join("app.ts"),
+            // Only the frontend's .gitignore excludes generated.ts
+            backend.path().join("generated.ts"),
+            backend.path().join("main.rs"),
+        ];
+        expected.sort();
+        assert_eq!(paths, expected);
+
+        assert!(FileWalker::new_multi(vec![], IndexerConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_is_text_content() {
+        assert!(is_text_content(b"just some text\r"));
+        // UTF-16 LE/BE byte order marks
+        assert!(!is_text_content(&[0xFF, 0xFE, b'h', 0x00]));
+        assert!(!is_text_content(&[0xFE, 0xFF, 0x00, b'h']));
+        // GIF header without any null bytes
+        assert!(!is_text_content(b"GIF89a\x01\x01"));
+    }
+
     #[test]
     fn test_glob_match() {
         assert!(glob_match("**/node_modules/**", "foo/node_modules/bar/baz.js"));
@@ -0,0 +1,226 @@
+//! Language detection for the `language` field and `--lang` filters
+//!
+//! The extension decides when it's unambiguous. Extensionless files fall back to well-known
+//! file names (`Makefile`, `Dockerfile`) and then to the interpreter on a `#!` line, and C/C++
+//! headers (`.h`) are told apart by looking for C++-only constructs.
+
+use std::path::Path;
+
+/// Languages by file extension (lowercase)
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("python", &["py", "pyi", "pyw"]),
+    ("javascript", &["js", "jsx", "mjs", "cjs"]),
+    ("typescript", &["ts", "tsx", "mts", "cts"]),
+    ("go", &["go"]),
+    ("c", &["c"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hh", "hxx"]),
+    ("csharp", &["cs"]),
+    ("java", &["java"]),
+    ("kotlin", &["kt", "kts"]),
+    ("scala", &["scala", "sc"]),
+    ("swift", &["swift"]),
+    ("ruby", &["rb", "rake", "gemspec"]),
+    ("php", &["php"]),
+    ("perl", &["pl", "pm"]),
+    ("lua", &["lua"]),
+    ("r", &["r"]),
+    ("julia", &["jl"]),
+    ("haskell", &["hs"]),
+    ("ocaml", &["ml", "mli"]),
+    ("fsharp", &["fs", "fsi", "fsx"]),
+    ("elixir", &["ex", "exs"]),
+    ("erlang", &["erl", "hrl"]),
+    ("clojure", &["clj", "cljs", "cljc", "edn"]),
+    ("shell", &["sh", "bash", "zsh", "fish"]),
+    ("powershell", &["ps1", "psm1"]),
+    ("batch", &["bat", "cmd"]),
+    ("html", &["html", "htm"]),
+    ("css", &["css", "scss", "sass", "less"]),
+    ("vue", &["vue"]),
+    ("svelte", &["svelte"]),
+    ("astro", &["astro"]),
+    ("json", &["json"]),
+    ("yaml", &["yaml", "yml"]),
+    ("toml", &["toml"]),
+    ("xml", &["xml"]),
+    ("markdown", &["md", "markdown"]),
+    ("sql", &["sql"]),
+    ("graphql", &["graphql", "gql"]),
+    ("protobuf", &["proto"]),
+    ("terraform", &["tf", "hcl"]),
+    ("nix", &["nix"]),
+    ("cmake", &["cmake"]),
+    ("dockerfile", &["dockerfile"]),
+    ("makefile", &["mk", "makefile"]),
+];
+
+/// Languages of extensionless files by name (lowercase)
+const FILENAMES: &[(&str, &str)] = &[
+    ("makefile", "makefile"),
+    ("gnumakefile", "makefile"),
+    ("dockerfile", "dockerfile"),
+    ("containerfile", "dockerfile"),
+    ("rakefile", "ruby"),
+    ("gemfile", "ruby"),
+    ("cmakelists.txt", "cmake"),
+];
+
+/// Languages by `#!` interpreter, with any version suffix removed (`python3.12` -> `python`)
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("pypy", "python"),
+    ("node", "javascript"),
+    ("nodejs", "javascript"),
+    ("deno", "typescript"),
+    ("bun", "javascript"),
+    ("ts-node", "typescript"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("rscript", "r"),
+    ("julia", "julia"),
+    ("elixir", "elixir"),
+    ("escript", "erlang"),
+    ("pwsh", "powershell"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("dash", "shell"),
+    ("ksh", "shell"),
+    ("fish", "shell"),
+];
+
+/// Language of files that aren't recognized, so `--lang unknown` can find them
+pub const UNKNOWN_LANGUAGE: &str = "unknown";
+
+/// Detect the language of a file from its path and content
+///
+/// Returns `UNKNOWN_LANGUAGE` when neither the extension, the file name nor a shebang line
+/// give it away.
+pub fn detect_language(path: &Path, content: &str) -> &'static str {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if let Some(&(_, language)) = FILENAMES.iter().find(|(file, _)| *file == name) {
+        return language;
+    }
+
+    if let Some(ext) = path.extension() {
+        let ext = ext.to_string_lossy().to_lowercase();
+        if ext == "h" {
+            return if looks_like_cpp(content) { "cpp" } else { "c" };
+        }
+        if let Some(language) = language_for_extension(&ext) {
+            return language;
+        }
+    }
+
+    content.lines().next().and_then(shebang_language).unwrap_or(UNKNOWN_LANGUAGE)
+}
+
+/// Whether `name` is a language `detect_language` can return (for validating `--lang`)
+pub fn is_known_language(name: &str) -> bool {
+    known_languages().iter().any(|language| language.eq_ignore_ascii_case(name))
+}
+
+/// All the languages `detect_language` can return, sorted
+pub fn known_languages() -> Vec<&'static str> {
+    let mut languages: Vec<&str> = LANGUAGES.iter().map(|(language, _)| *language).collect();
+    languages.push(UNKNOWN_LANGUAGE);
+    languages.sort_unstable();
+    languages
+}
+
+fn language_for_extension(ext: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(_, extensions)| extensions.contains(&ext))
+        .map(|(language, _)| *language)
+}
+
+/// The language of a `#!/usr/bin/python3` or `#!/usr/bin/env -S node --flag` line
+fn shebang_language(line: &str) -> Option<&'static str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+
+    let interpreter = interpreter
+        .to_lowercase()
+        .trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')
+        .to_string();
+    INTERPRETERS
+        .iter()
+        .find(|(name, _)| *name == interpreter)
+        .map(|(_, language)| *language)
+}
+
+/// Whether a `.h` header uses C++-only constructs
+fn looks_like_cpp(content: &str) -> bool {
+    const MARKERS: &[&str] = &["class ", "namespace ", "template<", "template <", "std::", "public:", "private:", "#include <iostream>"];
+    content.lines().any(|line| {
+        let line = line.trim_start();
+        !line.starts_with("//") && MARKERS.iter().any(|marker| line.contains(marker))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let detect = |path: &str, content: &str| detect_language(Path::new(path), content);
+
+        assert_eq!(detect("app/Views.PY", ""), "python");
+        assert_eq!(detect("build/Makefile", ""), "makefile");
+
+        // Extensionless scripts by their shebang
+        assert_eq!(detect("bin/deploy", "#!/usr/bin/env python\nimport sys\n"), "python");
+        assert_eq!(detect("bin/serve", "#!/usr/bin/env -S node --enable-source-maps\n"), "javascript");
+        assert_eq!(detect("bin/setup", "#!/bin/bash\nset -e\n"), "shell");
+        assert_eq!(detect("bin/tool", "#!/usr/local/bin/python3.12\n"), "python");
+        assert_eq!(detect("LICENSE", "MIT License\n"), UNKNOWN_LANGUAGE);
+        assert_eq!(detect("data/blob.bin", ""), UNKNOWN_LANGUAGE);
+
+        // Headers by their contents
+        assert_eq!(detect("include/list.h", "struct list { int len; };\n"), "c");
+        assert_eq!(detect("include/list.h", "namespace util {\nclass List {};\n}\n"), "cpp");
+
+        assert!(is_known_language("Rust"));
+        assert!(is_known_language("unknown"));
+        assert!(!is_known_language("klingon"));
+    }
+
+    #[test]
+    fn test_detect_common_languages() {
+        let cases = [
+            ("src/main.rs", "rust"),
+            ("app/models.py", "python"),
+            ("web/app.js", "javascript"),
+            ("web/app.jsx", "javascript"),
+            ("web/index.ts", "typescript"),
+            ("web/index.tsx", "typescript"),
+            ("cmd/server/main.go", "go"),
+            ("lib/list.c", "c"),
+            ("lib/list.cpp", "cpp"),
+            ("src/Program.cs", "csharp"),
+            ("src/Main.java", "java"),
+            ("src/Main.kt", "kotlin"),
+            ("Sources/App.swift", "swift"),
+            ("lib/user.rb", "ruby"),
+            ("src/Controller.php", "php"),
+            ("scripts/build.sh", "shell"),
+            ("templates/index.html", "html"),
+            ("styles/site.scss", "css"),
+            ("config/app.yml", "yaml"),
+            ("Cargo.toml", "toml"),
+            ("db/schema.sql", "sql"),
+            ("README.md", "markdown"),
+        ];
+        for (path, language) in cases {
+            assert_eq!(detect_language(Path::new(path), ""), language, "{}", path);
+        }
+    }
+}
@@ -1,9 +1,13 @@
+pub mod language;
 pub mod schema;
+pub mod symbols;
 pub mod writer;
 #[cfg(feature = "embeddings")]
 pub mod vector;
+pub mod vector_files;
 
-pub use schema::{build_document_schema, SchemaFields, fields, register_tokenizers, CODE_TOKENIZER};
-pub use writer::Indexer;
+pub use schema::{build_document_schema, SchemaFields, fields, register_tokenizers, register_tokenizers_with_limit, schema_hash, CODE_TOKENIZER, DEFAULT_MAX_TOKEN_CHARS, PATH_TOKENIZER, SCHEMA_VERSION};
+pub use writer::{IndexedFile, Indexer};
+pub use vector_files::vector_count;
 #[cfg(feature = "embeddings")]
 pub use vector::VectorIndex;
@@ -1,22 +1,199 @@
-use tantivy::schema::{Schema, STORED, STRING, FAST, TextFieldIndexing, TextOptions, IndexRecordOption};
-use tantivy::tokenizer::{TokenizerManager, TextAnalyzer, LowerCaser, RemoveLongFilter};
+use tantivy::schema::{Schema, STORED, STRING, FAST, INDEXED, TextFieldIndexing, TextOptions, IndexRecordOption};
+use tantivy::tokenizer::{TokenizerManager, TextAnalyzer, LowerCaser};
 
 /// Name of our custom code tokenizer
 pub const CODE_TOKENIZER: &str = "code";
 
-/// Register the code-aware tokenizer with an index
+/// Name of the path tokenizer (splits `src/auth/handler.rs` into `src`, `auth`, `handler`, `rs`)
+pub const PATH_TOKENIZER: &str = "path";
+
+/// Version of the document schema/tokenizer, stored in workspace.json
+/// Bump when a change requires `ygrep index --rebuild`
+pub const SCHEMA_VERSION: u32 = 7;
+
+/// Default longest token indexed, in characters (not bytes, so CJK or Arabic identifiers aren't cut short)
+///
+/// Longer tokens (minified code, base64 blobs, generated identifiers) are truncated to this
+/// many characters, so they can still be found by their prefix. A higher limit makes such
+/// tokens match more precisely at the cost of a bigger term dictionary.
+pub const DEFAULT_MAX_TOKEN_CHARS: usize = 256;
+
+/// Register the code-aware tokenizer with an index, using the default token length limit
 pub fn register_tokenizers(tokenizer_manager: &TokenizerManager) {
+    register_tokenizers_with_limit(tokenizer_manager, DEFAULT_MAX_TOKEN_CHARS);
+}
+
+/// Register the code-aware tokenizer with an index, truncating tokens to `max_token_chars` characters
+///
+/// The same limit must be used for indexing and searching (see `indexer.max_token_len`).
+pub fn register_tokenizers_with_limit(tokenizer_manager: &TokenizerManager, max_token_chars: usize) {
     // Code tokenizer: keeps $, @, # as part of tokens
     // Uses SimpleTokenizer which splits on whitespace, then we just lowercase
     let code_tokenizer = TextAnalyzer::builder(CodeTokenizer)
         .filter(LowerCaser)
-        .filter(RemoveLongFilter::limit(100))
+        .filter(TruncateLongFilter::limit(max_token_chars))
         .build();
 
     tokenizer_manager.register(CODE_TOKENIZER, code_tokenizer);
+
+    // Path tokenizer: path components, file name parts and extension, lowercased
+    let path_tokenizer = TextAnalyzer::builder(PathTokenizer)
+        .filter(LowerCaser)
+        .filter(TruncateLongFilter::limit(max_token_chars))
+        .build();
+
+    tokenizer_manager.register(PATH_TOKENIZER, path_tokenizer);
+}
+
+/// Truncates tokens longer than a number of characters to their first `max_chars` characters
+///
+/// Used instead of Tantivy's `RemoveLongFilter`, which counts UTF-8 bytes (so `数値`, 2
+/// characters but 6 bytes, hits the limit early) and removes long tokens from the index
+/// altogether. Truncated tokens can still be found by their prefix.
+#[derive(Clone)]
+struct TruncateLongFilter {
+    max_chars: usize,
+}
+
+impl TruncateLongFilter {
+    fn limit(max_chars: usize) -> Self {
+        Self { max_chars }
+    }
+}
+
+impl tantivy::tokenizer::TokenFilter for TruncateLongFilter {
+    type Tokenizer<T: tantivy::tokenizer::Tokenizer> = TruncateLongWrapper<T>;
+
+    fn transform<T: tantivy::tokenizer::Tokenizer>(self, tokenizer: T) -> TruncateLongWrapper<T> {
+        TruncateLongWrapper {
+            max_chars: self.max_chars,
+            inner: tokenizer,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct TruncateLongWrapper<T> {
+    max_chars: usize,
+    inner: T,
+}
+
+impl<T: tantivy::tokenizer::Tokenizer> tantivy::tokenizer::Tokenizer for TruncateLongWrapper<T> {
+    type TokenStream<'a> = TruncateLongStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        TruncateLongStream {
+            max_chars: self.max_chars,
+            tail: self.inner.token_stream(text),
+        }
+    }
+}
+
+struct TruncateLongStream<T> {
+    max_chars: usize,
+    tail: T,
+}
+
+impl<T: tantivy::tokenizer::TokenStream> tantivy::tokenizer::TokenStream for TruncateLongStream<T> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+        let text = &mut self.tail.token_mut().text;
+        if let Some((end, _)) = text.char_indices().nth(self.max_chars) {
+            text.truncate(end);
+        }
+        true
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        self.tail.token_mut()
+    }
+}
+
+/// Tokenizer for file paths that splits on `/`, `\`, `.`, `-` and `_`
+#[derive(Clone)]
+struct PathTokenizer;
+
+impl tantivy::tokenizer::Tokenizer for PathTokenizer {
+    type TokenStream<'a> = PathTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        PathTokenStream {
+            text,
+            chars: text.char_indices().peekable(),
+            token: tantivy::tokenizer::Token::default(),
+        }
+    }
+}
+
+struct PathTokenStream<'a> {
+    text: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    token: tantivy::tokenizer::Token,
+}
+
+impl PathTokenStream<'_> {
+    fn is_separator(c: char) -> bool {
+        matches!(c, '/' | '\\' | '.' | '-' | '_') || c.is_whitespace()
+    }
+}
+
+impl<'a> tantivy::tokenizer::TokenStream for PathTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        self.token.text.clear();
+
+        // Skip separators
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !Self::is_separator(c) {
+                break;
+            }
+            self.chars.next();
+        }
+
+        let start = match self.chars.peek() {
+            Some(&(pos, _)) => pos,
+            None => return false,
+        };
+
+        let mut end = start;
+        while let Some(&(pos, c)) = self.chars.peek() {
+            if Self::is_separator(c) {
+                break;
+            }
+            end = pos + c.len_utf8();
+            self.chars.next();
+        }
+
+        self.token.position = self.token.position.wrapping_add(1);
+        self.token.offset_from = start;
+        self.token.offset_to = end;
+        self.token.text.push_str(&self.text[start..end]);
+        true
+    }
+
+    fn token(&self) -> &tantivy::tokenizer::Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut tantivy::tokenizer::Token {
+        &mut self.token
+    }
 }
 
 /// Custom tokenizer for code that preserves $, @, #, etc.
+///
+/// Member access chains (`config.get`, `Foo::bar`, `$this->x`) are indexed as the whole
+/// chain followed by each part, with the chain at the first part's position, so BM25 can
+/// match `object.property` as a unit while phrase queries over the parts still work.
+///
+/// Compound identifiers (`getUserProfile`, `user_profile`, `HTTPServer`) are indexed whole
+/// and then as their words, all at the same position, so both `getUserProfile` and
+/// `profile` find them without shifting the positions phrase queries rely on.
 #[derive(Clone)]
 struct CodeTokenizer;
 
@@ -28,6 +205,9 @@ impl tantivy::tokenizer::Tokenizer for CodeTokenizer {
             text,
             chars: text.char_indices().peekable(),
             token: tantivy::tokenizer::Token::default(),
+            chain_end: 0,
+            hold_position: false,
+            subwords: Vec::new(),
         }
     }
 }
@@ -36,12 +216,111 @@ struct CodeTokenStream<'a> {
     text: &'a str,
     chars: std::iter::Peekable<std::str::CharIndices<'a>>,
     token: tantivy::tokenizer::Token,
+    /// End offset of the member access chain whose parts are being emitted
+    chain_end: usize,
+    /// The chain token was just emitted, so its first part reuses the position
+    hold_position: bool,
+    /// Word offsets of the last identifier still to emit at its position, last word first
+    subwords: Vec<(usize, usize)>,
+}
+
+/// Separators joining the parts of a member access chain
+const CHAIN_SEPARATORS: [&str; 3] = [".", "::", "->"];
+
+fn is_code_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '$' | '@' | '#' | '-')
+}
+
+/// Length of the identifier at the start of `text` (a `-` starting `->` ends it)
+fn identifier_len(text: &str) -> usize {
+    let mut chars = text.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let arrow = c == '-' && chars.peek().is_some_and(|&(_, next)| next == '>');
+        if !is_code_char(c) || arrow {
+            return pos;
+        }
+    }
+    text.len()
+}
+
+/// Offsets of the words in a compound identifier (`getUserProfile`, `user_profile`,
+/// `HTTPServer`), or nothing if it's a single word
+fn subword_ranges(identifier: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    let mut chars = identifier.char_indices().peekable();
+    let mut prev: Option<char> = None;
+    while let Some((pos, c)) = chars.next() {
+        if !c.is_alphanumeric() {
+            if let Some(from) = start.take() {
+                ranges.push((from, pos));
+            }
+            prev = None;
+            continue;
+        }
+        // A new word starts at `aB`, `1B`, or at the last capital of an acronym (`HTTPServer`)
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_numeric()
+                    || (p.is_uppercase() && chars.peek().is_some_and(|&(_, next)| next.is_lowercase()))
+            });
+        if boundary {
+            if let Some(from) = start.take() {
+                ranges.push((from, pos));
+            }
+        }
+        start.get_or_insert(pos);
+        prev = Some(c);
+    }
+    if let Some(from) = start {
+        ranges.push((from, identifier.len()));
+    }
+
+    if ranges.len() > 1 {
+        ranges
+    } else {
+        Vec::new()
+    }
+}
+
+/// End offset of a member access chain starting at `start` (e.g. `a.b.c`), if there is one
+fn chain_end(text: &str, start: usize) -> Option<usize> {
+    let mut end = start + identifier_len(&text[start..]);
+    if end == start {
+        return None;
+    }
+    let mut parts = 1;
+    'parts: loop {
+        for separator in CHAIN_SEPARATORS {
+            let Some(rest) = text[end..].strip_prefix(separator) else { continue };
+            let len = identifier_len(rest);
+            if len > 0 {
+                end += separator.len() + len;
+                parts += 1;
+                continue 'parts;
+            }
+        }
+        break;
+    }
+    (parts > 1).then_some(end)
 }
 
 impl<'a> tantivy::tokenizer::TokenStream for CodeTokenStream<'a> {
     fn advance(&mut self) -> bool {
         self.token.text.clear();
-        self.token.position = self.token.position.wrapping_add(1);
+
+        // Words of the last identifier share its position
+        if let Some((start, end)) = self.subwords.pop() {
+            self.token.offset_from = start;
+            self.token.offset_to = end;
+            self.token.text.push_str(&self.text[start..end]);
+            return true;
+        }
+
+        if !std::mem::take(&mut self.hold_position) {
+            self.token.position = self.token.position.wrapping_add(1);
+        }
 
         // Skip whitespace
         while let Some(&(_, c)) = self.chars.peek() {
@@ -56,10 +335,23 @@ impl<'a> tantivy::tokenizer::TokenStream for CodeTokenStream<'a> {
             None => return false,
         };
 
-        // Collect token: alphanumeric + code chars ($, @, #, _, -)
+        // Emit a whole member access chain before its parts
+        if start >= self.chain_end {
+            if let Some(end) = chain_end(self.text, start) {
+                self.chain_end = end;
+                self.hold_position = true;
+                self.token.offset_from = start;
+                self.token.offset_to = end;
+                self.token.text.push_str(&self.text[start..end]);
+                return true;
+            }
+        }
+
+        // Collect token: alphanumeric + code chars ($, @, #, _, -), but not the `-` of `->`
         let mut end = start;
+        let identifier_end = start + identifier_len(&self.text[start..]);
         while let Some(&(pos, c)) = self.chars.peek() {
-            if c.is_alphanumeric() || c == '_' || c == '$' || c == '@' || c == '#' || c == '-' {
+            if pos < identifier_end {
                 end = pos + c.len_utf8();
                 self.chars.next();
             } else if c.is_whitespace() {
@@ -80,6 +372,11 @@ impl<'a> tantivy::tokenizer::TokenStream for CodeTokenStream<'a> {
             self.token.offset_from = start;
             self.token.offset_to = end;
             self.token.text.push_str(&self.text[start..end]);
+            self.subwords = subword_ranges(&self.text[start..end])
+                .into_iter()
+                .rev()
+                .map(|(from, to)| (start + from, start + to))
+                .collect();
             true
         } else {
             false
@@ -99,15 +396,19 @@ impl<'a> tantivy::tokenizer::TokenStream for CodeTokenStream<'a> {
 pub mod fields {
     pub const DOC_ID: &str = "doc_id";
     pub const PATH: &str = "path";
+    pub const PATH_TEXT: &str = "path_text";
     pub const WORKSPACE: &str = "workspace";
     pub const CONTENT: &str = "content";
     pub const MTIME: &str = "mtime";
     pub const SIZE: &str = "size";
     pub const EXTENSION: &str = "extension";
+    pub const LANGUAGE: &str = "language";
     pub const LINE_START: &str = "line_start";
     pub const LINE_END: &str = "line_end";
     pub const CHUNK_ID: &str = "chunk_id";
     pub const PARENT_DOC: &str = "parent_doc";
+    pub const DEF_LINES: &str = "def_lines";
+    pub const COPY: &str = "copy";
 }
 
 /// Build the Tantivy schema for document indexing
@@ -129,10 +430,22 @@ pub fn build_document_schema() -> Schema {
     schema_builder.add_text_field(fields::PATH, STRING | STORED);
     schema_builder.add_text_field(fields::WORKSPACE, STRING | STORED);
 
+    // Tokenized copy of the path, so words in file and directory names are searchable
+    // (`path` stays untokenized: it's the exact key for deletes and `path:` substring search)
+    let path_text_options = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(PATH_TOKENIZER)
+            .set_index_option(IndexRecordOption::WithFreqs),
+    );
+    schema_builder.add_text_field(fields::PATH_TEXT, path_text_options);
+
     // File metadata
     schema_builder.add_u64_field(fields::MTIME, FAST | STORED);
     schema_builder.add_u64_field(fields::SIZE, FAST | STORED);
     schema_builder.add_text_field(fields::EXTENSION, STRING | STORED);
+    // Detected language (see `index::language`), on chunks too; a fast field so it can be
+    // filtered on without loading stored documents
+    schema_builder.add_text_field(fields::LANGUAGE, STRING | STORED | FAST);
 
     // Content for full-text search
     schema_builder.add_text_field(fields::CONTENT, text_options);
@@ -145,23 +458,47 @@ pub fn build_document_schema() -> Schema {
     schema_builder.add_text_field(fields::CHUNK_ID, STRING | STORED);
     schema_builder.add_text_field(fields::PARENT_DOC, STRING | STORED);
 
+    // Lines that look like definitions (one value per line number, whole files only)
+    schema_builder.add_u64_field(fields::DEF_LINES, STORED);
+
+    // Set on files with the same content as an indexed file (see `content_hash_dedup`): they
+    // keep their path and metadata but no content, so one can be indexed in full if that file goes
+    schema_builder.add_bool_field(fields::COPY, INDEXED | STORED);
+
     schema_builder.build()
 }
 
+/// Fingerprint of the document schema, tokenizer version and token length limit, stored in
+/// workspace.json so indexes built with a different layout can be detected on open
+pub fn schema_hash(max_token_chars: usize) -> String {
+    use xxhash_rust::xxh3::xxh3_64;
+
+    let schema_json = serde_json::to_string(&build_document_schema()).unwrap_or_default();
+    let fingerprint = format!(
+        "{}:{}:{}:{}:{}",
+        SCHEMA_VERSION, CODE_TOKENIZER, PATH_TOKENIZER, max_token_chars, schema_json
+    );
+    format!("{:016x}", xxh3_64(fingerprint.as_bytes()))
+}
+
 /// Schema field handles for efficient access
 #[derive(Clone)]
 pub struct SchemaFields {
     pub doc_id: tantivy::schema::Field,
     pub path: tantivy::schema::Field,
+    pub path_text: tantivy::schema::Field,
     pub workspace: tantivy::schema::Field,
     pub content: tantivy::schema::Field,
     pub mtime: tantivy::schema::Field,
     pub size: tantivy::schema::Field,
     pub extension: tantivy::schema::Field,
+    pub language: tantivy::schema::Field,
     pub line_start: tantivy::schema::Field,
     pub line_end: tantivy::schema::Field,
     pub chunk_id: tantivy::schema::Field,
     pub parent_doc: tantivy::schema::Field,
+    pub def_lines: tantivy::schema::Field,
+    pub copy: tantivy::schema::Field,
 }
 
 impl SchemaFields {
@@ -169,15 +506,19 @@ impl SchemaFields {
         Self {
             doc_id: schema.get_field(fields::DOC_ID).unwrap(),
             path: schema.get_field(fields::PATH).unwrap(),
+            path_text: schema.get_field(fields::PATH_TEXT).unwrap(),
             workspace: schema.get_field(fields::WORKSPACE).unwrap(),
             content: schema.get_field(fields::CONTENT).unwrap(),
             mtime: schema.get_field(fields::MTIME).unwrap(),
             size: schema.get_field(fields::SIZE).unwrap(),
             extension: schema.get_field(fields::EXTENSION).unwrap(),
+            language: schema.get_field(fields::LANGUAGE).unwrap(),
             line_start: schema.get_field(fields::LINE_START).unwrap(),
             line_end: schema.get_field(fields::LINE_END).unwrap(),
             chunk_id: schema.get_field(fields::CHUNK_ID).unwrap(),
             parent_doc: schema.get_field(fields::PARENT_DOC).unwrap(),
+            def_lines: schema.get_field(fields::DEF_LINES).unwrap(),
+            copy: schema.get_field(fields::COPY).unwrap(),
         }
     }
 }
@@ -200,4 +541,96 @@ mod tests {
         let _ = fields.doc_id;
         let _ = fields.content;
     }
+
+    #[test]
+    fn test_path_tokenizer() {
+        let manager = TokenizerManager::default();
+        register_tokenizers(&manager);
+        let mut analyzer = manager.get(PATH_TOKENIZER).unwrap();
+
+        let mut tokens = Vec::new();
+        analyzer
+            .token_stream("src/Auth/request-handler_v2.rs")
+            .process(&mut |token| tokens.push(token.text.clone()));
+        assert_eq!(tokens, ["src", "auth", "request", "handler", "v2", "rs"]);
+    }
+
+    #[test]
+    fn test_code_tokenizer_unicode() {
+        let manager = TokenizerManager::default();
+        register_tokenizers(&manager);
+        let mut analyzer = manager.get(CODE_TOKENIZER).unwrap();
+        let mut tokenize = |text: &str| {
+            let mut tokens = Vec::new();
+            analyzer.token_stream(text).process(&mut |token| tokens.push(token.text.clone()));
+            tokens
+        };
+
+        assert_eq!(tokenize("let 数値 = Résumé;"), ["let", "数値", "résumé"]);
+        assert_eq!(tokenize("var متغير_عدد = 1"), ["var", "متغير_عدد", "متغير", "عدد", "1"]);
+        // Emoji aren't identifier characters, so they split the identifier
+        assert_eq!(tokenize("fn 🦀crab_👍ok()"), ["fn", "crab_", "ok"]);
+
+        // The length limit counts characters: 200 CJK characters (600 bytes) are kept whole,
+        // longer tokens are cut to the limit
+        let long = "値".repeat(200);
+        assert_eq!(tokenize(&long), vec![long]);
+        assert_eq!(tokenize(&"値".repeat(300)), vec!["値".repeat(DEFAULT_MAX_TOKEN_CHARS)]);
+    }
+
+    #[test]
+    fn test_code_tokenizer_dotted_identifiers() {
+        let manager = TokenizerManager::default();
+        register_tokenizers(&manager);
+        let mut analyzer = manager.get(CODE_TOKENIZER).unwrap();
+        let mut tokenize = |text: &str| {
+            let mut tokens = Vec::new();
+            analyzer
+                .token_stream(text)
+                .process(&mut |token| tokens.push((token.text.clone(), token.position)));
+            tokens
+        };
+        let texts = |tokens: Vec<(String, usize)>| tokens.into_iter().map(|(text, _)| text).collect::<Vec<_>>();
+
+        assert_eq!(texts(tokenize("a.b.c")), ["a.b.c", "a", "b", "c"]);
+        assert_eq!(texts(tokenize("Foo::bar()")), ["foo::bar", "foo", "bar"]);
+        assert_eq!(texts(tokenize("$this->x = 1;")), ["$this->x", "$this", "x", "1"]);
+        // A trailing dot isn't part of the chain
+        assert_eq!(texts(tokenize("end. next")), ["end", "next"]);
+
+        // The chain shares the first part's position, so phrases over the parts still match
+        let tokens = tokenize("return config.get(key)");
+        let positions: Vec<_> = tokens.iter().map(|(text, position)| (text.as_str(), *position)).collect();
+        assert_eq!(positions, [("return", 0), ("config.get", 1), ("config", 1), ("get", 2), ("key", 3)]);
+    }
+
+    #[test]
+    fn test_code_tokenizer_compound_identifiers() {
+        let manager = TokenizerManager::default();
+        register_tokenizers(&manager);
+        let mut analyzer = manager.get(CODE_TOKENIZER).unwrap();
+        let mut tokenize = |text: &str| {
+            let mut tokens = Vec::new();
+            analyzer
+                .token_stream(text)
+                .process(&mut |token| tokens.push((token.text.clone(), token.position)));
+            tokens
+        };
+
+        // The whole identifier comes first, then its words at the same position
+        let tokens = tokenize("return getUserProfile(id)");
+        let positions: Vec<_> = tokens.iter().map(|(text, position)| (text.as_str(), *position)).collect();
+        assert_eq!(positions, [
+            ("return", 0),
+            ("getuserprofile", 1), ("get", 1), ("user", 1), ("profile", 1),
+            ("id", 2),
+        ]);
+
+        let mut texts = |text: &str| tokenize(text).into_iter().map(|(text, _)| text).collect::<Vec<_>>();
+        assert_eq!(texts("user_profile"), ["user_profile", "user", "profile"]);
+        assert_eq!(texts("HTTPServer"), ["httpserver", "http", "server"]);
+        assert_eq!(texts("parseUtf8Bytes"), ["parseutf8bytes", "parse", "utf8", "bytes"]);
+        // Single words aren't repeated
+        assert_eq!(texts("$user Config _private"), ["$user", "config", "_private"]);
+    }
 }
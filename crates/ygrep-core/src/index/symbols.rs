@@ -0,0 +1,106 @@
+//! Lightweight detection of definition lines (functions, classes, types, constants)
+//!
+//! One regex per language family, keyed on the file extension and matched against each
+//! line with its indentation stripped. It's a heuristic for `--defs` searches, not a parser:
+//! a definition split oddly across lines can be missed, and a line that merely looks like
+//! one (e.g. `type = "x"` in some languages) can be included.
+
+use std::sync::OnceLock;
+use regex::Regex;
+
+/// Definition patterns by file extension
+const PATTERNS: &[(&[&str], &str)] = &[
+    (
+        &["rs"],
+        r#"^(pub(\([^)]*\))?\s+)?((async|const|unsafe|default|extern\s+"[^"]*")\s+)*(fn|struct|enum|trait|type|mod|union|static|const|impl)\b|^macro_rules!"#,
+    ),
+    (
+        &["py"],
+        r"^(async\s+)?(def|class)\s+\w+|^[A-Z_][A-Z0-9_]*\s*(:[^=]+)?=",
+    ),
+    (
+        &["js", "ts", "jsx", "tsx", "mjs", "mts", "cjs", "cts", "vue", "svelte"],
+        r"^(export\s+)?(default\s+)?(declare\s+)?(abstract\s+)?(async\s+)?(function\*?|class|interface|type|enum|namespace)\s+[\w$]+|^(export\s+)?(const|let|var)\s+[\w$]+\s*(:[^=]+)?=",
+    ),
+    (
+        &["go"],
+        r"^(func|type|var|const)\b",
+    ),
+    (
+        &["rb"],
+        r"^(def|class|module)\s+",
+    ),
+    (
+        &["php"],
+        r"^((abstract|final|readonly|public|private|protected|static)\s+)*(function|class|interface|trait|enum|const)\s+",
+    ),
+    (
+        &["java", "kt", "cs", "scala", "swift"],
+        r"^((public|private|protected|internal|static|final|abstract|sealed|open|override|data|case|async|partial)\s+)*(class|interface|enum|record|struct|object|trait|protocol|extension|fun|func|def|val|var|let)\s+\w+",
+    ),
+    (
+        &["c", "h", "cpp", "cc", "hpp", "hh"],
+        r"^(#define\s+\w+|(typedef\s+)?(struct|class|enum|union|namespace)\s+\w+|[\w:<>,*&\s]+[\s*&]\**[\w:~]+\s*\([^;]*$)",
+    ),
+];
+
+/// Used for extensions without their own pattern
+const GENERIC_PATTERN: &str =
+    r"^(export\s+)?(pub\s+)?(async\s+)?(fn|def|func|function|class|struct|enum|interface|trait|type|module)\s+[\w$]+";
+
+/// The compiled definition pattern for a file extension
+fn definition_regex(extension: &str) -> &'static Regex {
+    static REGEXES: OnceLock<Vec<Regex>> = OnceLock::new();
+    let regexes = REGEXES.get_or_init(|| {
+        PATTERNS
+            .iter()
+            .map(|(_, pattern)| *pattern)
+            .chain([GENERIC_PATTERN])
+            .map(|pattern| Regex::new(pattern).expect("definition patterns are valid"))
+            .collect()
+    });
+
+    let extension = extension.to_lowercase();
+    let index = PATTERNS
+        .iter()
+        .position(|(extensions, _)| extensions.contains(&extension.as_str()))
+        .unwrap_or(PATTERNS.len());
+    &regexes[index]
+}
+
+/// Whether a line looks like a definition in a file with this extension
+pub fn is_definition(line: &str, extension: &str) -> bool {
+    definition_regex(extension).is_match(line.trim_start())
+}
+
+/// Line numbers (1-based) of the lines that look like definitions
+pub fn definition_lines(content: &str, extension: &str) -> Vec<u64> {
+    let regex = definition_regex(extension);
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line.trim_start()))
+        .map(|(i, _)| i as u64 + 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definition_lines() {
+        let rust = "use std::fs;\n\npub fn parse_config() {\n    let config = load();\n}\n\npub(crate) struct Config;\nimpl Config {}\n";
+        assert_eq!(definition_lines(rust, "rs"), [3, 7, 8]);
+
+        let python = "import os\nMAX_SIZE = 10\n\nclass Parser:\n    async def parse(self):\n        parse_config()\n";
+        assert_eq!(definition_lines(python, "py"), [2, 4, 5]);
+
+        let typescript = "export const parseConfig = () => {};\nparseConfig();\nexport default class App {}\ninterface Props {}\n";
+        assert_eq!(definition_lines(typescript, "ts"), [1, 3, 4]);
+
+        // Unknown extensions use the generic pattern
+        assert!(is_definition("  function parseConfig() {", "unknown"));
+        assert!(!is_definition("parseConfig();", "unknown"));
+    }
+}
@@ -1,21 +1,37 @@
 //! HNSW vector index for semantic search
 
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
-use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use parking_lot::{Mutex, RwLock};
 use hnsw_rs::prelude::*;
 use hnsw_rs::hnswio::HnswIo;
+use bincode::Options;
 use serde::{Deserialize, Serialize};
 
-use crate::error::{Result, YgrepError};
+use crate::error::{Result, ResultExt, YgrepError};
+use super::vector_files::{doc_ids_encoding, DocIdIndex, WalEntry, DOC_IDS_FILE, DOC_IDS_JSON_FILE, LEGACY_VECTORS_FILE, WAL_FILE};
 
 /// HNSW dump file basename
 const HNSW_BASENAME: &str = "hnsw";
 
-/// Compact doc_id index (fast to load)
-#[derive(Debug, Serialize, Deserialize)]
-struct DocIdIndex {
+/// Previous successful save, kept for recovery if the current files are corrupt
+const HNSW_BACKUP_BASENAME: &str = "hnsw.bak";
+const DOC_IDS_BACKUP_FILE: &str = "doc_ids.bin.bak";
+
+/// In-progress save, renamed into place once fully written
+const HNSW_TMP_BASENAME: &str = "hnsw.tmp";
+const DOC_IDS_TMP_FILE: &str = "doc_ids.bin.tmp";
+
+/// Logged changes after which `save` writes a fresh snapshot instead of appending
+const WAL_COMPACT_ENTRIES: usize = 1000;
+
+/// Borrowed view of [`DocIdIndex`] so saving doesn't copy every doc_id
+#[derive(Serialize)]
+struct DocIdIndexRef<'a> {
     dimension: usize,
-    doc_ids: Vec<String>,
+    doc_ids: &'a [String],
 }
 
 /// Stored vector with its document ID (legacy format)
@@ -33,12 +49,27 @@ struct VectorData {
 }
 
 /// HNSW vector index for storing and searching embeddings
+///
+/// `save` appends changes since the last save to a write-ahead log, which `load`
+/// replays on top of the snapshot; the snapshot is only rewritten by `compact`,
+/// once the log grows long, or after `clear`.
+///
+/// Embeddings are only held by the HNSW graph (one allocation per point); besides it, the
+/// index keeps just the doc_ids, so there is no second copy of the vectors to compact.
 pub struct VectorIndex {
     path: PathBuf,
     hnsw: RwLock<Hnsw<'static, f32, DistCosine>>,
     dimension: usize,
     /// Document IDs (index matches HNSW point ID)
     doc_ids: RwLock<Vec<String>>,
+    /// Changes not yet written to the log
+    pending: Mutex<Vec<WalEntry>>,
+    /// Changes already in the log file
+    wal_entries: AtomicUsize,
+    /// Points in the snapshot on disk, which the log applies to
+    snapshot_points: AtomicUsize,
+    /// Set when the next save must write a full snapshot (new or cleared index)
+    needs_snapshot: AtomicBool,
 }
 
 impl VectorIndex {
@@ -59,40 +90,65 @@ impl VectorIndex {
             DistCosine {},
         );
 
-        Ok(Self {
+        Ok(Self::from_parts(path, hnsw, dimension, Vec::new(), true))
+    }
+
+    fn from_parts(
+        path: PathBuf,
+        hnsw: Hnsw<'static, f32, DistCosine>,
+        dimension: usize,
+        doc_ids: Vec<String>,
+        needs_snapshot: bool,
+    ) -> Self {
+        Self {
             path,
             hnsw: RwLock::new(hnsw),
             dimension,
-            doc_ids: RwLock::new(Vec::new()),
-        })
+            snapshot_points: AtomicUsize::new(doc_ids.len()),
+            doc_ids: RwLock::new(doc_ids),
+            pending: Mutex::new(Vec::new()),
+            wal_entries: AtomicUsize::new(0),
+            needs_snapshot: AtomicBool::new(needs_snapshot),
+        }
     }
 
-    /// Load an existing vector index
+    /// Load an existing vector index, replaying changes logged since its snapshot
+    /// Falls back to the backup from the previous save if the current files are corrupt
     pub fn load(path: PathBuf) -> Result<Self> {
-        // Try fast path: load from doc_ids.json + HNSW dump
-        let doc_ids_path = path.join("doc_ids.json");
-        let hnsw_graph = path.join(format!("{}.hnsw.graph", HNSW_BASENAME));
+        let index = Self::load_snapshot(path)?;
+        index.replay_wal()?;
+        Ok(index)
+    }
 
-        if doc_ids_path.exists() && hnsw_graph.exists() {
-            // Fast path: load compact doc_id index + HNSW dump
-            let doc_index: DocIdIndex = serde_json::from_reader(
-                std::fs::File::open(&doc_ids_path)?
-            ).map_err(|e| YgrepError::Config(format!("Failed to load doc_id index: {}", e)))?;
+    /// Load the last snapshot, without the write-ahead log
+    fn load_snapshot(path: PathBuf) -> Result<Self> {
+        // Fast path: load compact doc_id index + HNSW dump
+        let mut current = Self::load_dump(&path, DOC_IDS_FILE, HNSW_BASENAME);
+        if let Ok(Some(index)) = current {
+            return Ok(index);
+        }
 
-            let reloader = Box::leak(Box::new(HnswIo::new(&path, HNSW_BASENAME)));
-            let hnsw = reloader.load_hnsw::<f32, DistCosine>()
-                .map_err(|e| YgrepError::Config(format!("Failed to load HNSW index: {}", e)))?;
+        // Indexes saved before the switch to bincode
+        if let Ok(None) = current {
+            current = Self::load_dump(&path, DOC_IDS_JSON_FILE, HNSW_BASENAME);
+            if let Ok(Some(index)) = current {
+                return Ok(index);
+            }
+        }
 
-            return Ok(Self {
-                path,
-                hnsw: RwLock::new(hnsw),
-                dimension: doc_index.dimension,
-                doc_ids: RwLock::new(doc_index.doc_ids),
-            });
+        match Self::load_dump(&path, DOC_IDS_BACKUP_FILE, HNSW_BACKUP_BASENAME) {
+            Ok(Some(index)) => {
+                tracing::warn!("Vector index at {} was incomplete, recovered previous save", path.display());
+                return Ok(index);
+            }
+            Ok(None) | Err(_) => {
+                // No usable backup - report the original corruption
+                current?;
+            }
         }
 
         // Fallback: load from legacy vectors.json
-        let data_path = path.join("vectors.json");
+        let data_path = path.join(LEGACY_VECTORS_FILE);
         if !data_path.exists() {
             return Err(YgrepError::WorkspaceNotIndexed(path.clone()));
         }
@@ -100,7 +156,7 @@ impl VectorIndex {
         // Load legacy vector data (slow but backwards compatible)
         let data: VectorData = serde_json::from_reader(
             std::fs::File::open(&data_path)?
-        ).map_err(|e| YgrepError::Config(format!("Failed to load vector data: {}", e)))?;
+        ).context("Failed to load vector data")?;
 
         // Extract doc_ids from vectors
         let doc_ids: Vec<String> = data.vectors.iter().map(|sv| sv.doc_id.clone()).collect();
@@ -111,30 +167,141 @@ impl VectorIndex {
             hnsw.insert((&sv.vector, id));
         }
 
-        Ok(Self {
-            path,
-            hnsw: RwLock::new(hnsw),
-            dimension: data.dimension,
-            doc_ids: RwLock::new(doc_ids),
-        })
+        // Converted to the current format on the next save
+        Ok(Self::from_parts(path, hnsw, data.dimension, doc_ids, true))
+    }
+
+    /// Load a doc_id index + HNSW dump pair, or None if the files don't exist
+    fn load_dump(path: &Path, doc_ids_file: &str, hnsw_basename: &str) -> Result<Option<Self>> {
+        let doc_ids_path = path.join(doc_ids_file);
+        let hnsw_graph = path.join(format!("{}.hnsw.graph", hnsw_basename));
+
+        if !doc_ids_path.exists() || !hnsw_graph.exists() {
+            return Ok(None);
+        }
+
+        let reader = std::io::BufReader::new(std::fs::File::open(&doc_ids_path)?);
+        let doc_index: DocIdIndex = if doc_ids_file == DOC_IDS_JSON_FILE {
+            serde_json::from_reader(reader)
+                .context("Failed to load doc_id index")?
+        } else {
+            doc_ids_encoding().deserialize_from(reader)
+                .context("Failed to load doc_id index")?
+        };
+
+        let reloader = Box::leak(Box::new(HnswIo::new(path, hnsw_basename)));
+        let hnsw = reloader.load_hnsw::<f32, DistCosine>()
+            .context("Failed to load HNSW index")?;
+
+        // A crash between renames can pair doc_ids with a graph from another save
+        if hnsw.get_nb_point() != doc_index.doc_ids.len() {
+            return Err(YgrepError::Config(format!(
+                "Vector index mismatch: {} points but {} doc_ids",
+                hnsw.get_nb_point(), doc_index.doc_ids.len()
+            ).into()));
+        }
+
+        // JSON doc_id indexes are migrated to bincode by the next save
+        let needs_snapshot = doc_ids_file == DOC_IDS_JSON_FILE;
+        Ok(Some(Self::from_parts(path.to_path_buf(), hnsw, doc_index.dimension, doc_index.doc_ids, needs_snapshot)))
+    }
+
+    /// Apply the write-ahead log on top of the loaded snapshot
+    ///
+    /// A log written against a different snapshot (e.g. a crash during compaction,
+    /// or a recovered backup) is ignored. A partly written entry (a crash mid-append) is cut
+    /// off the file, so later saves append after the last complete entry.
+    fn replay_wal(&self) -> Result<()> {
+        let wal_path = self.path.join(WAL_FILE);
+        let file = match std::fs::File::open(&wal_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut reader = std::io::BufReader::new(file);
+        let mut line = Vec::new();
+        let mut entries = 0;
+        // End of the last complete entry
+        let mut valid_len = 0u64;
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+
+            let entry = match line.strip_suffix(b"\n").map(serde_json::from_slice::<WalEntry>) {
+                Some(Ok(entry)) => entry,
+                torn => {
+                    if let Some(Err(e)) = torn {
+                        tracing::warn!("Ignoring unreadable vector log entry in {}: {}", wal_path.display(), e);
+                    }
+                    drop(reader);
+                    std::fs::OpenOptions::new().write(true).open(&wal_path)?.set_len(valid_len)?;
+                    break;
+                }
+            };
+
+            match entry {
+                WalEntry::Base { points } => {
+                    if points != self.doc_ids.read().len() {
+                        tracing::warn!("Ignoring vector log for another snapshot in {}", wal_path.display());
+                        self.needs_snapshot.store(true, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                }
+                WalEntry::Insert { doc_id, vector } => {
+                    self.insert_unlogged(&doc_id, &vector)?;
+                }
+                WalEntry::Delete { doc_id } => {
+                    self.remove_unlogged(&doc_id);
+                }
+            }
+            entries += 1;
+            valid_len += read as u64;
+        }
+
+        self.wal_entries.store(entries, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Queue a change for the next `save` (not needed while a full snapshot is due)
+    fn log(&self, pending: &mut Vec<WalEntry>, entry: WalEntry) {
+        if !self.needs_snapshot.load(Ordering::Relaxed) {
+            pending.push(entry);
+        }
     }
 
     /// Check if a vector index exists at the path
     pub fn exists(path: &Path) -> bool {
-        // Check for new format (doc_ids.json + HNSW dump) or legacy format (vectors.json)
-        let new_format = path.join("doc_ids.json").exists()
-            && path.join(format!("{}.hnsw.graph", HNSW_BASENAME)).exists();
-        let legacy_format = path.join("vectors.json").exists();
+        // Check for new format (doc_ids + HNSW dump), its backup, or legacy format (vectors.json)
+        let dump_exists = |doc_ids_file: &str, hnsw_basename: &str| {
+            path.join(doc_ids_file).exists()
+                && path.join(format!("{}.hnsw.graph", hnsw_basename)).exists()
+        };
+        let new_format = dump_exists(DOC_IDS_FILE, HNSW_BASENAME)
+            || dump_exists(DOC_IDS_JSON_FILE, HNSW_BASENAME)
+            || dump_exists(DOC_IDS_BACKUP_FILE, HNSW_BACKUP_BASENAME);
+        let legacy_format = path.join(LEGACY_VECTORS_FILE).exists();
         new_format || legacy_format
     }
 
     /// Insert an embedding and return its ID
     pub fn insert(&self, doc_id: &str, embedding: &[f32]) -> Result<u64> {
+        // Held throughout, so a concurrent save never sees the change without its log entry
+        let mut pending = self.pending.lock();
+        let id = self.insert_unlogged(doc_id, embedding)?;
+        self.log(&mut pending, WalEntry::Insert { doc_id: doc_id.to_string(), vector: embedding.to_vec() });
+        Ok(id)
+    }
+
+    fn insert_unlogged(&self, doc_id: &str, embedding: &[f32]) -> Result<u64> {
         if embedding.len() != self.dimension {
             return Err(YgrepError::Config(format!(
                 "Embedding dimension mismatch: expected {}, got {}",
                 self.dimension, embedding.len()
-            )));
+            ).into()));
         }
 
         let mut doc_ids = self.doc_ids.write();
@@ -145,11 +312,20 @@ impl VectorIndex {
 
         // Insert into HNSW
         let hnsw = self.hnsw.write();
-        hnsw.insert((&embedding.to_vec(), id));
+        hnsw.insert((embedding, id));
 
         Ok(id as u64)
     }
 
+    /// Insert an embedding, replacing any existing one for the same doc_id
+    ///
+    /// HNSW points can't be removed, so the old point is tombstoned (its doc_id
+    /// cleared) and skipped by `search`.
+    pub fn upsert(&self, doc_id: &str, embedding: &[f32]) -> Result<u64> {
+        self.remove(doc_id);
+        self.insert(doc_id, embedding)
+    }
+
     /// Search for similar vectors
     ///
     /// Returns (vector_id, distance, doc_id) tuples, sorted by distance (ascending)
@@ -158,7 +334,7 @@ impl VectorIndex {
             return Err(YgrepError::Config(format!(
                 "Query dimension mismatch: expected {}, got {}",
                 self.dimension, query.len()
-            )));
+            ).into()));
         }
 
         let hnsw = self.hnsw.read();
@@ -175,38 +351,152 @@ impl VectorIndex {
         Ok(neighbors
             .into_iter()
             .filter_map(|n| {
-                doc_ids.get(n.d_id).map(|doc_id| {
-                    (n.d_id as u64, n.distance, doc_id.clone())
-                })
+                doc_ids.get(n.d_id)
+                    .filter(|doc_id| !doc_id.is_empty()) // Tombstoned by upsert
+                    .map(|doc_id| (n.d_id as u64, n.distance, doc_id.clone()))
             })
             .collect())
     }
 
-    /// Save the index to disk
+    /// Save changes since the last save to disk
+    ///
+    /// Changes are appended to the write-ahead log, so a save costs O(changes) rather
+    /// than O(index size). A full snapshot is written instead for new or cleared
+    /// indexes and once the log reaches `WAL_COMPACT_ENTRIES` entries.
     pub fn save(&self) -> Result<()> {
-        // Save compact doc_id index (fast to load)
-        let doc_ids_path = self.path.join("doc_ids.json");
+        let mut pending = self.pending.lock();
+        let wal_entries = self.wal_entries.load(Ordering::Relaxed);
+        if self.needs_snapshot.load(Ordering::Relaxed) || wal_entries + pending.len() >= WAL_COMPACT_ENTRIES {
+            drop(pending);
+            return self.compact();
+        }
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let wal_path = self.path.join(WAL_FILE);
+        let mut writer = std::io::BufWriter::new(
+            std::fs::OpenOptions::new().create(true).append(true).open(&wal_path)?
+        );
+        let mut lines = Vec::with_capacity(pending.len() + 1);
+        if wal_entries == 0 {
+            // Tie the log to the snapshot it applies to
+            lines.push(WalEntry::Base { points: self.snapshot_points.load(Ordering::Relaxed) });
+        }
+        lines.append(&mut pending);
+
+        for entry in &lines {
+            serde_json::to_writer(&mut writer, entry)
+                .context("Failed to write vector log")?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+
+        self.wal_entries.store(wal_entries + lines.len(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Write a full snapshot of the index and truncate the write-ahead log
+    ///
+    /// Files are written under temporary names and renamed into place, keeping the
+    /// previous save as a backup, so a crash mid-save never leaves only a truncated index.
+    pub fn compact(&self) -> Result<()> {
+        // Changes made from here on are logged against the new snapshot
+        let mut pending = self.pending.lock();
+
+        // Write compact doc_id index (fast to load), streamed straight from the borrowed doc_ids
         let doc_ids = self.doc_ids.read();
-        let doc_index = DocIdIndex {
+        let doc_index = DocIdIndexRef {
             dimension: self.dimension,
-            doc_ids: doc_ids.clone(),
+            doc_ids: &doc_ids,
         };
-        serde_json::to_writer(
-            std::fs::File::create(&doc_ids_path)?,
-            &doc_index,
-        ).map_err(|e| YgrepError::Config(format!("Failed to save doc_id index: {}", e)))?;
+        let tmp_doc_ids = self.path.join(DOC_IDS_TMP_FILE);
+        {
+            let mut writer = std::io::BufWriter::new(std::fs::File::create(&tmp_doc_ids)?);
+            doc_ids_encoding().serialize_into(&mut writer, &doc_index)
+                .context("Failed to save doc_id index")?;
+            std::io::Write::flush(&mut writer)?;
+            writer.get_ref().sync_all()?;
+        }
 
-        // Save HNSW graph for fast loading
+        // Write HNSW graph for fast loading
         let hnsw = self.hnsw.read();
-        hnsw.file_dump(&self.path, HNSW_BASENAME)
-            .map_err(|e| YgrepError::Config(format!("Failed to save HNSW index: {}", e)))?;
+        let tmp_basename = hnsw.file_dump(&self.path, HNSW_TMP_BASENAME)
+            .context("Failed to save HNSW index")?;
+
+        // Keep the previous save as a backup, then move the new files into place
+        // (doc_ids last, so a complete doc_ids.json always has its graph beside it)
+        for ext in ["hnsw.graph", "hnsw.data"] {
+            let current = self.path.join(format!("{}.{}", HNSW_BASENAME, ext));
+            if current.exists() {
+                std::fs::rename(&current, self.path.join(format!("{}.{}", HNSW_BACKUP_BASENAME, ext)))?;
+            }
+            std::fs::rename(self.path.join(format!("{}.{}", tmp_basename, ext)), &current)?;
+        }
+
+        let current_doc_ids = self.path.join(DOC_IDS_FILE);
+        if current_doc_ids.exists() {
+            std::fs::rename(&current_doc_ids, self.path.join(DOC_IDS_BACKUP_FILE))?;
+        }
+        std::fs::rename(&tmp_doc_ids, &current_doc_ids)?;
+
+        // Drop the JSON doc_id index once migrated, so it can't be paired with a newer graph
+        let legacy_doc_ids = self.path.join(DOC_IDS_JSON_FILE);
+        if legacy_doc_ids.exists() {
+            std::fs::remove_file(legacy_doc_ids)?;
+        }
+
+        // The snapshot now includes everything the log recorded
+        let wal_path = self.path.join(WAL_FILE);
+        if wal_path.exists() {
+            std::fs::remove_file(wal_path)?;
+        }
+        pending.clear();
+        self.snapshot_points.store(doc_ids.len(), Ordering::Relaxed);
+        self.wal_entries.store(0, Ordering::Relaxed);
+        self.needs_snapshot.store(false, Ordering::Relaxed);
 
         Ok(())
     }
 
-    /// Get the number of vectors in the index
+    /// Remove a document's embedding (tombstoned like in `upsert`)
+    /// Returns whether the document had one
+    pub fn remove(&self, doc_id: &str) -> bool {
+        let mut pending = self.pending.lock();
+        let removed = self.remove_unlogged(doc_id);
+        if removed {
+            self.log(&mut pending, WalEntry::Delete { doc_id: doc_id.to_string() });
+        }
+        removed
+    }
+
+    fn remove_unlogged(&self, doc_id: &str) -> bool {
+        if doc_id.is_empty() {
+            return false;
+        }
+
+        let mut removed = false;
+        for existing in self.doc_ids.write().iter_mut().filter(|id| id.as_str() == doc_id) {
+            existing.clear();
+            removed = true;
+        }
+        removed
+    }
+
+    /// Check if a document already has a live embedding
+    pub fn contains(&self, doc_id: &str) -> bool {
+        !doc_id.is_empty() && self.doc_ids.read().iter().any(|id| id == doc_id)
+    }
+
+    /// IDs of all documents with a live embedding
+    pub fn doc_ids(&self) -> HashSet<String> {
+        self.doc_ids.read().iter().filter(|id| !id.is_empty()).cloned().collect()
+    }
+
+    /// Get the number of live vectors in the index
     pub fn len(&self) -> usize {
-        self.doc_ids.read().len()
+        self.doc_ids.read().iter().filter(|id| !id.is_empty()).count()
     }
 
     /// Check if the index is empty
@@ -221,6 +511,11 @@ impl VectorIndex {
 
     /// Clear the index
     pub fn clear(&self) {
+        // Everything is re-added, so the next save writes a fresh snapshot
+        let mut pending = self.pending.lock();
+        pending.clear();
+        self.needs_snapshot.store(true, Ordering::Relaxed);
+
         let mut hnsw = self.hnsw.write();
         *hnsw = Hnsw::new(16, 10_000, 16, 200, DistCosine {});
         self.doc_ids.write().clear();
@@ -286,4 +581,145 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_vector_index_upsert() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let index = VectorIndex::new(temp_dir.path().to_path_buf(), 4)?;
+
+        index.insert("doc1", &[1.0, 0.0, 0.0, 0.0])?;
+        index.upsert("doc1", &[0.0, 1.0, 0.0, 0.0])?;
+        assert_eq!(index.len(), 1);
+
+        // Only the replacement embedding is returned
+        let results = index.search(&[1.0, 0.0, 0.0, 0.0], 2)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_index_wal() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf();
+
+        {
+            let index = VectorIndex::new(path.clone(), 4)?;
+            index.insert("doc1", &[1.0, 0.0, 0.0, 0.0])?;
+            index.save()?;
+            assert!(!path.join(WAL_FILE).exists());
+
+            // Later changes are appended to the log, the snapshot stays as is
+            let snapshot = std::fs::read(path.join(DOC_IDS_FILE))?;
+            index.insert("doc2", &[0.0, 1.0, 0.0, 0.0])?;
+            index.remove("doc1");
+            index.save()?;
+            assert!(path.join(WAL_FILE).exists());
+            assert_eq!(std::fs::read(path.join(DOC_IDS_FILE))?, snapshot);
+
+            // A crash mid-append leaves a partial line, which is skipped
+            let mut wal = std::fs::OpenOptions::new().append(true).open(path.join(WAL_FILE))?;
+            wal.write_all(br#"{"op":"insert","doc_id":"doc3","vec"#)?;
+        }
+
+        let index = VectorIndex::load(path.clone())?;
+        assert_eq!(index.len(), 1);
+        assert!(index.contains("doc2"));
+        assert!(!index.contains("doc1"));
+        assert_eq!(index.doc_ids(), HashSet::from(["doc2".to_string()]));
+        assert_eq!(index.search(&[0.0, 1.0, 0.0, 0.0], 1)?[0].2, "doc2");
+
+        // Compaction folds the log into a new snapshot
+        index.compact()?;
+        assert!(!path.join(WAL_FILE).exists());
+        let index = VectorIndex::load(path)?;
+        assert_eq!(index.len(), 1);
+        assert!(index.contains("doc2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_index_wal_after_torn_entry() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf();
+
+        {
+            let index = VectorIndex::new(path.clone(), 4)?;
+            index.insert("doc1", &[1.0, 0.0, 0.0, 0.0])?;
+            index.save()?;
+            index.insert("doc2", &[0.0, 1.0, 0.0, 0.0])?;
+            index.save()?;
+
+            let mut wal = std::fs::OpenOptions::new().append(true).open(path.join(WAL_FILE))?;
+            wal.write_all(br#"{"op":"insert","doc_id":"doc3","vec"#)?;
+        }
+
+        // Entries saved after the crash are appended past the torn one, and survive a reload
+        {
+            let index = VectorIndex::load(path.clone())?;
+            index.insert("doc4", &[0.0, 0.0, 1.0, 0.0])?;
+            index.save()?;
+        }
+
+        let index = VectorIndex::load(path)?;
+        assert_eq!(index.len(), 3);
+        assert!(index.contains("doc2"));
+        assert!(index.contains("doc4"));
+        assert!(!index.contains("doc3"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_index_recovers_from_partial_write() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf();
+
+        {
+            let index = VectorIndex::new(path.clone(), 4)?;
+            index.insert("doc1", &[1.0, 0.0, 0.0, 0.0])?;
+            index.save()?;
+            index.insert("doc2", &[0.0, 1.0, 0.0, 0.0])?;
+            index.compact()?;
+        }
+
+        // Simulate a crash that left a truncated doc_id index
+        let doc_ids = std::fs::read(path.join(DOC_IDS_FILE))?;
+        std::fs::write(path.join(DOC_IDS_FILE), &doc_ids[..doc_ids.len() / 2])?;
+
+        // The previous save is recovered from the backup
+        let index = VectorIndex::load(path)?;
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.search(&[1.0, 0.0, 0.0, 0.0], 1)?[0].2, "doc1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_index_loads_json_doc_ids() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf();
+
+        {
+            let index = VectorIndex::new(path.clone(), 4)?;
+            index.insert("doc1", &[1.0, 0.0, 0.0, 0.0])?;
+            index.save()?;
+        }
+
+        // Rewrite the doc_id index the way older versions stored it
+        std::fs::remove_file(path.join(DOC_IDS_FILE))?;
+        std::fs::write(path.join(DOC_IDS_JSON_FILE), r#"{"dimension": 4, "doc_ids": ["doc1"]}"#)?;
+
+        let index = VectorIndex::load(path.clone())?;
+        assert_eq!(index.len(), 1);
+
+        // Saving migrates to bincode and removes the JSON file
+        index.save()?;
+        assert!(path.join(DOC_IDS_FILE).exists());
+        assert!(!path.join(DOC_IDS_JSON_FILE).exists());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,126 @@
+//! On-disk layout of the vector index (see `VectorIndex`), readable without the
+//! `embeddings` feature so tools listing indexes can report on it
+
+use std::io::BufRead;
+use std::path::Path;
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+/// Compact doc_id index file name (bincode)
+pub(crate) const DOC_IDS_FILE: &str = "doc_ids.bin";
+
+/// Doc_id index written by older versions (JSON, still loadable)
+pub(crate) const DOC_IDS_JSON_FILE: &str = "doc_ids.json";
+
+/// Vectors with their doc_ids, written by the oldest versions (JSON, slow to load)
+pub(crate) const LEGACY_VECTORS_FILE: &str = "vectors.json";
+
+/// Append-only log of changes since the last snapshot (newline-delimited JSON)
+pub(crate) const WAL_FILE: &str = "vectors.wal";
+
+/// One line of the write-ahead log
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub(crate) enum WalEntry {
+    /// First line: number of points in the snapshot the log applies to
+    Base { points: usize },
+    Insert { doc_id: String, vector: Vec<f32> },
+    Delete { doc_id: String },
+}
+
+/// Compact doc_id index (fast to load)
+#[derive(Debug, Deserialize)]
+pub(crate) struct DocIdIndex {
+    #[cfg_attr(not(feature = "embeddings"), allow(dead_code))]
+    pub dimension: usize,
+    pub doc_ids: Vec<String>,
+}
+
+/// Bincode options for the doc_id index (varint lengths keep short doc_ids compact)
+pub(crate) fn doc_ids_encoding() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+/// Number of live vectors in the vector index at `path`, including changes in its
+/// write-ahead log, without loading the HNSW graph
+///
+/// Returns None if there's no readable doc_id index.
+pub fn vector_count(path: &Path) -> Option<usize> {
+    let mut doc_ids = read_doc_ids(path)?;
+
+    // Replayed like `VectorIndex::load`: a log for another snapshot is ignored, a torn entry ends it
+    if let Ok(file) = std::fs::File::open(path.join(WAL_FILE)) {
+        for line in std::io::BufReader::new(file).lines() {
+            let Some(entry) = line.ok().and_then(|line| serde_json::from_str::<WalEntry>(&line).ok()) else {
+                break;
+            };
+            match entry {
+                WalEntry::Base { points } if points != doc_ids.len() => break,
+                WalEntry::Base { .. } => {}
+                WalEntry::Insert { doc_id, .. } => doc_ids.push(doc_id),
+                WalEntry::Delete { doc_id } => {
+                    doc_ids.iter_mut().filter(|id| **id == doc_id).for_each(String::clear);
+                }
+            }
+        }
+    }
+
+    // Empty doc_ids are tombstones left by replaced embeddings
+    Some(doc_ids.iter().filter(|id| !id.is_empty()).count())
+}
+
+/// The snapshot's doc_ids, from doc_ids.bin (or older doc_ids.json / legacy vectors.json)
+fn read_doc_ids(path: &Path) -> Option<Vec<String>> {
+    if let Ok(file) = std::fs::File::open(path.join(DOC_IDS_FILE)) {
+        let index: DocIdIndex = doc_ids_encoding().deserialize_from(std::io::BufReader::new(file)).ok()?;
+        return Some(index.doc_ids);
+    }
+
+    if let Ok(file) = std::fs::File::open(path.join(DOC_IDS_JSON_FILE)) {
+        let index: DocIdIndex = serde_json::from_reader(std::io::BufReader::new(file)).ok()?;
+        return Some(index.doc_ids);
+    }
+
+    #[derive(Deserialize)]
+    struct LegacyVector {
+        doc_id: String,
+    }
+    #[derive(Deserialize)]
+    struct LegacyVectors {
+        vectors: Vec<LegacyVector>,
+    }
+    let file = std::fs::File::open(path.join(LEGACY_VECTORS_FILE)).ok()?;
+    let legacy: LegacyVectors = serde_json::from_reader(std::io::BufReader::new(file)).ok()?;
+    Some(legacy.vectors.into_iter().map(|vector| vector.doc_id).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_vector_count() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        assert_eq!(vector_count(path), None);
+
+        let doc_ids = doc_ids_encoding().serialize(&(384usize, vec!["a", "", "b"])).unwrap();
+        std::fs::write(path.join(DOC_IDS_FILE), doc_ids).unwrap();
+        assert_eq!(vector_count(path), Some(2));
+
+        // Changes logged since the snapshot count, up to a torn last entry
+        let wal = [
+            r#"{"op":"base","points":3}"#,
+            r#"{"op":"insert","doc_id":"c","vector":[0.5]}"#,
+            r#"{"op":"delete","doc_id":"a"}"#,
+            r#"{"op":"insert","doc_id":"d","vec"#,
+        ];
+        std::fs::write(path.join(WAL_FILE), wal.join("\n")).unwrap();
+        assert_eq!(vector_count(path), Some(2));
+
+        // A log written against another snapshot is ignored
+        std::fs::write(path.join(WAL_FILE), wal[..2].join("\n").replace("3", "7")).unwrap();
+        assert_eq!(vector_count(path), Some(2));
+    }
+}
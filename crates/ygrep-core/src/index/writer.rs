@@ -1,7 +1,10 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use parking_lot::RwLock;
-use tantivy::{Index, IndexWriter, TantivyDocument, Term};
+use parking_lot::{Mutex, RwLock};
+use tantivy::{Index, IndexReader, IndexWriter, TantivyDocument, Term};
 use xxhash_rust::xxh3::xxh3_64;
 
 use crate::config::IndexerConfig;
@@ -12,6 +15,30 @@ use super::schema::SchemaFields;
 #[cfg(feature = "embeddings")]
 use super::VectorIndex;
 
+/// Tantivy writer heap shared by all indexing threads
+const WRITER_HEAP_BYTES: usize = 50_000_000;
+
+/// Smallest heap Tantivy accepts per indexing thread
+const WRITER_HEAP_BYTES_PER_THREAD: usize = 15_000_000;
+
+/// Create a Tantivy writer using at most `threads` indexing threads
+///
+/// The thread count is capped so every thread keeps Tantivy's minimum heap.
+/// Tantivy's background merge pool is fixed in size and not affected.
+pub(crate) fn index_writer(index: &Index, threads: usize) -> Result<IndexWriter> {
+    let threads = threads.clamp(1, WRITER_HEAP_BYTES / WRITER_HEAP_BYTES_PER_THREAD);
+    Ok(index.writer_with_num_threads(threads, WRITER_HEAP_BYTES)?)
+}
+
+/// What `Indexer::index_file` did with a file
+#[derive(Debug, Clone)]
+pub struct IndexedFile {
+    /// Content hash the file is indexed under
+    pub doc_id: String,
+    /// Kept as a copy of an identical indexed file instead of in full (see `content_hash_dedup`)
+    pub deduplicated: bool,
+}
+
 /// Handles indexing of files and content
 pub struct Indexer {
     config: IndexerConfig,
@@ -28,6 +55,16 @@ pub struct Indexer {
     /// Optional embedding cache
     #[cfg(feature = "embeddings")]
     embedding_cache: Option<Arc<EmbeddingCache>>,
+    /// Documents and chunks whose embedding could not be generated
+    embedding_errors: AtomicUsize,
+    /// Reader for finding already indexed copies of a file (only with `content_hash_dedup`)
+    dedup_reader: Option<IndexReader>,
+    /// Path each content hash was indexed under by this indexer
+    canonical_paths: Mutex<HashMap<String, String>>,
+    /// Paths deleted since the last commit, which an earlier commit may still list as indexed
+    removed_paths: Mutex<HashSet<String>>,
+    /// Files with invalid UTF-8, indexed with replacement characters
+    lossy_decoded: AtomicUsize,
 }
 
 impl Indexer {
@@ -37,9 +74,10 @@ impl Indexer {
         index: Index,
         workspace_root: &Path,
     ) -> Result<Self> {
-        let writer = index.writer(50_000_000)?; // 50MB heap
+        let writer = index_writer(&index, config.threads)?;
         let schema = index.schema();
         let fields = SchemaFields::new(&schema);
+        let dedup_reader = config.content_hash_dedup.then(|| index.reader()).transpose()?;
 
         Ok(Self {
             config,
@@ -53,6 +91,11 @@ impl Indexer {
             embedding_model: None,
             #[cfg(feature = "embeddings")]
             embedding_cache: None,
+            embedding_errors: AtomicUsize::new(0),
+            dedup_reader,
+            canonical_paths: Mutex::new(HashMap::new()),
+            removed_paths: Mutex::new(HashSet::new()),
+            lossy_decoded: AtomicUsize::new(0),
         })
     }
 
@@ -66,9 +109,10 @@ impl Indexer {
         embedding_model: Arc<EmbeddingModel>,
         embedding_cache: Arc<EmbeddingCache>,
     ) -> Result<Self> {
-        let writer = index.writer(50_000_000)?; // 50MB heap
+        let writer = index_writer(&index, config.threads)?;
         let schema = index.schema();
         let fields = SchemaFields::new(&schema);
+        let dedup_reader = config.content_hash_dedup.then(|| index.reader()).transpose()?;
 
         Ok(Self {
             config,
@@ -79,13 +123,19 @@ impl Indexer {
             vector_index: Some(vector_index),
             embedding_model: Some(embedding_model),
             embedding_cache: Some(embedding_cache),
+            embedding_errors: AtomicUsize::new(0),
+            dedup_reader,
+            canonical_paths: Mutex::new(HashMap::new()),
+            removed_paths: Mutex::new(HashSet::new()),
+            lossy_decoded: AtomicUsize::new(0),
         })
     }
 
     /// Index a single file
-    pub fn index_file(&self, path: &Path) -> Result<String> {
-        // Read file content
-        let content = std::fs::read_to_string(path)?;
+    pub fn index_file(&self, path: &Path) -> Result<IndexedFile> {
+        // Read file content, with `\n` line endings so line numbers match an editor's
+        let (content, lossy) = read_text_lossy(path)?;
+        let content = normalize_line_endings(content);
         let metadata = std::fs::metadata(path)?;
 
         // Check file size
@@ -98,101 +148,171 @@ impl Indexer {
             });
         }
 
+        // Check line count (catches generated files that are small in bytes)
+        let line_count = content.lines().count();
+        if let Some(max_lines) = self.config.max_lines_per_file {
+            if line_count > max_lines {
+                return Err(YgrepError::FileTooLarge {
+                    path: path.to_path_buf(),
+                    size: line_count as u64,
+                    max: max_lines as u64,
+                });
+            }
+        }
+
+        if lossy {
+            tracing::debug!("Invalid UTF-8 replaced in {}", path.display());
+            self.lossy_decoded.fetch_add(1, Ordering::Relaxed);
+        }
+
         // Generate content hash for deduplication and doc_id
         let content_hash = xxh3_64(content.as_bytes());
         let doc_id = format!("{:016x}", content_hash);
 
-        // Get relative path
-        let rel_path = path
-            .strip_prefix(&self.workspace_root)
-            .unwrap_or(path)
-            .to_string_lossy()
-            .to_string();
-
-        // Get file extension
-        let extension = path
-            .extension()
-            .map(|e| e.to_string_lossy().to_string())
-            .unwrap_or_default();
-
-        // Get modification time
-        let mtime = metadata
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
+        let rel_path = stored_path(path, Path::new(&self.workspace_root));
 
-        let line_count = content.lines().count() as u64;
+        let language = super::language::detect_language(path, &content);
+        let doc = self.file_document(path, &rel_path, &doc_id, &content, &metadata, false);
 
-        // Build the document
-        let mut doc = TantivyDocument::new();
-        doc.add_text(self.fields.doc_id, &doc_id);
-        doc.add_text(self.fields.path, &rel_path);
-        doc.add_text(self.fields.workspace, &self.workspace_root);
-        doc.add_text(self.fields.content, &content);
-        doc.add_u64(self.fields.mtime, mtime);
-        doc.add_u64(self.fields.size, size);
-        doc.add_text(self.fields.extension, &extension);
-        doc.add_u64(self.fields.line_start, 1);
-        doc.add_u64(self.fields.line_end, line_count);
-        doc.add_text(self.fields.chunk_id, ""); // Not a chunk
-        doc.add_text(self.fields.parent_doc, ""); // Not a chunk
+        // The content this path had before, if it changed (its copies may need a new canonical file)
+        let replaced_doc_id = self.indexed_doc_id(&rel_path)?.filter(|old| *old != doc_id);
 
         // Delete any existing document with same path
         self.delete_by_path(&rel_path)?;
 
+        // Index identical files once, under the shortest path; the others are kept as copies
+        let mut deduplicated = false;
+        if self.config.content_hash_dedup {
+            if let Some(canonical) = self.indexed_copy(&doc_id, content_hash, &rel_path)? {
+                deduplicated = true;
+                if preferred_path(&canonical, &rel_path) {
+                    let copy = self.file_document(path, &rel_path, &doc_id, &content, &metadata, true);
+                    self.writer.write().add_document(copy)?;
+                    self.reindex_copies(replaced_doc_id.into_iter().collect(), &HashSet::new())?;
+                    return Ok(IndexedFile { doc_id, deduplicated });
+                }
+                // This copy has the shorter path, so it replaces the indexed one, which becomes a copy
+                self.delete_by_path(&canonical)?;
+                let canonical_path = Path::new(&self.workspace_root).join(&canonical);
+                if let Ok(canonical_metadata) = std::fs::metadata(&canonical_path) {
+                    let copy = self.file_document(&canonical_path, &canonical, &doc_id, &content, &canonical_metadata, true);
+                    self.writer.write().add_document(copy)?;
+                }
+            }
+            self.canonical_paths.lock().insert(doc_id.clone(), rel_path.clone());
+        }
+
         // Add the document
         let mut writer = self.writer.write();
         writer.add_document(doc)?;
 
         // Also create chunks for the file
         #[cfg(feature = "embeddings")]
-        let chunk_ids = self.index_chunks(&content, &doc_id, &rel_path, &mut writer)?;
+        let chunk_ids = self.index_chunks(&content, &doc_id, &rel_path, language, &mut writer)?;
         #[cfg(not(feature = "embeddings"))]
-        let _ = self.index_chunks(&content, &doc_id, &rel_path, &mut writer)?;
+        let _ = self.index_chunks(&content, &doc_id, &rel_path, language, &mut writer)?;
 
         // Release the writer lock before embedding generation
         drop(writer);
 
+        self.reindex_copies(replaced_doc_id.into_iter().collect(), &HashSet::new())?;
+
         // Generate embeddings if semantic search is enabled
+        // (doc_id is a content hash, so identical files share one set of vectors when deduplicating)
         #[cfg(feature = "embeddings")]
         if let (Some(vector_index), Some(model), Some(cache)) =
             (&self.vector_index, &self.embedding_model, &self.embedding_cache)
         {
-            // Embed the full document
-            let embedding = cache.get_or_insert(&content, || {
-                model.embed(&content).unwrap_or_else(|_| vec![0.0; 384])
-            });
-            vector_index.insert(&doc_id, &embedding)?;
+            if self.config.deduplicate && vector_index.contains(&doc_id) {
+                return Ok(IndexedFile { doc_id, deduplicated });
+            }
 
-            // Embed chunks
-            for (chunk_id, chunk_content) in chunk_ids {
-                let chunk_embedding = cache.get_or_insert(&chunk_content, || {
-                    model.embed(&chunk_content).unwrap_or_else(|_| vec![0.0; 384])
-                });
-                vector_index.insert(&chunk_id, &chunk_embedding)?;
+            // Embed the full document, then its chunks
+            // (a failed embedding is skipped rather than stored as a zero vector)
+            let texts = std::iter::once((doc_id.clone(), content)).chain(chunk_ids);
+            for (id, text) in texts {
+                match cache.try_get_or_insert(&text, || model.embed(&text)) {
+                    Ok(embedding) => {
+                        vector_index.insert(&id, &embedding)?;
+                    }
+                    Err(e) => {
+                        tracing::debug!("Failed to generate embedding for {}: {}", id, e);
+                        self.embedding_errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
             }
         }
 
-        Ok(doc_id)
+        Ok(IndexedFile { doc_id, deduplicated })
+    }
+
+    /// Build the document of a whole file
+    ///
+    /// A `copy` of an indexed file (see `content_hash_dedup`) leaves out the content, so it's
+    /// listed but not searched.
+    fn file_document(
+        &self,
+        path: &Path,
+        rel_path: &str,
+        doc_id: &str,
+        content: &str,
+        metadata: &std::fs::Metadata,
+        copy: bool,
+    ) -> TantivyDocument {
+        // Get file extension
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let language = super::language::detect_language(path, content);
+
+        // Get modification time
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut doc = TantivyDocument::new();
+        doc.add_text(self.fields.doc_id, doc_id);
+        doc.add_text(self.fields.path, rel_path);
+        doc.add_text(self.fields.path_text, rel_path); // Not on chunks, so a path match is one hit per file
+        doc.add_text(self.fields.workspace, &self.workspace_root);
+        doc.add_text(self.fields.content, if copy { "" } else { content });
+        doc.add_u64(self.fields.mtime, mtime);
+        doc.add_u64(self.fields.size, metadata.len());
+        doc.add_text(self.fields.extension, &extension);
+        doc.add_text(self.fields.language, language);
+        doc.add_u64(self.fields.line_start, 1);
+        doc.add_u64(self.fields.line_end, content.lines().count() as u64);
+        doc.add_text(self.fields.chunk_id, ""); // Not a chunk
+        doc.add_text(self.fields.parent_doc, ""); // Not a chunk
+        doc.add_bool(self.fields.copy, copy);
+        if !copy {
+            for line in super::symbols::definition_lines(content, &extension) {
+                doc.add_u64(self.fields.def_lines, line);
+            }
+        }
+        doc
     }
 
-    /// Index chunks of a file for more granular search
+    /// Index chunks of a file for more granular search (nothing with `index_chunks` off)
     /// Returns a list of (chunk_id, chunk_content) tuples for embedding generation
     fn index_chunks(
         &self,
         content: &str,
         parent_doc_id: &str,
         path: &str,
+        language: &str,
         writer: &mut IndexWriter,
     ) -> Result<Vec<(String, String)>> {
         let lines: Vec<&str> = content.lines().collect();
         let chunk_size = self.config.chunk_size;
         let overlap = self.config.chunk_overlap;
 
-        if lines.len() <= chunk_size {
-            // File is small enough, no need for chunks
+        if !self.config.index_chunks || lines.len() <= chunk_size {
+            // Chunks are disabled, or the file is small enough not to need them
             return Ok(vec![]);
         }
 
@@ -213,6 +333,7 @@ impl Indexer {
             doc.add_u64(self.fields.mtime, 0);
             doc.add_u64(self.fields.size, chunk_content.len() as u64);
             doc.add_text(self.fields.extension, "");
+            doc.add_text(self.fields.language, language);
             doc.add_u64(self.fields.line_start, (start + 1) as u64);
             doc.add_u64(self.fields.line_end, end as u64);
             doc.add_text(self.fields.chunk_id, &chunk_id);
@@ -246,10 +367,135 @@ impl Indexer {
         Ok(())
     }
 
+    /// Delete every document matching a query
+    pub fn delete_query(&self, query: Box<dyn tantivy::query::Query>) -> Result<()> {
+        let writer = self.writer.write();
+        writer.delete_query(query)?;
+        Ok(())
+    }
+
+    /// Path of an indexed file with this content other than `path`, if any
+    ///
+    /// Files indexed by an earlier run only count if they still have the same content.
+    fn indexed_copy(&self, doc_id: &str, content_hash: u64, path: &str) -> Result<Option<String>> {
+        use tantivy::collector::TopDocs;
+        use tantivy::query::{BooleanQuery, Occur};
+        use tantivy::schema::Value;
+
+        if let Some(existing) = self.canonical_paths.lock().get(doc_id) {
+            return Ok((existing != path).then(|| existing.clone()));
+        }
+
+        let Some(reader) = &self.dedup_reader else {
+            return Ok(None);
+        };
+        let searcher = reader.searcher();
+        let query = BooleanQuery::new(vec![
+            (Occur::Must, self.term_query(Term::from_field_text(self.fields.doc_id, doc_id))),
+            (Occur::MustNot, self.term_query(Term::from_field_bool(self.fields.copy, true))),
+        ]);
+        for (_, address) in searcher.search(&query, &TopDocs::with_limit(16))? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let Some(existing) = doc.get_first(self.fields.path).and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if existing == path || self.removed_paths.lock().contains(existing) {
+                continue;
+            }
+            let unchanged = read_text_lossy(&Path::new(&self.workspace_root).join(existing))
+                .is_ok_and(|(content, _)| xxh3_64(normalize_line_endings(content).as_bytes()) == content_hash);
+            if unchanged {
+                return Ok(Some(existing.to_string()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Content hash `path` was indexed in full under by an earlier commit (only with `content_hash_dedup`)
+    fn indexed_doc_id(&self, path: &str) -> Result<Option<String>> {
+        use tantivy::collector::DocSetCollector;
+        use tantivy::schema::Value;
+
+        let Some(reader) = &self.dedup_reader else {
+            return Ok(None);
+        };
+        let searcher = reader.searcher();
+        let query = self.term_query(Term::from_field_text(self.fields.path, path));
+        for address in searcher.search(&query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let is_file = doc.get_first(self.fields.chunk_id).and_then(|v| v.as_str()) == Some("");
+            let is_copy = doc.get_first(self.fields.copy).and_then(|v| v.as_bool()).unwrap_or(false);
+            if is_file && !is_copy {
+                return Ok(doc.get_first(self.fields.doc_id).and_then(|v| v.as_str()).map(str::to_string));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Index a copy of each file deleted or changed since the last commit in full in its place
+    ///
+    /// `doc_ids` are the content hashes those files were indexed under and `removed_paths` the
+    /// deleted paths, which are no longer kept as copies (only with `content_hash_dedup`).
+    pub fn reindex_copies(&self, doc_ids: HashSet<String>, removed_paths: &HashSet<String>) -> Result<()> {
+        use tantivy::collector::DocSetCollector;
+        use tantivy::query::{BooleanQuery, Occur};
+        use tantivy::schema::Value;
+
+        let Some(reader) = &self.dedup_reader else {
+            return Ok(());
+        };
+        if doc_ids.is_empty() {
+            return Ok(());
+        }
+        self.removed_paths.lock().extend(removed_paths.iter().cloned());
+
+        let searcher = reader.searcher();
+        let mut copies = Vec::new();
+        for doc_id in &doc_ids {
+            self.canonical_paths.lock().remove(doc_id);
+            let query = BooleanQuery::new(vec![
+                (Occur::Must, self.term_query(Term::from_field_text(self.fields.doc_id, doc_id))),
+                (Occur::Must, self.term_query(Term::from_field_bool(self.fields.copy, true))),
+            ]);
+            for address in searcher.search(&query, &DocSetCollector)? {
+                let doc: TantivyDocument = searcher.doc(address)?;
+                if let Some(path) = doc.get_first(self.fields.path).and_then(|v| v.as_str()) {
+                    if !removed_paths.contains(path) {
+                        copies.push(path.to_string());
+                    }
+                }
+            }
+        }
+
+        // The first copy is indexed in full and the rest are kept as its copies
+        copies.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+        for path in copies {
+            if let Err(e) = self.index_file(&Path::new(&self.workspace_root).join(&path)) {
+                tracing::debug!("Failed to index copy {}: {}", path, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn term_query(&self, term: Term) -> Box<dyn tantivy::query::Query> {
+        Box::new(tantivy::query::TermQuery::new(term, tantivy::schema::IndexRecordOption::Basic))
+    }
+
+    /// Number of files indexed so far whose invalid UTF-8 was replaced (see `read_text_lossy`)
+    pub fn lossy_decoded(&self) -> usize {
+        self.lossy_decoded.load(Ordering::Relaxed)
+    }
+
+    /// Number of documents and chunks that could not be embedded so far
+    pub fn embedding_errors(&self) -> usize {
+        self.embedding_errors.load(Ordering::Relaxed)
+    }
+
     /// Commit pending changes to the index
     pub fn commit(&self) -> Result<()> {
         let mut writer = self.writer.write();
         writer.commit()?;
+        self.removed_paths.lock().clear();
 
         // Also save the vector index if present
         #[cfg(feature = "embeddings")]
@@ -266,10 +512,57 @@ impl Indexer {
     }
 }
 
+/// Read a file as text, replacing invalid UTF-8 (e.g. Latin-1 bytes) with U+FFFD
+/// Returns the text and whether anything had to be replaced
+pub(crate) fn read_text_lossy(path: &Path) -> Result<(String, bool)> {
+    match String::from_utf8(std::fs::read(path)?) {
+        Ok(content) => Ok((content, false)),
+        Err(e) => Ok((String::from_utf8_lossy(e.as_bytes()).into_owned(), true)),
+    }
+}
+
+/// The path a file is indexed under: relative to the workspace root, or absolute for files
+/// outside it (e.g. in extra roots), so deletes must look paths up the same way
+pub(crate) fn stored_path(path: &Path, workspace_root: &Path) -> String {
+    match path.strip_prefix(workspace_root) {
+        Ok(relative) => normalize_path(&relative.to_string_lossy()).into_owned(),
+        Err(_) if path.is_relative() => normalize_path(&path.to_string_lossy()).into_owned(),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Normalize a path relative to the workspace root the way the index stores it: `/`
+/// separators (also on Windows), without a leading `./` or `/`, `//` or `/./`
+pub(crate) fn normalize_path(path: &str) -> Cow<'_, str> {
+    let windows = std::path::MAIN_SEPARATOR == '\\';
+    let is_separator = |c: char| c == '/' || (windows && c == '\\');
+    let is_part = |part: &&str| !part.is_empty() && *part != ".";
+
+    let clean = path.split(is_separator).all(|part| is_part(&part)) && !(windows && path.contains('\\'));
+    if clean {
+        return Cow::Borrowed(path);
+    }
+    Cow::Owned(path.split(is_separator).filter(is_part).collect::<Vec<_>>().join("/"))
+}
+
+/// Convert `\r\n` and lone `\r` (classic Mac) line endings to `\n`
+fn normalize_line_endings(content: String) -> String {
+    if content.contains('\r') {
+        content.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        content
+    }
+}
+
+/// Whether `a` is kept over `b` as the path of identical files: the shorter one, then the first in order
+fn preferred_path(a: &str, b: &str) -> bool {
+    (a.len(), a) <= (b.len(), b)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::index::schema::build_document_schema;
+    use crate::index::schema::{build_document_schema, register_tokenizers};
     use tempfile::tempdir;
 
     #[test]
@@ -285,15 +578,168 @@ mod tests {
         // Create index
         let schema = build_document_schema();
         let index = Index::create_in_dir(&index_path, schema)?;
+        register_tokenizers(index.tokenizers());
 
         let config = IndexerConfig::default();
         let indexer = Indexer::new(config, index, temp_dir.path())?;
 
         // Index the file
-        let doc_id = indexer.index_file(&test_file)?;
+        let file = indexer.index_file(&test_file)?;
         indexer.commit()?;
 
-        assert!(!doc_id.is_empty());
+        assert!(!file.doc_id.is_empty());
+        assert!(!file.deduplicated);
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("src/main.rs"), "src/main.rs");
+        assert_eq!(normalize_path("./src/main.rs"), "src/main.rs");
+        assert_eq!(normalize_path("/src/main.rs"), "src/main.rs");
+        assert_eq!(normalize_path(".//./src//main.rs"), "src/main.rs");
+        // Hidden files and directories keep their dot
+        assert_eq!(normalize_path(".github/ci.yml"), ".github/ci.yml");
+    }
+
+    #[test]
+    fn test_stored_paths_stable() -> Result<()> {
+        use tantivy::collector::DocSetCollector;
+        use tantivy::query::AllQuery;
+        use tantivy::schema::Value;
+
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        std::fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(temp_dir.path().join("src/lib.rs"), "pub fn lib() {}").unwrap();
+
+        let index = Index::create_in_ram(build_document_schema());
+        register_tokenizers(index.tokenizers());
+
+        // A root with a trailing slash, and file paths spelled in different ways
+        let root = format!("{}/", temp_dir.path().display());
+        let indexer = Indexer::new(IndexerConfig::default(), index.clone(), Path::new(&root))?;
+        indexer.index_file(&temp_dir.path().join("src/main.rs"))?;
+        indexer.index_file(Path::new(&format!("{}./src//lib.rs", root)))?;
+        indexer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let fields = SchemaFields::new(&index.schema());
+        let mut paths: Vec<String> = searcher
+            .search(&AllQuery, &DocSetCollector)?
+            .into_iter()
+            .map(|address| {
+                let doc: TantivyDocument = searcher.doc(address).unwrap();
+                doc.get_first(fields.path).and_then(|v| v.as_str()).unwrap().to_string()
+            })
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["src/lib.rs", "src/main.rs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_chunks_disabled() -> Result<()> {
+        use tantivy::collector::Count;
+        use tantivy::query::AllQuery;
+
+        let temp_dir = tempdir().unwrap();
+        let test_file = temp_dir.path().join("large.rs");
+        std::fs::write(&test_file, "fn step() {}\n".repeat(200)).unwrap();
+
+        let count_docs = |config: IndexerConfig| -> Result<usize> {
+            let index = Index::create_in_ram(build_document_schema());
+            register_tokenizers(index.tokenizers());
+            let indexer = Indexer::new(config, index.clone(), temp_dir.path())?;
+            indexer.index_file(&test_file)?;
+            indexer.commit()?;
+            Ok(index.reader()?.searcher().search(&AllQuery, &Count)?)
+        };
+
+        assert!(count_docs(IndexerConfig::default())? > 1);
+        let config = IndexerConfig { index_chunks: false, ..IndexerConfig::default() };
+        assert_eq!(count_docs(config)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_lines_per_file() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let index_path = temp_dir.path().join("index");
+        std::fs::create_dir_all(&index_path).unwrap();
+
+        let test_file = temp_dir.path().join("generated.rs");
+        std::fs::write(&test_file, "// generated\n".repeat(20)).unwrap();
+
+        let schema = build_document_schema();
+        let index = Index::create_in_dir(&index_path, schema)?;
+        register_tokenizers(index.tokenizers());
+
+        let config = IndexerConfig {
+            max_lines_per_file: Some(10),
+            ..IndexerConfig::default()
+        };
+        let indexer = Indexer::new(config, index, temp_dir.path())?;
+
+        assert!(matches!(
+            indexer.index_file(&test_file),
+            Err(YgrepError::FileTooLarge { size: 20, max: 10, .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_indexer_threads() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let test_file = temp_dir.path().join("test.rs");
+        std::fs::write(&test_file, "fn main() {}").unwrap();
+
+        // Out-of-range thread counts are clamped instead of failing writer creation
+        for threads in [0, 1, 64] {
+            let index = Index::create_in_ram(build_document_schema());
+            register_tokenizers(index.tokenizers());
+
+            let config = IndexerConfig { threads, ..IndexerConfig::default() };
+            let indexer = Indexer::new(config, index, temp_dir.path())?;
+            indexer.index_file(&test_file)?;
+            indexer.commit()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "embeddings")]
+    #[test]
+    fn test_embedding_error_skips_vector() -> Result<()> {
+        use crate::embeddings::ModelType;
+
+        let temp_dir = tempdir().unwrap();
+        let index_path = temp_dir.path().join("index");
+        let model_dir = temp_dir.path().join("models");
+        std::fs::create_dir_all(&index_path).unwrap();
+        std::fs::create_dir_all(&model_dir).unwrap();
+
+        let test_file = temp_dir.path().join("test.rs");
+        std::fs::write(&test_file, "fn main() {\n    println!(\"hello\");\n}").unwrap();
+
+        let schema = build_document_schema();
+        let index = Index::create_in_dir(&index_path, schema)?;
+        register_tokenizers(index.tokenizers());
+
+        // Offline with an empty cache dir, so every embed call fails
+        let config = IndexerConfig {
+            model_cache_dir: Some(model_dir),
+            offline: true,
+            ..IndexerConfig::default()
+        };
+        let model = Arc::new(EmbeddingModel::with_config(ModelType::default(), &config));
+        let vector_index = Arc::new(VectorIndex::new(temp_dir.path().join("vectors"), model.dimension())?);
+        let cache = Arc::new(EmbeddingCache::new(1, model.dimension()));
+        let indexer = Indexer::with_semantic(config, index, temp_dir.path(), vector_index.clone(), model, cache)?;
+
+        indexer.index_file(&test_file)?;
+
+        assert!(vector_index.is_empty());
+        assert_eq!(indexer.embedding_errors(), 1);
         Ok(())
     }
 }
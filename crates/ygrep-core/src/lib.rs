@@ -6,6 +6,9 @@
 //! - BM25 text search + semantic vector search (with `embeddings` feature)
 //! - Hybrid search with Reciprocal Rank Fusion
 //! - Configuration management
+//!
+//! `Workspace` manages an index for a directory; to query an existing Tantivy index
+//! directly, use `search::Searcher`.
 
 pub mod config;
 #[cfg(feature = "embeddings")]
@@ -13,12 +16,13 @@ pub mod embeddings;
 pub mod error;
 pub mod fs;
 pub mod index;
+pub mod protocol;
 pub mod search;
 pub mod watcher;
 
 pub use config::Config;
 pub use error::{Result, YgrepError};
-pub use watcher::{FileWatcher, WatchEvent};
+pub use watcher::{FileWatcher, SearchRequest, WatchEvent};
 
 use std::path::Path;
 use tantivy::Index;
@@ -26,14 +30,10 @@ use tantivy::Index;
 #[cfg(feature = "embeddings")]
 use std::sync::Arc;
 #[cfg(feature = "embeddings")]
-use embeddings::{EmbeddingModel, EmbeddingCache};
+use embeddings::{CrossEncoderModel, EmbeddingModel, EmbeddingCache, ModelType};
 #[cfg(feature = "embeddings")]
 use index::VectorIndex;
 
-/// Embedding dimension for all-MiniLM-L6-v2
-#[cfg(feature = "embeddings")]
-const EMBEDDING_DIM: usize = 384;
-
 /// High-level workspace for indexing and searching
 pub struct Workspace {
     /// Workspace root directory
@@ -42,8 +42,13 @@ pub struct Workspace {
     config: Config,
     /// Tantivy index
     index: Index,
+    /// Reader shared by every search, so segments aren't reopened per query; reloaded after
+    /// this workspace's own commits and shortly after other processes' (`ygrep watch`)
+    reader: tantivy::IndexReader,
     /// Index directory path
     index_path: std::path::PathBuf,
+    /// Subdirectory prefix searches are limited to, when opened inside an indexed parent
+    scope: Option<String>,
     /// Vector index for semantic search
     #[cfg(feature = "embeddings")]
     vector_index: Arc<VectorIndex>,
@@ -53,6 +58,9 @@ pub struct Workspace {
     /// Embedding cache
     #[cfg(feature = "embeddings")]
     embedding_cache: Arc<EmbeddingCache>,
+    /// Cross-encoder for re-ranking hybrid results (lazy-loaded, only used with `rerank_top_n`)
+    #[cfg(feature = "embeddings")]
+    reranker: Arc<CrossEncoderModel>,
 }
 
 impl Workspace {
@@ -80,84 +88,191 @@ impl Workspace {
 
     /// Open or create a workspace with custom config
     /// If create is false, returns an error if the index doesn't exist
-    fn open_internal(root: &Path, config: Config, create: bool) -> Result<Self> {
+    fn open_internal(root: &Path, mut config: Config, create: bool) -> Result<Self> {
+        config.validate().map_err(|e| YgrepError::Config(Box::new(e)))?;
         let root = std::fs::canonicalize(root)?;
+        let extra_roots = match resolve_extra_roots(&root, &config.indexer.extra_roots) {
+            Ok(extra_roots) => extra_roots,
+            // Relative extra roots may only exist next to an indexed parent
+            Err(e) if !create => {
+                return match find_indexed_parent(&root, &config.indexer.data_dir, &config.indexer.extra_roots) {
+                    Some(parent) => Self::open_scoped(&root, &parent, config),
+                    None => Err(e),
+                };
+            }
+            Err(e) => return Err(e),
+        };
 
-        // Calculate index directory path based on workspace path hash
-        let workspace_hash = hash_path(&root);
-        let index_path = config.indexer.data_dir.join("indexes").join(&workspace_hash);
+        // Use the data dir that already holds this workspace's index (configured or
+        // local), otherwise the configured one, with relative paths under the root
+        let data_dir = indexed_data_dir(&root, &extra_roots, &config.indexer.data_dir)
+            .unwrap_or_else(|| root.join(&config.indexer.data_dir));
+        // Index directory named by the hash of the workspace's roots
+        let index_path = data_dir.join("indexes").join(index_key(&root, &extra_roots, &data_dir));
 
         // Check if workspace has been properly indexed (workspace.json is written after indexing)
         let workspace_indexed = index_path.join("workspace.json").exists();
         // Check if Tantivy files exist (meta.json is created by Tantivy)
         let tantivy_exists = index_path.join("meta.json").exists();
 
-        // If not creating and workspace not indexed, return error
+        // If not creating and workspace not indexed, search an indexed parent or return error
         if !create && !workspace_indexed {
+            if let Some(parent) = find_indexed_parent(&root, &config.indexer.data_dir, &config.indexer.extra_roots) {
+                return Self::open_scoped(&root, &parent, config);
+            }
             return Err(YgrepError::Config(
-                format!("Workspace not indexed: {}", root.display())
+                format!("Workspace not indexed: {}", root.display()).into()
             ));
         }
 
+        // Refuse to search an index built with a different schema/tokenizer
+        if !create {
+            check_index_compatible(&root, &index_path, config.indexer.max_token_len)?;
+        }
+        config.indexer.data_dir = data_dir;
+        config.indexer.extra_roots = extra_roots;
+
         // Open or create Tantivy index
         let schema = index::build_document_schema();
         let index = if tantivy_exists {
             Index::open_in_dir(&index_path)?
         } else {
-            // Create directory only when explicitly creating the index
-            std::fs::create_dir_all(&index_path)?;
+            // Create directories only when explicitly creating the index
+            let data_dir = &config.indexer.data_dir;
+            std::fs::create_dir_all(data_dir)
+                .and_then(|_| std::fs::create_dir_all(&index_path))
+                .map_err(|e| data_dir_error(data_dir, e))?;
             Index::create_in_dir(&index_path, schema)?
         };
 
         // Register our custom code tokenizer
-        index::register_tokenizers(index.tokenizers());
+        index::register_tokenizers_with_limit(index.tokenizers(), config.indexer.max_token_len);
+        let reader = search::open_reader(&index)?;
 
         #[cfg(feature = "embeddings")]
-        let (vector_index, embedding_model, embedding_cache) = {
+        let (vector_index, embedding_model, embedding_cache, reranker) = {
+            let metadata = read_workspace_metadata(&index_path);
+
+            // Existing indexes keep the model they were built with, (re)indexing uses the configured one
+            let stored_model = metadata.as_ref()
+                .and_then(|v| v.get("embedding_model"))
+                .and_then(|m| m.as_str())
+                .and_then(ModelType::from_name);
+            let configured_model = ModelType::from_config(&config.indexer)?;
+            let model_type = if create && config.indexer.embedding_model.is_some() {
+                configured_model
+            } else {
+                stored_model.unwrap_or(configured_model)
+            };
+
             // Create vector index path
             let vector_path = index_path.join("vectors");
 
-            // Load or create vector index
-            let vector_index = if VectorIndex::exists(&vector_path) {
-                Arc::new(VectorIndex::load(vector_path)?)
+            // Load or create vector index, using the dimension stored at index time
+            let stored_dim = metadata.as_ref()
+                .and_then(|v| v.get("embedding_dim"))
+                .and_then(|d| d.as_u64())
+                .map(|d| d as usize);
+            let mut vector_index = if VectorIndex::exists(&vector_path) {
+                VectorIndex::load(vector_path.clone())?
             } else {
-                Arc::new(VectorIndex::new(vector_path, EMBEDDING_DIM)?)
+                VectorIndex::new(vector_path.clone(), stored_dim.unwrap_or(model_type.dimension()))?
             };
 
+            if vector_index.dimension() != model_type.dimension() {
+                if !create {
+                    return Err(YgrepError::EmbeddingDimensionMismatch {
+                        path: root,
+                        index: vector_index.dimension(),
+                        model: model_type.dimension(),
+                    });
+                }
+                // Re-indexing with a different model replaces all vectors anyway
+                vector_index = VectorIndex::new(vector_path, model_type.dimension())?;
+            }
+
             // Create embedding model (lazy-loaded on first use)
-            let embedding_model = Arc::new(EmbeddingModel::default()); // Uses all-MiniLM-L6-v2
+            let embedding_model = Arc::new(EmbeddingModel::with_config(model_type, &config.indexer));
+
+            // Create embedding cache (100MB cache)
+            let embedding_cache = Arc::new(EmbeddingCache::new(100, model_type.dimension()));
 
-            // Create embedding cache (100MB cache, 384 dimensions)
-            let embedding_cache = Arc::new(EmbeddingCache::new(100, EMBEDDING_DIM));
+            let reranker = Arc::new(CrossEncoderModel::with_config(&config.indexer));
 
-            (vector_index, embedding_model, embedding_cache)
+            (Arc::new(vector_index), embedding_model, embedding_cache, reranker)
         };
 
         Ok(Self {
             root,
             config,
             index,
+            reader,
             index_path,
+            scope: None,
             #[cfg(feature = "embeddings")]
             vector_index,
             #[cfg(feature = "embeddings")]
             embedding_model,
             #[cfg(feature = "embeddings")]
             embedding_cache,
+            #[cfg(feature = "embeddings")]
+            reranker,
         })
     }
 
+    /// Open the indexed `parent` of `root`, with searches limited to `root`
+    fn open_scoped(root: &Path, parent: &Path, config: Config) -> Result<Self> {
+        let relative = root.strip_prefix(parent).unwrap_or(root);
+        let scope = format!("{}/", index::writer::normalize_path(&relative.to_string_lossy()));
+        let mut workspace = Self::open_internal(parent, config, false)?;
+        workspace.scope = Some(scope);
+        Ok(workspace)
+    }
+
     /// Index all files in the workspace (text-only by default, fast)
     pub fn index_all(&self) -> Result<IndexStats> {
         self.index_all_with_options(false)
     }
 
     /// Index all files with options
-    #[allow(unused_variables)]
     pub fn index_all_with_options(&self, with_embeddings: bool) -> Result<IndexStats> {
-        // Clear vector index for fresh re-index
+        self.index_all_with_progress(with_embeddings, false)
+    }
+
+    /// Index all files, with `progress` showing progress bars for the indexing and
+    /// embedding phases (only on a terminal; otherwise the plain `Indexed N files...` lines)
+    pub fn index_all_with_progress(&self, with_embeddings: bool, progress: bool) -> Result<IndexStats> {
+        self.index_all_interruptible(with_embeddings, progress, &std::sync::atomic::AtomicBool::new(false))
+    }
+
+    /// Index all files, stopping early once `interrupted` is set (e.g. from a Ctrl-C handler)
+    ///
+    /// The file or embedding batch in progress is finished, then the text index is committed,
+    /// the vector index saved and workspace.json written with `"interrupted": true`, so the
+    /// index is usable and `IndexStats::interrupted` is set. The next run keeps the embeddings
+    /// already made and only embeds the remaining documents.
+    #[allow(unused_variables)]
+    pub fn index_all_interruptible(
+        &self,
+        with_embeddings: bool,
+        progress: bool,
+        interrupted: &std::sync::atomic::AtomicBool,
+    ) -> Result<IndexStats> {
+        use std::io::IsTerminal;
+        use std::sync::atomic::Ordering;
+        use indicatif::{ProgressBar, ProgressStyle};
+
+        // Clear vector index for fresh re-index, unless resuming an interrupted semantic run
         #[cfg(feature = "embeddings")]
-        self.vector_index.clear();
+        let resuming = with_embeddings
+            && read_workspace_metadata(&self.index_path).is_some_and(|metadata| {
+                metadata.get("interrupted").and_then(|v| v.as_bool()) == Some(true)
+                    && metadata.get("semantic").and_then(|v| v.as_bool()) == Some(true)
+            });
+        #[cfg(feature = "embeddings")]
+        if !resuming {
+            self.vector_index.clear();
+        }
 
         // Phase 1: Index all files with BM25 (fast)
         let indexer = index::Indexer::new(
@@ -166,31 +281,64 @@ impl Workspace {
             &self.root,
         )?;
 
-        let mut walker = fs::FileWalker::new(self.root.clone(), self.config.indexer.clone())?;
+        let roots = std::iter::once(self.root.clone())
+            .chain(self.config.indexer.extra_roots.iter().cloned())
+            .collect();
+        let mut walker = fs::FileWalker::new_multi(roots, self.config.indexer.clone())?;
+
+        // Walk first so the progress bar knows the total
+        let entries: Vec<fs::WalkEntry> = walker.walk().collect();
+        let show_bars = progress && std::io::stderr().is_terminal();
+        let bar_style = |unit: &str| {
+            ProgressStyle::default_bar()
+                .template(&format!("[{{elapsed_precise}}] {{bar:40}} {{pos}}/{{len}} {} {{msg}}", unit))
+                .unwrap()
+        };
+        let pb = show_bars.then(|| {
+            let pb = ProgressBar::new(entries.len() as u64);
+            pb.set_style(bar_style("files"));
+            pb.set_message("indexing");
+            pb
+        });
 
         let mut indexed = 0;
+        let mut deduplicated = 0;
         let mut skipped = 0;
         let mut errors = 0;
 
         // Collect content for batch embedding
         #[cfg(feature = "embeddings")]
         let mut embedding_batch: Vec<(String, String)> = Vec::new(); // (doc_id, content)
+        // doc_ids are content hashes, so identical files only need embedding once
+        #[cfg(feature = "embeddings")]
+        let mut batched_doc_ids = std::collections::HashSet::new();
         // Larger batch size = more efficient SIMD/vectorization in ONNX Runtime
         #[cfg(feature = "embeddings")]
         const BATCH_SIZE: usize = 64;
 
-        for entry in walker.walk() {
+        for entry in &entries {
+            if let Some(pb) = &pb {
+                pb.inc(1);
+            }
             match indexer.index_file(&entry.path) {
-                Ok(doc_id) => {
-                    indexed += 1;
-                    if indexed % 500 == 0 {
-                        eprint!("\r  Indexed {} files...          ", indexed);
+                Ok(index::IndexedFile { doc_id, deduplicated: is_copy }) => {
+                    // Identical copies of an indexed file count as deduplicated, not indexed
+                    // (counted here only: re-indexing copies of a changed file reaches them again)
+                    if is_copy {
+                        deduplicated += 1;
+                    } else {
+                        indexed += 1;
+                        if pb.is_none() && indexed % 500 == 0 {
+                            eprint!("\r  Indexed {} files...          ", indexed);
+                        }
                     }
 
                     // Collect for embedding if enabled
                     #[cfg(feature = "embeddings")]
-                    if with_embeddings {
-                        if let Ok(content) = std::fs::read_to_string(&entry.path) {
+                    if with_embeddings
+                        && (!self.config.indexer.deduplicate || batched_doc_ids.insert(doc_id.clone()))
+                    {
+                        if let Ok((content, _)) = index::writer::read_text_lossy(&entry.path) {
                             embedding_batch.push((doc_id, content));
                         }
                     }
@@ -201,36 +349,62 @@ impl Workspace {
                     skipped += 1;
                 }
                 Err(e) => {
-                    tracing::debug!("Error indexing {}: {}", entry.path.display(), e);
+                    tracing::debug!("Error indexing {}: {}", entry.path.display(), e.chain());
                     errors += 1;
                 }
             }
+
+            if interrupted.load(Ordering::Relaxed) {
+                break;
+            }
         }
 
-        eprintln!("\r  Indexed {} files.              ", indexed);
+        let lossy_decoded = indexer.lossy_decoded();
+
+        match pb {
+            Some(pb) => {
+                pb.finish_and_clear();
+                eprintln!("  Indexed {} files.", indexed);
+            }
+            None => eprintln!("\r  Indexed {} files.              ", indexed),
+        }
         indexer.commit()?;
+        self.reader.reload()?;
 
-        // Track embedded count
+        // Track embedded count and documents that failed to embed
+        #[allow(unused_mut)]
         let mut total_embedded = 0usize;
+        #[allow(unused_mut)]
+        let mut embedding_errors = 0usize;
 
-        // Phase 2: Generate embeddings in batches (if enabled)
+        // Phase 2: Generate embeddings in batches (if enabled and the text phase finished)
         #[cfg(feature = "embeddings")]
-        if with_embeddings && !embedding_batch.is_empty() {
+        if with_embeddings && !embedding_batch.is_empty() && !interrupted.load(Ordering::Relaxed) {
             // Filter out very short content (< 50 chars) and very long content (> 50KB)
             // These don't embed well or are too slow
             let filtered_batch: Vec<_> = embedding_batch
                 .into_iter()
                 .filter(|(_, content)| {
                     let len = content.len();
-                    len >= 50 && len <= 50_000
+                    (50..=50_000).contains(&len)
                 })
                 .collect();
 
+            // Documents embedded before an interrupted run stopped are kept
+            let embedded = if resuming { self.vector_index.doc_ids() } else { std::collections::HashSet::new() };
+            let (filtered_batch, resumed): (Vec<_>, Vec<_>) = filtered_batch
+                .into_iter()
+                .partition(|(doc_id, _)| !embedded.contains(doc_id));
+            total_embedded += resumed.len();
+            if !resumed.is_empty() {
+                eprintln!("Resuming: {} documents were already embedded.", resumed.len());
+            }
+
             if filtered_batch.is_empty() {
-                eprintln!("No documents suitable for semantic indexing.");
+                if resumed.is_empty() {
+                    eprintln!("No documents suitable for semantic indexing.");
+                }
             } else {
-                use indicatif::{ProgressBar, ProgressStyle};
-
                 let total_docs = filtered_batch.len() as u64;
                 eprintln!("Building semantic index for {} documents...", total_docs);
 
@@ -238,43 +412,37 @@ impl Workspace {
                 self.embedding_model.preload()?;
 
                 let pb = ProgressBar::new(total_docs);
-                pb.set_style(ProgressStyle::default_bar()
-                    .template("  [{bar:40.cyan/blue}] {pos}/{len} ({percent}%)")
-                    .unwrap()
-                    .progress_chars("━╸─"));
+                if show_bars {
+                    pb.set_style(bar_style("documents"));
+                    pb.set_message("embedding");
+                } else {
+                    pb.set_style(ProgressStyle::default_bar()
+                        .template("  [{bar:40.cyan/blue}] {pos}/{len} ({percent}%)")
+                        .unwrap()
+                        .progress_chars("━╸─"));
+                }
                 pb.enable_steady_tick(std::time::Duration::from_millis(100));
 
-                for chunk in filtered_batch.chunks(BATCH_SIZE) {
-                    // Truncate to ~4KB for embedding - sufficient context for code, faster tokenization
-                    // Use floor_char_boundary to avoid slicing in the middle of multi-byte UTF-8 characters
-                    const EMBED_TRUNCATE: usize = 4096;
-                    let texts: Vec<&str> = chunk.iter()
-                        .map(|(_, content)| {
-                            if content.len() > EMBED_TRUNCATE {
-                                let boundary = content.floor_char_boundary(EMBED_TRUNCATE);
-                                &content[..boundary]
-                            } else {
-                                content.as_str()
-                            }
-                        })
-                        .collect();
-
-                    match self.embedding_model.embed_batch(&texts) {
-                        Ok(embeddings) => {
-                            for ((doc_id, _), embedding) in chunk.iter().zip(embeddings) {
-                                if let Err(e) = self.vector_index.insert(doc_id, &embedding) {
-                                    tracing::debug!("Failed to insert embedding for {}: {}", doc_id, e);
-                                }
-                            }
-                            total_embedded += chunk.len();
-                            pb.set_position(total_embedded as u64);
-                        }
-                        Err(e) => {
-                            tracing::warn!("Batch embedding failed: {}", e);
-                            pb.inc(chunk.len() as u64);
-                        }
-                    }
-                }
+                // Batches run concurrently (up to indexer.threads); vector ids are assigned as
+                // each one is inserted, so the order batches finish in doesn't matter
+                let phase = std::time::Instant::now();
+                let (embedded, failed) = embed_documents(
+                    &filtered_batch,
+                    BATCH_SIZE,
+                    self.config.indexer.threads,
+                    interrupted,
+                    |texts| self.embedding_model.embed_batch(texts),
+                    &self.vector_index,
+                    |count| pb.inc(count as u64),
+                )?;
+                total_embedded += embedded;
+                embedding_errors += failed;
+                tracing::debug!(
+                    "Embedded {} documents in {:.1}s ({:.0} documents/s)",
+                    embedded,
+                    phase.elapsed().as_secs_f64(),
+                    embedded as f64 / phase.elapsed().as_secs_f64().max(0.001)
+                );
 
                 pb.finish_and_clear();
                 eprintln!("  Indexed {} documents.", total_embedded);
@@ -288,14 +456,26 @@ impl Workspace {
         }
 
         let stats = walker.stats();
+        let interrupted = interrupted.load(Ordering::Relaxed);
 
         // Save workspace metadata for index management
-        let metadata = serde_json::json!({
+        #[allow(unused_mut)]
+        let mut metadata = serde_json::json!({
             "workspace": self.root.to_string_lossy(),
             "indexed_at": chrono::Utc::now().to_rfc3339(),
             "files_indexed": indexed,
             "semantic": with_embeddings,
+            "interrupted": interrupted,
+            "no_ignore": self.config.indexer.no_ignore,
+            "schema_version": index::SCHEMA_VERSION,
+            "schema_hash": index::schema_hash(self.config.indexer.max_token_len),
+            "version": env!("CARGO_PKG_VERSION"),
         });
+        #[cfg(feature = "embeddings")]
+        if with_embeddings {
+            metadata["embedding_model"] = self.embedding_model.name().into();
+            metadata["embedding_dim"] = self.vector_index.dimension().into();
+        }
         let metadata_path = self.index_path.join("workspace.json");
         if let Err(e) = std::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata).unwrap_or_default()) {
             tracing::warn!("Failed to save workspace metadata: {}", e);
@@ -304,16 +484,47 @@ impl Workspace {
         Ok(IndexStats {
             indexed,
             embedded: total_embedded,
-            skipped,
+            skipped: skipped + stats.size_skipped,
             errors,
+            embedding_errors,
             unique_paths: stats.visited_paths,
+            deduplicated,
+            lossy_decoded,
+            interrupted,
         })
     }
 
+    /// Get a text searcher for this workspace's index (reusable across queries)
+    pub fn searcher(&self) -> search::Searcher {
+        let searcher = search::Searcher::new(self.config.search.clone(), self.index.clone()).with_reader(self.reader.clone());
+        match &self.scope {
+            Some(scope) => searcher.with_scope(scope),
+            None => searcher,
+        }
+    }
+
+    /// Find the closest indexed ancestor of `path` (with the default config's data dir)
+    ///
+    /// `open` already falls back to this workspace for unindexed subdirectories.
+    pub fn find_ancestor_workspace(path: &Path) -> Option<std::path::PathBuf> {
+        let path = std::fs::canonicalize(path).ok()?;
+        let config = Config::load();
+        find_indexed_parent(&path, &config.indexer.data_dir, &config.indexer.extra_roots)
+    }
+
+    /// Subdirectory (relative to `root()`) that searches are limited to, when this
+    /// workspace was opened from inside an indexed parent workspace
+    pub fn scope(&self) -> Option<&str> {
+        self.scope.as_deref()
+    }
+
     /// Search the workspace
     pub fn search(&self, query: &str, limit: Option<usize>) -> Result<search::SearchResult> {
-        let searcher = search::Searcher::new(self.config.search.clone(), self.index.clone());
-        searcher.search(query, limit)
+        if query.trim().is_empty() {
+            return Ok(search::SearchResult::empty());
+        }
+        let searcher = self.searcher();
+        searcher.search(query, limit, None)
     }
 
     /// Search with filters
@@ -321,26 +532,203 @@ impl Workspace {
         &self,
         query: &str,
         limit: Option<usize>,
+        offset: Option<usize>,
+        extensions: Option<Vec<String>>,
+        paths: Option<Vec<String>>,
+        use_regex: bool,
+    ) -> Result<search::SearchResult> {
+        let filters = search::SearchFilters { extensions, paths, ..Default::default() };
+        self.search_with_filters(query, limit, offset, filters, use_regex)
+    }
+
+    /// Search like `search`, with each snippet running from `context_lines` before the first
+    /// matching line of the hit to `context_lines` after the last one (at most 50 lines);
+    /// `line_start` is the snippet's first line
+    pub fn search_with_snippets(
+        &self,
+        query: &str,
+        context_lines: usize,
+        limit: Option<usize>,
+    ) -> Result<search::SearchResult> {
+        if query.trim().is_empty() {
+            return Ok(search::SearchResult::empty());
+        }
+        let searcher = self.searcher().with_snippet_context(context_lines);
+        searcher.search(query, limit, None)
+    }
+
+    /// Search with a full set of filters, including an exact-path allowlist (`files`)
+    pub fn search_with_filters(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        filters: search::SearchFilters,
+        use_regex: bool,
+    ) -> Result<search::SearchResult> {
+        let searcher = self.searcher();
+        let requested_limit = self.config.search.effective_limit(limit);
+        if query.trim().is_empty() {
+            return Ok(search::SearchResult::empty().with_query_context(query, &filters, requested_limit));
+        }
+        let result = searcher.search_filtered(query, limit, offset, filters.clone(), use_regex)?;
+        Ok(result.with_query_context(query, &filters, requested_limit))
+    }
+
+    /// Search several workspaces in parallel and merge the results (see `SearchResult::merge`)
+    /// Hit paths are prefixed with their workspace root
+    pub fn search_multi(workspaces: &[Workspace], query: &str, limit: Option<usize>) -> Result<search::SearchResult> {
+        Self::search_multi_filtered(workspaces, query, limit, None, None, None, false)
+    }
+
+    /// Search several workspaces in parallel with filters and merge the results
+    /// Hit paths are prefixed with their workspace root; `limit` and `offset` apply to the
+    /// merged set (the default limit is the first workspace's)
+    pub fn search_multi_filtered(
+        workspaces: &[Workspace],
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
         extensions: Option<Vec<String>>,
         paths: Option<Vec<String>>,
         use_regex: bool,
     ) -> Result<search::SearchResult> {
-        let searcher = search::Searcher::new(self.config.search.clone(), self.index.clone());
-        let filters = search::SearchFilters { extensions, paths };
-        searcher.search_filtered(query, limit, filters, use_regex)
+        use rayon::prelude::*;
+
+        let start = std::time::Instant::now();
+        let limit = limit.unwrap_or(workspaces.first().map_or(10, |w| w.config.search.default_limit));
+        let offset = offset.unwrap_or(0);
+
+        let results = workspaces
+            .par_iter()
+            .map(|workspace| {
+                // Each workspace must return enough hits to fill the merged page on its own
+                let mut result = workspace.search_filtered(
+                    query,
+                    Some(offset + limit),
+                    None,
+                    extensions.clone(),
+                    paths.clone(),
+                    use_regex,
+                )?;
+                for hit in &mut result.hits {
+                    hit.path = workspace.root.join(&hit.path).to_string_lossy().to_string();
+                }
+                Ok(result)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut result = results
+            .into_iter()
+            .reduce(search::SearchResult::merge)
+            .unwrap_or_else(search::SearchResult::empty);
+        // The total stays the sum of the workspaces' totals
+        result.hits = std::mem::take(&mut result.hits).into_iter().skip(offset).take(limit).collect();
+        result.query_time_ms = start.elapsed().as_millis() as u64;
+
+        let filters = search::SearchFilters { extensions, paths, ..Default::default() };
+        Ok(result.with_query_context(query, &filters, limit))
+    }
+
+    /// Search with a boolean expression over words, e.g. `error AND (timeout OR deadline) NOT test`
+    /// (see `search::BoolExpr` for the syntax)
+    pub fn search_boolean(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        extensions: Option<Vec<String>>,
+        paths: Option<Vec<String>>,
+    ) -> Result<search::SearchResult> {
+        let filters = search::SearchFilters { extensions, paths, ..Default::default() };
+        self.search_boolean_with_filters(query, limit, offset, filters)
+    }
+
+    /// Boolean search (see `search_boolean`) with a full set of filters
+    pub fn search_boolean_with_filters(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        filters: search::SearchFilters,
+    ) -> Result<search::SearchResult> {
+        let searcher = self.searcher();
+        let requested_limit = self.config.search.effective_limit(limit);
+        let result = searcher.search_boolean(query, limit, offset, filters.clone())?;
+        Ok(result.with_query_context(query, &filters, requested_limit))
+    }
+
+    /// List indexed files with no match for the query (like `grep -L`)
+    pub fn files_without_match(&self, query: &str, use_regex: bool) -> Result<Vec<String>> {
+        self.searcher().files_without_match(query, use_regex)
+    }
+
+    /// Search a single field, e.g. `path` or `content` (see `search::SEARCHABLE_FIELDS`)
+    pub fn search_field(
+        &self,
+        field_name: &str,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<search::SearchResult> {
+        self.search_field_with_filters(field_name, query, limit, offset, search::SearchFilters::default())
+    }
+
+    /// Field search (see `search_field`) with a full set of filters
+    pub fn search_field_with_filters(
+        &self,
+        field_name: &str,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        filters: search::SearchFilters,
+    ) -> Result<search::SearchResult> {
+        self.searcher().search_field(field_name, query, limit, offset, filters)
     }
 
     /// Hybrid search combining BM25 and vector search
     #[cfg(feature = "embeddings")]
-    pub fn search_hybrid(&self, query: &str, limit: Option<usize>) -> Result<search::SearchResult> {
+    ///
+    /// With `search.rerank_top_n` set, the top candidates are re-ranked by a cross-encoder.
+    pub fn search_hybrid(&self, query: &str, limit: Option<usize>, offset: Option<usize>) -> Result<search::SearchResult> {
+        self.search_hybrid_with_filters(query, limit, offset, search::SearchFilters::default())
+    }
+
+    /// Hybrid search limited to the files passing `filters` (see `HybridSearcher::with_filters`)
+    #[cfg(feature = "embeddings")]
+    pub fn search_hybrid_with_filters(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        filters: search::SearchFilters,
+    ) -> Result<search::SearchResult> {
+        // Nothing to match, and nothing worth embedding
+        if query.trim().is_empty() {
+            return Ok(search::SearchResult::empty());
+        }
+        let filters = search::SearchFilters { scope: filters.scope.or_else(|| self.scope.clone()), ..filters };
         let searcher = search::HybridSearcher::new(
             self.config.search.clone(),
             self.index.clone(),
             self.vector_index.clone(),
             self.embedding_model.clone(),
             self.embedding_cache.clone(),
-        );
-        searcher.search(query, limit)
+        )
+        .with_reranker(self.reranker.clone())
+        .with_filters(filters)
+        .with_reader(self.reader.clone());
+
+        let rerank_top_n = self.config.search.rerank_top_n;
+        if rerank_top_n == 0 {
+            return searcher.search(query, limit, offset);
+        }
+
+        let limit = self.config.search.effective_limit(limit);
+        let offset = offset.unwrap_or(0);
+        let mut result = searcher.search_with_rerank(query, Some(offset + limit), rerank_top_n)?;
+        result.hits.drain(..offset.min(result.hits.len()));
+        Ok(result)
     }
 
     /// Check if semantic search is available (vector index has data)
@@ -349,12 +737,90 @@ impl Workspace {
         !self.vector_index.is_empty()
     }
 
+    /// Embedding model used for this workspace's semantic index
+    #[cfg(feature = "embeddings")]
+    pub fn embedding_model_type(&self) -> embeddings::ModelType {
+        self.embedding_model.model_type()
+    }
+
     /// Check if semantic search is available (always false without embeddings feature)
     #[cfg(not(feature = "embeddings"))]
     pub fn has_semantic_index(&self) -> bool {
         false
     }
 
+    /// Current state of the index: documents, chunks, segments, vectors and size on disk
+    pub fn indexer_stats(&self) -> Result<IndexerStats> {
+        use tantivy::collector::Count;
+        use tantivy::query::TermQuery;
+        use tantivy::schema::IndexRecordOption;
+
+        let searcher = self.reader.searcher();
+        let fields = index::SchemaFields::new(&self.index.schema());
+
+        // Whole files have an empty chunk_id, so everything else is a chunk
+        let total_documents = searcher.num_docs();
+        let whole_files = TermQuery::new(
+            tantivy::Term::from_field_text(fields.chunk_id, ""),
+            IndexRecordOption::Basic,
+        );
+        let total_chunks = total_documents.saturating_sub(searcher.search(&whole_files, &Count)? as u64);
+
+        #[cfg(feature = "embeddings")]
+        let vector_count = self.vector_index.len();
+        #[cfg(not(feature = "embeddings"))]
+        let vector_count = 0;
+
+        Ok(IndexerStats {
+            total_documents,
+            total_chunks,
+            segment_count: self.index.searchable_segment_metas()?.len(),
+            vector_count,
+            index_size_bytes: dir_size(&self.index_path),
+        })
+    }
+
+    /// List every file in the index (parent documents only, sorted by path)
+    pub fn list_indexed_paths(&self) -> Result<Vec<IndexedFile>> {
+        use tantivy::collector::DocSetCollector;
+        use tantivy::query::AllQuery;
+        use tantivy::schema::Value;
+
+        let searcher = self.reader.searcher();
+        let fields = index::SchemaFields::new(&self.index.schema());
+
+        #[cfg(feature = "embeddings")]
+        let embedded = self.vector_index.doc_ids();
+
+        let mut files = Vec::new();
+        for address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: tantivy::TantivyDocument = searcher.doc(address)?;
+            let text = |field| doc.get_first(field).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            let number = |field| doc.get_first(field).and_then(|v| v.as_u64()).unwrap_or(0);
+
+            // Chunks carry a chunk_id, whole files don't
+            if !text(fields.chunk_id).is_empty() {
+                continue;
+            }
+
+            #[cfg(feature = "embeddings")]
+            let has_embedding = embedded.contains(&text(fields.doc_id));
+            #[cfg(not(feature = "embeddings"))]
+            let has_embedding = false;
+
+            files.push(IndexedFile {
+                path: text(fields.path),
+                line_count: number(fields.line_end),
+                mtime: number(fields.mtime),
+                size: number(fields.size),
+                has_embedding,
+            });
+        }
+
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
     /// Get the workspace root
     pub fn root(&self) -> &Path {
         &self.root
@@ -382,8 +848,9 @@ impl Workspace {
         )?;
 
         match indexer.index_file(path) {
-            Ok(_doc_id) => {
+            Ok(_) => {
                 indexer.commit()?;
+                self.reader.reload()?;
                 tracing::debug!("Indexed: {}", path.display());
                 Ok(())
             }
@@ -397,26 +864,102 @@ impl Workspace {
 
     /// Delete a file from the index (for incremental updates)
     pub fn delete_file(&self, path: &Path) -> Result<()> {
-        use tantivy::Term;
+        self.delete_paths(path, true)?;
+        tracing::debug!("Deleted from index: {}", path.display());
+        Ok(())
+    }
+
+    /// Delete every file under a directory from the index, with their chunks and vectors
+    /// Returns the number of files removed
+    pub fn delete_directory(&self, dir: &Path) -> Result<usize> {
+        let file_count = self.delete_paths(dir, false)?;
+        tracing::debug!("Deleted {} files under {} from index", file_count, dir.display());
+        Ok(file_count)
+    }
 
-        // Get the relative path as doc_id
-        let relative_path = path
-            .strip_prefix(&self.root)
-            .unwrap_or(path)
-            .to_string_lossy();
+    /// Delete the files under `path` (see `delete_under`) and commit, indexing a copy of each
+    /// deleted file in its place when identical files are deduplicated
+    /// Returns the number of files removed
+    fn delete_paths(&self, path: &Path, include_path: bool) -> Result<usize> {
+        let indexer = index::Indexer::new(self.config.indexer.clone(), self.index.clone(), &self.root)?;
+        let (paths, doc_ids) = self.delete_under(path, include_path, |query| indexer.delete_query(query))?;
+        if paths.is_empty() {
+            return Ok(0);
+        }
 
-        let schema = self.index.schema();
-        let doc_id_field = schema.get_field("doc_id").map_err(|_| {
-            YgrepError::Config("doc_id field not found in schema".to_string())
-        })?;
+        indexer.reindex_copies(doc_ids.clone(), &paths)?;
+        indexer.commit()?;
+        self.reader.reload()?;
+        #[cfg(feature = "embeddings")]
+        self.remove_unused_vectors(&doc_ids)?;
+        Ok(paths.len())
+    }
 
-        let term = Term::from_field_text(doc_id_field, &relative_path);
+    /// Delete the documents of every file under `path` (and of `path` itself with
+    /// `include_path`) through `delete`, leaving the commit to the caller
+    /// Returns the paths of the files matched and their doc_ids
+    fn delete_under(
+        &self,
+        path: &Path,
+        include_path: bool,
+        delete: impl FnOnce(Box<dyn tantivy::query::Query>) -> Result<()>,
+    ) -> Result<(std::collections::HashSet<String>, std::collections::HashSet<String>)> {
+        use tantivy::collector::DocSetCollector;
+        use tantivy::query::RegexQuery;
+        use tantivy::schema::Value;
+
+        let relative_path = index::writer::stored_path(path, &self.root);
+        let relative_path = relative_path.trim_end_matches('/');
+
+        let fields = index::SchemaFields::new(&self.index.schema());
+        let pattern = match (relative_path.is_empty(), include_path) {
+            (true, _) => ".*".to_string(),
+            (false, false) => format!("{}/.*", regex::escape(relative_path)),
+            (false, true) => format!("{}(/.*)?", regex::escape(relative_path)),
+        };
+        let query = RegexQuery::from_pattern(&pattern, fields.path)?;
+
+        // Collect doc_ids first (chunks share their file's path, so they match too)
+        let searcher = self.reader.searcher();
+        let mut paths = std::collections::HashSet::new();
+        let mut doc_ids = std::collections::HashSet::new();
+        for address in searcher.search(&query, &DocSetCollector)? {
+            let doc: tantivy::TantivyDocument = searcher.doc(address)?;
+            if let Some(doc_id) = doc.get_first(fields.doc_id).and_then(|v| v.as_str()) {
+                doc_ids.insert(doc_id.to_string());
+            }
+            if let Some(path) = doc.get_first(fields.path).and_then(|v| v.as_str()) {
+                paths.insert(path.to_string());
+            }
+        }
 
-        let mut writer = self.index.writer::<tantivy::TantivyDocument>(50_000_000)?;
-        writer.delete_term(term);
-        writer.commit()?;
+        if !paths.is_empty() {
+            delete(Box::new(query))?;
+        }
+        Ok((paths, doc_ids))
+    }
 
-        tracing::debug!("Deleted from index: {}", path.display());
+    /// Drop the vectors of deleted documents no other file still uses (identical files share
+    /// a doc_id when deduplicating); call after the deletes are committed and the reader reloaded
+    #[cfg(feature = "embeddings")]
+    fn remove_unused_vectors(&self, doc_ids: &std::collections::HashSet<String>) -> Result<()> {
+        use tantivy::collector::Count;
+        use tantivy::query::TermQuery;
+        use tantivy::schema::IndexRecordOption;
+        use tantivy::Term;
+
+        let fields = index::SchemaFields::new(&self.index.schema());
+        let searcher = self.reader.searcher();
+        let mut removed = false;
+        for doc_id in doc_ids {
+            let term = Term::from_field_text(fields.doc_id, doc_id);
+            if searcher.search(&TermQuery::new(term, IndexRecordOption::Basic), &Count)? == 0 {
+                removed |= self.vector_index.remove(doc_id);
+            }
+        }
+        if removed {
+            self.vector_index.save()?;
+        }
         Ok(())
     }
 
@@ -425,6 +968,98 @@ impl Workspace {
         FileWatcher::new(self.root.clone(), self.config.indexer.clone())
     }
 
+    /// Watch the workspace for changes and answer searches in one loop, as the only index writer
+    ///
+    /// File changes are indexed through one `IndexWriter` held for the whole loop and committed
+    /// after each debounce batch, then forwarded to `event_tx` (a full channel holds up the loop).
+    /// Searches from `search_rx` run between batches on a fresh reader, so they see the last
+    /// commit. Anything else that writes to this index (`index_file`, `ygrep index`) fails to
+    /// get the writer lock while this runs. Returns when `search_rx` is closed.
+    pub async fn watch_and_serve(
+        &self,
+        mut search_rx: tokio::sync::mpsc::Receiver<SearchRequest>,
+        event_tx: tokio::sync::mpsc::Sender<WatchEvent>,
+    ) -> Result<()> {
+        let mut watcher = self.create_watcher()?;
+        watcher.start()?;
+        let indexer = self.watch_indexer()?;
+
+        loop {
+            tokio::select! {
+                request = search_rx.recv() => {
+                    let Some(request) = request else { break };
+                    let SearchRequest { query, filters, limit, reply } = request;
+                    let result = self.search_filtered(&query, limit, None, filters.extensions, filters.paths, false);
+                    // The requester may have stopped waiting
+                    let _ = reply.send(result);
+                }
+                event = watcher.next_event() => {
+                    let Some(first) = event else { break };
+                    let mut batch = vec![first];
+                    while let Some(event) = watcher.try_next_event() {
+                        batch.push(event);
+                    }
+                    let mut deleted_doc_ids = std::collections::HashSet::new();
+                    for event in &batch {
+                        self.apply_watch_event(&indexer, event, &mut deleted_doc_ids);
+                    }
+                    indexer.commit()?;
+                    self.reader.reload()?;
+                    #[cfg(feature = "embeddings")]
+                    if let Err(e) = self.remove_unused_vectors(&deleted_doc_ids) {
+                        tracing::warn!("Failed to remove deleted files' vectors: {}", e.chain());
+                    }
+                    for event in batch {
+                        // Keep indexing even if nobody listens for events
+                        let _ = event_tx.send(event).await;
+                    }
+                }
+            }
+        }
+
+        watcher.stop()
+    }
+
+    /// Indexer for `watch_and_serve`, embedding changed files if the index is semantic
+    fn watch_indexer(&self) -> Result<index::Indexer> {
+        #[cfg(feature = "embeddings")]
+        if self.stored_semantic_flag().unwrap_or(false) {
+            return index::Indexer::with_semantic(
+                self.config.indexer.clone(),
+                self.index.clone(),
+                &self.root,
+                Arc::clone(&self.vector_index),
+                Arc::clone(&self.embedding_model),
+                Arc::clone(&self.embedding_cache),
+            );
+        }
+        index::Indexer::new(self.config.indexer.clone(), self.index.clone(), &self.root)
+    }
+
+    /// Apply one watch event through `indexer`, leaving the commit to the caller
+    /// The doc_ids of deleted files are added to `deleted_doc_ids`, for removing their vectors
+    fn apply_watch_event(&self, indexer: &index::Indexer, event: &WatchEvent, deleted_doc_ids: &mut std::collections::HashSet<String>) {
+        let outcome = match event {
+            WatchEvent::Changed(path) => match indexer.index_file(path) {
+                Err(YgrepError::FileTooLarge { .. }) => Ok(()),
+                result => result.map(|_| ()),
+            },
+            // Removals don't say whether a file or a directory went away
+            WatchEvent::Deleted(path) | WatchEvent::DirDeleted(path) => self
+                .delete_under(path, true, |query| indexer.delete_query(query))
+                .and_then(|(paths, doc_ids)| {
+                    // A copy of a deleted file is indexed in its place
+                    indexer.reindex_copies(doc_ids.clone(), &paths)?;
+                    deleted_doc_ids.extend(doc_ids);
+                    Ok(())
+                }),
+            WatchEvent::DirCreated(_) | WatchEvent::Error(_) => Ok(()),
+        };
+        if let Err(e) = outcome {
+            tracing::debug!("Failed to apply {:?}: {}", event, e.chain());
+        }
+    }
+
     /// Get the indexer config
     pub fn indexer_config(&self) -> &config::IndexerConfig {
         &self.config.indexer
@@ -444,6 +1079,20 @@ impl Workspace {
         }
     }
 
+    /// Whether the last full index ignored nothing (`indexer.no_ignore`, `ygrep index --no-ignore`)
+    /// Returns None if no metadata exists or the flag wasn't recorded
+    pub fn stored_no_ignore_flag(&self) -> Option<bool> {
+        read_workspace_metadata(&self.index_path)
+            .and_then(|v| v.get("no_ignore").and_then(|s| s.as_bool()))
+    }
+
+    /// Index or re-index a single file and update its embedding (for incremental updates)
+    /// Files outside the semantic size bounds (50 bytes - 50 KB) are indexed as text only
+    #[cfg(feature = "embeddings")]
+    pub fn index_file_with_embeddings(&self, path: &Path) -> Result<()> {
+        self.index_file_with_options(path, true)
+    }
+
     /// Index or re-index a single file with optional semantic indexing (for incremental updates)
     #[allow(unused_variables)]
     pub fn index_file_with_options(&self, path: &Path, with_embeddings: bool) -> Result<()> {
@@ -455,17 +1104,21 @@ impl Workspace {
         )?;
 
         match indexer.index_file(path) {
-            Ok(doc_id) => {
+            Ok(index::IndexedFile { doc_id, .. }) => {
                 indexer.commit()?;
+                self.reader.reload()?;
                 tracing::debug!("Indexed: {}", path.display());
 
                 // Generate embedding if semantic indexing is enabled
+                // (identical content is already embedded under the same doc_id when deduplicating)
                 #[cfg(feature = "embeddings")]
-                if with_embeddings {
-                    if let Ok(content) = std::fs::read_to_string(path) {
+                if with_embeddings
+                    && !(self.config.indexer.deduplicate && self.vector_index.contains(&doc_id))
+                {
+                    if let Ok((content, _)) = index::writer::read_text_lossy(path) {
                         // Only embed files within size bounds
                         let len = content.len();
-                        if len >= 50 && len <= 50_000 {
+                        if (50..=50_000).contains(&len) {
                             // Truncate for embedding
                             const EMBED_TRUNCATE: usize = 4096;
                             let text = if content.len() > EMBED_TRUNCATE {
@@ -477,17 +1130,17 @@ impl Workspace {
 
                             match self.embedding_model.embed(text) {
                                 Ok(embedding) => {
-                                    if let Err(e) = self.vector_index.insert(&doc_id, &embedding) {
-                                        tracing::debug!("Failed to insert embedding for {}: {}", doc_id, e);
+                                    if let Err(e) = self.vector_index.upsert(&doc_id, &embedding) {
+                                        tracing::debug!("Failed to insert embedding for {}: {}", doc_id, e.chain());
                                     } else {
                                         // Save vector index after each file (incremental)
                                         if let Err(e) = self.vector_index.save() {
-                                            tracing::debug!("Failed to save vector index: {}", e);
+                                            tracing::debug!("Failed to save vector index: {}", e.chain());
                                         }
                                     }
                                 }
                                 Err(e) => {
-                                    tracing::debug!("Failed to generate embedding for {}: {}", doc_id, e);
+                                    tracing::debug!("Failed to generate embedding for {}: {}", doc_id, e.chain());
                                 }
                             }
                         }
@@ -505,6 +1158,71 @@ impl Workspace {
     }
 }
 
+/// A set of indexed workspaces searched together (e.g. the projects of a monorepo)
+pub struct MultiWorkspace {
+    workspaces: Vec<Workspace>,
+}
+
+impl MultiWorkspace {
+    /// Open every indexed workspace found in the data directory
+    pub fn open_all() -> Result<Self> {
+        Self::open_all_with_config(Config::load())
+    }
+
+    /// Open every indexed workspace found in the configured data directory
+    /// Indexes whose workspace no longer exists (or fails to open) are skipped
+    pub fn open_all_with_config(config: Config) -> Result<Self> {
+        let indexes_dir = config.indexer.data_dir.join("indexes");
+        let mut workspaces = Vec::new();
+
+        if !indexes_dir.exists() {
+            return Ok(Self { workspaces });
+        }
+
+        for entry in std::fs::read_dir(&indexes_dir)? {
+            let metadata_path = entry?.path().join("workspace.json");
+            let root = std::fs::read_to_string(&metadata_path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .and_then(|v| v.get("workspace").and_then(|w| w.as_str()).map(std::path::PathBuf::from));
+
+            let Some(root) = root else { continue };
+            if !root.exists() {
+                continue;
+            }
+
+            match Workspace::open_with_config(&root, config.clone()) {
+                Ok(workspace) => workspaces.push(workspace),
+                Err(e) => tracing::debug!("Skipping workspace {}: {}", root.display(), e.chain()),
+            }
+        }
+
+        // Stable order so equal scores merge deterministically
+        workspaces.sort_by(|a, b| a.root.cmp(&b.root));
+
+        Ok(Self { workspaces })
+    }
+
+    /// Get the opened workspaces
+    pub fn workspaces(&self) -> &[Workspace] {
+        &self.workspaces
+    }
+
+    /// Search all workspaces with filters and merge the ranked results
+    /// (see `Workspace::search_multi_filtered`)
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        extensions: Option<Vec<String>>,
+        paths: Option<Vec<String>>,
+        use_regex: bool,
+    ) -> Result<search::SearchResult> {
+        Workspace::search_multi_filtered(&self.workspaces, query, limit, offset, extensions, paths, use_regex)
+    }
+}
+
 /// Statistics from an indexing operation
 #[derive(Debug, Clone, Default)]
 pub struct IndexStats {
@@ -512,30 +1230,889 @@ pub struct IndexStats {
     pub embedded: usize,
     pub skipped: usize,
     pub errors: usize,
+    /// Documents skipped by the semantic index because their embedding failed
+    pub embedding_errors: usize,
     pub unique_paths: usize,
+    /// Files not indexed because an identical file is (with `indexer.content_hash_dedup`)
+    pub deduplicated: usize,
+    /// Files with invalid UTF-8 (e.g. Latin-1), indexed with U+FFFD replacement characters
+    pub lossy_decoded: usize,
+    /// Indexing was stopped early (see `Workspace::index_all_interruptible`)
+    pub interrupted: bool,
 }
 
-/// Hash a path to create a unique identifier
-fn hash_path(path: &Path) -> String {
-    use xxhash_rust::xxh3::xxh3_64;
-    let hash = xxh3_64(path.to_string_lossy().as_bytes());
-    format!("{:016x}", hash)
+/// Current state of a workspace index (see `Workspace::indexer_stats`)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct IndexerStats {
+    /// Documents in the index (whole files and chunks)
+    pub total_documents: u64,
+    /// Documents that are chunks of a larger file
+    pub total_chunks: u64,
+    /// Searchable Tantivy segments
+    pub segment_count: usize,
+    /// Vectors in the semantic index (0 without one)
+    pub vector_count: usize,
+    /// Size of the index directory on disk
+    pub index_size_bytes: u64,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
-
-    #[test]
-    fn test_workspace_open() -> Result<()> {
-        let temp_dir = tempdir().unwrap();
+/// Embed `docs` (doc_id, content) in batches of `batch_size`, running up to `threads` batches
+/// at once (at most `MAX_PARALLEL_BATCHES`), and insert the vectors into `vector_index`
+///
+/// Returns how many documents were embedded and how many failed; a failed batch is skipped,
+/// and no new batches start once `interrupted` is set. `on_batch` gets each batch's size as it
+/// finishes (embedded or not).
+#[cfg(feature = "embeddings")]
+fn embed_documents(
+    docs: &[(String, String)],
+    batch_size: usize,
+    threads: usize,
+    interrupted: &std::sync::atomic::AtomicBool,
+    embed: impl Fn(&[&str]) -> Result<Vec<Vec<f32>>> + Sync,
+    vector_index: &VectorIndex,
+    on_batch: impl Fn(usize) + Sync,
+) -> Result<(usize, usize)> {
+    use crate::error::ResultExt;
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Truncate to ~4KB for embedding - sufficient context for code, faster tokenization
+    const EMBED_TRUNCATE: usize = 4096;
+
+    // fastembed gives its ONNX Runtime session one intra-op thread per CPU, so a single batch
+    // already keeps every core busy; a second one only overlaps tokenization and inserts with
+    // inference, and more would just oversubscribe the CPUs
+    const MAX_PARALLEL_BATCHES: usize = 2;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.clamp(1, MAX_PARALLEL_BATCHES))
+        .build()
+        .context("Failed to start embedding threads")?;
+
+    let embedded = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    pool.install(|| {
+        docs.par_chunks(batch_size.max(1)).for_each(|chunk| {
+            if interrupted.load(Ordering::Relaxed) {
+                return;
+            }
 
-        // Create a test file
-        std::fs::write(temp_dir.path().join("test.rs"), "fn main() {}").unwrap();
+            // Use floor_char_boundary to avoid slicing in the middle of multi-byte UTF-8 characters
+            let texts: Vec<&str> = chunk.iter()
+                .map(|(_, content)| &content[..content.floor_char_boundary(EMBED_TRUNCATE)])
+                .collect();
 
-        let workspace = Workspace::open(temp_dir.path())?;
-        assert!(workspace.root().exists());
+            match embed(&texts) {
+                Ok(embeddings) => {
+                    for ((doc_id, _), embedding) in chunk.iter().zip(embeddings) {
+                        if let Err(e) = vector_index.insert(doc_id, &embedding) {
+                            tracing::debug!("Failed to insert embedding for {}: {}", doc_id, e.chain());
+                        }
+                    }
+                    embedded.fetch_add(chunk.len(), Ordering::Relaxed);
+                }
+                Err(e) => {
+                    tracing::warn!("Batch embedding failed: {}", e.chain());
+                    failed.fetch_add(chunk.len(), Ordering::Relaxed);
+                }
+            }
+            on_batch(chunk.len());
+        });
+    });
+
+    Ok((embedded.into_inner(), failed.into_inner()))
+}
+
+/// Total size of the files under `path` (unreadable entries count as 0)
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// A file stored in a workspace index
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IndexedFile {
+    /// File path (relative to workspace)
+    pub path: String,
+    /// Number of lines in the file
+    pub line_count: u64,
+    /// Modification time (seconds since the Unix epoch)
+    pub mtime: u64,
+    /// File size in bytes
+    pub size: u64,
+    /// Whether the file has a semantic embedding
+    pub has_embedding: bool,
+}
+
+/// Check the schema hash stamped in workspace.json against the current schema and token limit
+/// Indexes written before the stamp existed are assumed compatible
+fn check_index_compatible(root: &Path, index_path: &Path, max_token_len: usize) -> Result<()> {
+    let metadata = read_workspace_metadata(index_path);
+
+    let Some(stored_hash) = metadata.as_ref().and_then(|v| v.get("schema_hash")).and_then(|h| h.as_str()) else {
+        return Ok(());
+    };
+
+    if stored_hash != index::schema_hash(max_token_len) {
+        let version = metadata.as_ref()
+            .and_then(|v| v.get("version"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        return Err(YgrepError::IncompatibleIndex { path: root.to_path_buf(), version });
+    }
+
+    Ok(())
+}
+
+/// Read the workspace.json metadata written after indexing
+fn read_workspace_metadata(index_path: &Path) -> Option<serde_json::Value> {
+    std::fs::read_to_string(index_path.join("workspace.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// Find the closest ancestor of `root` that has its own index, under the key `Workspace::open`
+/// would give it: its path plus `extra_roots` resolved against it
+fn find_indexed_parent(root: &Path, data_dir: &Path, extra_roots: &[std::path::PathBuf]) -> Option<std::path::PathBuf> {
+    root.ancestors()
+        .skip(1)
+        .find(|dir| {
+            let Ok(extra_roots) = resolve_extra_roots(dir, extra_roots) else {
+                return false;
+            };
+            indexed_data_dir(dir, &extra_roots, data_dir).is_some()
+        })
+        .map(Path::to_path_buf)
+}
+
+/// Canonicalize the configured extra roots, resolving relative ones against `root`
+fn resolve_extra_roots(root: &Path, extra_roots: &[std::path::PathBuf]) -> Result<Vec<std::path::PathBuf>> {
+    extra_roots
+        .iter()
+        .map(|dir| std::fs::canonicalize(root.join(dir)).map_err(|e| YgrepError::Config(
+            format!("Extra root {} not found: {}", dir.display(), e).into()
+        )))
+        .collect()
+}
+
+/// The data dir holding the index of `root`: the configured one (relative paths are
+/// resolved against the root), else the `ygrep index --local` dir inside the root
+fn indexed_data_dir(root: &Path, extra_roots: &[std::path::PathBuf], data_dir: &Path) -> Option<std::path::PathBuf> {
+    [root.join(data_dir), root.join(config::LOCAL_DATA_DIR)]
+        .into_iter()
+        .find(|dir| dir.join("indexes").join(index_key(root, extra_roots, dir)).join("workspace.json").exists())
+}
+
+/// Name of a workspace's index directory in `data_dir`
+///
+/// A data dir inside the root hashes the roots relative to it, so the index still belongs
+/// to the workspace after the repository is moved or cloned elsewhere; a shared data dir
+/// hashes the absolute roots to keep workspaces apart.
+fn index_key(root: &Path, extra_roots: &[std::path::PathBuf], data_dir: &Path) -> String {
+    use std::path::Component;
+
+    let inside_root = data_dir
+        .strip_prefix(root)
+        .is_ok_and(|relative| relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)));
+    if !inside_root {
+        return hash_roots(root, extra_roots);
+    }
+
+    // Extra roots outside the root keep their absolute path
+    let extra_roots: Vec<_> = extra_roots
+        .iter()
+        .map(|dir| dir.strip_prefix(root).map_or_else(|_| dir.clone(), |relative| Path::new(".").join(relative)))
+        .collect();
+    hash_roots(Path::new("."), &extra_roots)
+}
+
+/// Explain a failure to create the data directory, suggesting another `data_dir` when it isn't writable
+fn data_dir_error(data_dir: &Path, e: std::io::Error) -> YgrepError {
+    match e.kind() {
+        std::io::ErrorKind::ReadOnlyFilesystem | std::io::ErrorKind::PermissionDenied => YgrepError::Config(format!(
+            "Cannot create index directory in {} ({}). Set `data_dir` under [indexer] in .ygrep.toml or ~/.config/ygrep/config.toml to a writable directory",
+            data_dir.display(),
+            e
+        ).into()),
+        _ => YgrepError::Io(e),
+    }
+}
+
+/// Hash a path to create a unique identifier
+fn hash_path(path: &Path) -> String {
+    use xxhash_rust::xxh3::xxh3_64;
+    let hash = xxh3_64(path.to_string_lossy().as_bytes());
+    format!("{:016x}", hash)
+}
+
+/// Hash all of a workspace's roots, sorted and joined, so their order doesn't matter
+/// (a workspace without extra roots keeps the hash of its root path)
+fn hash_roots(root: &Path, extra_roots: &[std::path::PathBuf]) -> String {
+    let mut roots: Vec<_> = std::iter::once(root)
+        .chain(extra_roots.iter().map(|p| p.as_path()))
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    hash_path(Path::new(&roots.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    /// A workspace with `files` (relative path, content), indexed into its own data dir
+    fn indexed_workspace(files: &[(&str, &str)]) -> (TempDir, TempDir, Workspace) {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        for (path, content) in files {
+            let path = temp_dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config).unwrap();
+        workspace.index_all().unwrap();
+        (temp_dir, data_dir, workspace)
+    }
+
+    #[test]
+    fn test_workspace_open() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+
+        let data_dir = tempdir().unwrap();
+
+        // Create a test file
+        std::fs::write(temp_dir.path().join("test.rs"), "fn main() {}").unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+
+        // Opening fails until the workspace has been indexed
+        assert!(Workspace::open_with_config(temp_dir.path(), config.clone()).is_err());
+
+        Workspace::create_with_config(temp_dir.path(), config.clone())?.index_all()?;
+
+        let workspace = Workspace::open_with_config(temp_dir.path(), config)?;
+        assert!(workspace.root().exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_with_snippets() -> Result<()> {
+        let content = (1..=30)
+            .map(|i| match i {
+                12 | 15 => format!("    retry_request({});", i),
+                _ => format!("    step_{}();", i),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (_temp_dir, _data_dir, workspace) = indexed_workspace(&[("client.rs", &content)]);
+
+        let hit = &workspace.search_with_snippets("retry_request", 1, None)?.hits[0];
+        assert_eq!((hit.line_start, hit.line_end), (11, 16));
+        assert_eq!(hit.snippet.lines().next(), Some("    step_11();"));
+        assert_eq!(hit.snippet.lines().last(), Some("    step_16();"));
+
+        let result = workspace.search_with_snippets("retry_request", 0, None)?;
+        assert_eq!((result.hits[0].line_start, result.hits[0].line_end), (12, 15));
+        assert!(result.format_pretty().contains("client.rs:12-15\n  12: retry_request(12);\n  13: step_13();\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_language_filter() -> Result<()> {
+        let (_temp_dir, _data_dir, workspace) = indexed_workspace(&[
+            ("deploy", "#!/usr/bin/env python\nprint('release ready')\n"),
+            ("deploy.sh", "#!/bin/sh\necho 'release ready'\n"),
+            ("notes.md", "release ready\n"),
+        ]);
+
+        let search = |languages: &[&str]| -> Result<Vec<(String, String)>> {
+            let filters = search::SearchFilters {
+                languages: Some(languages.iter().map(|l| l.to_string()).collect()),
+                ..Default::default()
+            };
+            let mut hits: Vec<_> = workspace.search_with_filters("release ready", None, None, filters, false)?
+                .hits
+                .into_iter()
+                .map(|hit| (hit.path, hit.language))
+                .collect();
+            hits.sort();
+            Ok(hits)
+        };
+
+        assert_eq!(search(&["python"])?, [("deploy".to_string(), "python".to_string())]);
+        assert_eq!(search(&["Shell", "markdown"])?, [
+            ("deploy.sh".to_string(), "shell".to_string()),
+            ("notes.md".to_string(), "markdown".to_string()),
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_create_missing_data_dir() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("test.rs"), "fn main() {}").unwrap();
+
+        // Neither the data dir nor its parents exist yet
+        let mut config = Config::default();
+        config.indexer.data_dir = temp_dir.path().join("home/.local/share/ygrep");
+
+        Workspace::create_with_config(temp_dir.path(), config.clone())?.index_all()?;
+        assert!(config.indexer.data_dir.join("indexes").is_dir());
+
+        let err = data_dir_error(&config.indexer.data_dir, std::io::ErrorKind::ReadOnlyFilesystem.into());
+        assert!(matches!(err, YgrepError::Config(ref msg) if msg.to_string().contains("data_dir")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_sees_other_commits() -> Result<()> {
+        let (temp_dir, _data_dir, workspace) = indexed_workspace(&[("main.rs", "fn main() {}")]);
+        let searching = Workspace::open_with_config(temp_dir.path(), workspace.config.clone())?;
+        assert!(searching.search("late_arrival", None)?.hits.is_empty());
+
+        // Its own commits are visible at once
+        std::fs::write(temp_dir.path().join("late.rs"), "fn late_arrival() {}").unwrap();
+        workspace.index_file(&temp_dir.path().join("late.rs"))?;
+        assert_eq!(workspace.search("late_arrival", None)?.hits.len(), 1);
+
+        // Another writer's (e.g. `ygrep watch`) shortly after, without reopening
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while searching.search("late_arrival", None)?.hits.is_empty() {
+            assert!(std::time::Instant::now() < deadline, "commit never became visible");
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_directory() -> Result<()> {
+        let (_temp_dir, _data_dir, workspace) = indexed_workspace(&[
+            ("old/a.rs", "fn stale_helper() {}"),
+            ("old/nested/b.rs", "fn stale_helper() { 2 }"),
+            ("older.rs", "fn stale_helper() { 3 }"),
+        ]);
+        assert_eq!(workspace.search("stale_helper", None)?.hits.len(), 3);
+
+        assert_eq!(workspace.delete_directory(&workspace.root().join("old"))?, 2);
+        assert_eq!(workspace.delete_directory(&workspace.root().join("old"))?, 0);
+
+        // Only the sibling whose name shares the prefix is left
+        let result = workspace.search("stale_helper", None)?;
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "older.rs");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_under_extra_root() -> Result<()> {
+        let frontend = tempdir().unwrap();
+        let backend = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        std::fs::create_dir_all(backend.path().join("jobs")).unwrap();
+        std::fs::write(frontend.path().join("app.ts"), "function extraRootCleanup() {}").unwrap();
+        std::fs::write(backend.path().join("main.rs"), "fn extra_root_cleanup() {}").unwrap();
+        std::fs::write(backend.path().join("jobs/purge.rs"), "fn extra_root_cleanup() { 2 }").unwrap();
+        std::fs::write(backend.path().join("lib.rs"), "fn extra_root_cleanup() { 3 }").unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        config.indexer.extra_roots = vec![backend.path().to_path_buf()];
+        let workspace = Workspace::create_with_config(frontend.path(), config)?;
+        workspace.index_all()?;
+        let backend = std::fs::canonicalize(backend.path())?;
+        let paths = || -> Result<Vec<String>> {
+            Ok(workspace.list_indexed_paths()?.into_iter().map(|file| file.path).collect())
+        };
+        assert_eq!(paths()?.len(), 4);
+        workspace.delete_file(&backend.join("lib.rs"))?;
+
+        // Files outside the root are stored under their absolute path, and deleted by it
+        let indexer = workspace.watch_indexer()?;
+        workspace.apply_watch_event(&indexer, &WatchEvent::Deleted(backend.join("main.rs")), &mut Default::default());
+        indexer.commit()?;
+        workspace.reader.reload()?;
+        drop(indexer);
+        assert_eq!(paths()?, [backend.join("jobs/purge.rs").to_string_lossy().to_string(), "app.ts".to_string()]);
+
+        assert_eq!(workspace.delete_directory(&backend.join("jobs"))?, 1);
+        assert_eq!(paths()?, ["app.ts"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_subdirectory_of_indexed_workspace() -> Result<()> {
+        let (temp_dir, _data_dir, workspace) = indexed_workspace(&[
+            ("services/api/routes.rs", "fn shared_handler() {}"),
+            ("web/app.rs", "fn shared_handler() { 2 }"),
+        ]);
+        let config = workspace.config.clone();
+        drop(workspace);
+
+        // The subdirectory has no index of its own, so the parent index is searched
+        let subdir = std::fs::canonicalize(temp_dir.path().join("services/api"))?;
+        assert_eq!(find_indexed_parent(&subdir, &config.indexer.data_dir, &[]), Some(std::fs::canonicalize(temp_dir.path())?));
+        let workspace = Workspace::open_with_config(&subdir, config)?;
+        assert_eq!(workspace.root(), std::fs::canonicalize(temp_dir.path())?);
+        assert_eq!(workspace.scope(), Some("services/api/"));
+
+        let result = workspace.search("shared_handler", None)?;
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "services/api/routes.rs");
+        assert_eq!(workspace.files_without_match("shared_handler", false)?, Vec::<String>::new());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scoped_search_pagination() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("api")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("web")).unwrap();
+        for i in 0..3 {
+            std::fs::write(temp_dir.path().join(format!("api/route{}.rs", i)), "// many words before the match\nfn scoped_handler() {}").unwrap();
+        }
+        // Denser matches outside the scope, enough to fill any window fetched before filtering
+        for i in 0..60 {
+            std::fs::write(temp_dir.path().join(format!("web/page{}.rs", i)), "scoped_handler").unwrap();
+        }
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        config.search.max_limit = 20;
+        Workspace::create_with_config(temp_dir.path(), config.clone())?.index_all()?;
+
+        let subdir = std::fs::canonicalize(temp_dir.path().join("api"))?;
+        let workspace = Workspace::open_with_config(&subdir, config)?;
+        let paths = |result: search::SearchResult| result.hits.into_iter().map(|hit| hit.path).collect::<Vec<_>>();
+
+        let all = paths(workspace.search_with_filters("scoped_handler", Some(10), None, Default::default(), false)?);
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().all(|path| path.starts_with("api/")));
+
+        let page = paths(workspace.search_with_filters("scoped_handler", Some(2), Some(1), Default::default(), false)?);
+        assert_eq!(page, all[1..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_local_data_dir() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "fn local_index() {}").unwrap();
+
+        // A relative data_dir lives inside the workspace
+        let mut config = Config::default();
+        config.indexer.data_dir = config::LOCAL_DATA_DIR.into();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        workspace.index_all()?;
+        assert!(workspace.index_path().starts_with(std::fs::canonicalize(temp_dir.path())?.join(".ygrep-index")));
+
+        // and is found even when the configured data_dir is elsewhere
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::open_with_config(temp_dir.path(), config.clone())?;
+        assert_eq!(workspace.search("local_index", None)?.hits.len(), 1);
+        drop(workspace);
+
+        // or after the workspace is moved
+        let moved_dir = tempdir().unwrap();
+        let moved = moved_dir.path().join("moved");
+        std::fs::rename(temp_dir.path(), &moved).unwrap();
+        let workspace = Workspace::open_with_config(&moved, config)?;
+        assert_eq!(workspace.search("local_index", None)?.hits.len(), 1);
+        assert!(workspace.index_path().starts_with(std::fs::canonicalize(&moved)?.join(".ygrep-index")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_indexer_stats() -> Result<()> {
+        let long: String = (0..120).map(|i| format!("fn f{}() {{}}\n", i)).collect();
+        let (_temp_dir, _data_dir, workspace) = indexed_workspace(&[("small.rs", "fn small() {}"), ("long.rs", &long)]);
+
+        let stats = workspace.indexer_stats()?;
+        assert!(stats.total_chunks > 0);
+        assert_eq!(stats.total_documents, stats.total_chunks + 2);
+        assert!(stats.segment_count >= 1);
+        assert_eq!(stats.vector_count, 0);
+        assert!(stats.index_size_bytes > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_hash_dedup() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let content = "fn shared_helper() {\n    println!(\"identical content\");\n}\n";
+        std::fs::create_dir_all(temp_dir.path().join("src/copy")).unwrap();
+        std::fs::write(temp_dir.path().join("src/copy/helper.rs"), content).unwrap();
+        std::fs::write(temp_dir.path().join("helper.rs"), content).unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "fn main() { shared_helper(); }").unwrap();
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        config.indexer.content_hash_dedup = true;
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+
+        // Re-indexing finds the copy indexed by the previous run
+        for _ in 0..2 {
+            let stats = workspace.index_all()?;
+            assert_eq!((stats.indexed, stats.deduplicated), (2, 1));
+            let paths: Vec<_> = workspace.search("identical", None)?.hits.into_iter().map(|hit| hit.path).collect();
+            assert_eq!(paths, ["helper.rs"]);
+        }
+
+        // So does indexing a single new copy
+        std::fs::write(temp_dir.path().join("helper_copy.rs"), content).unwrap();
+        workspace.index_file(&temp_dir.path().join("helper_copy.rs"))?;
+        assert_eq!(workspace.search("identical", None)?.hits.len(), 1);
+        assert_eq!(workspace.files_without_match("identical", false)?, ["main.rs"]);
+
+        // Editing the indexed file indexes a copy in its place
+        let search = |query| -> Result<Vec<String>> {
+            Ok(workspace.search(query, None)?.hits.into_iter().map(|hit| hit.path).collect())
+        };
+        std::fs::write(temp_dir.path().join("helper.rs"), "fn edited_helper() {}\n").unwrap();
+        workspace.index_file(&temp_dir.path().join("helper.rs"))?;
+        assert_eq!(search("identical")?, ["helper_copy.rs"]);
+        assert_eq!(search("edited_helper")?, ["helper.rs"]);
+
+        // And so does deleting it
+        std::fs::remove_file(temp_dir.path().join("helper_copy.rs")).unwrap();
+        workspace.delete_file(&temp_dir.path().join("helper_copy.rs"))?;
+        assert_eq!(search("identical")?, ["src/copy/helper.rs"]);
+        assert_eq!(workspace.files_without_match("identical", false)?, ["helper.rs", "main.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_hash_dedup_stats() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let content = "fn shared_helper() {}\n";
+        for path in ["a.rs", "copy_b.rs", "copy_c.rs", "copy_d.rs", "copy_e.rs"] {
+            std::fs::write(temp_dir.path().join(path), content).unwrap();
+        }
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        config.indexer.content_hash_dedup = true;
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        let stats = workspace.index_all()?;
+        assert_eq!((stats.indexed, stats.deduplicated), (1, 4));
+
+        // Changing the indexed file re-indexes its copies before the walk reaches them,
+        // and each file still counts once
+        std::fs::write(temp_dir.path().join("a.rs"), "fn changed() {}\n").unwrap();
+        let stats = workspace.index_all()?;
+        assert_eq!((stats.indexed, stats.deduplicated), (2, 3));
+        let paths: Vec<_> = workspace.search("shared_helper", None)?.hits.into_iter().map(|hit| hit.path).collect();
+        assert_eq!(paths, ["copy_b.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_source_in_build_dir() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("build")).unwrap();
+        std::fs::write(temp_dir.path().join("build/compiler.rs"), "fn emit_bytecode() {}").unwrap();
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config.clone())?;
+        workspace.index_all()?;
+        assert!(workspace.search("emit_bytecode", None)?.hits.is_empty());
+
+        // Dropping "build" from both skip lists indexes it
+        config.indexer.skip_dirs.retain(|d| d != "build");
+        config.indexer.ignore_patterns.retain(|p| p != "**/build/**");
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        workspace.index_all()?;
+        let paths: Vec<_> = workspace.search("emit_bytecode", None)?.hits.into_iter().map(|hit| hit.path).collect();
+        assert_eq!(paths, ["build/compiler.rs"]);
+
+        Ok(())
+    }
+
+    /// Send a search to `watch_and_serve` and return the paths of the hits
+    async fn served_search(search_tx: &tokio::sync::mpsc::Sender<SearchRequest>, query: &str) -> Result<Vec<String>> {
+        let (reply, result) = tokio::sync::oneshot::channel();
+        let request = SearchRequest { query: query.to_string(), filters: Default::default(), limit: None, reply };
+        search_tx.send(request).await.expect("watch_and_serve stopped");
+        let mut paths: Vec<_> = result.await.expect("no reply")?.hits.into_iter().map(|hit| hit.path).collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    #[tokio::test]
+    async fn test_watch_and_serve() -> Result<()> {
+        // Under /tmp/.tmpXXXX, which the watcher used to treat as ignored and hidden
+        let (temp_dir, _data_dir, workspace) = indexed_workspace(&[("main.rs", "fn main() { start_server(); }")]);
+
+        let (search_tx, search_rx) = tokio::sync::mpsc::channel(4);
+        let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(16);
+        let client = async {
+            assert_eq!(served_search(&search_tx, "start_server").await?, ["main.rs"]);
+
+            // A new file is searchable once its event has been forwarded
+            std::fs::write(temp_dir.path().join("server.rs"), "fn start_server() {}").unwrap();
+            let event = tokio::time::timeout(std::time::Duration::from_secs(10), event_rx.recv()).await;
+            assert!(matches!(event, Ok(Some(WatchEvent::Changed(_)))), "{:?}", event);
+            assert_eq!(served_search(&search_tx, "start_server").await?, ["main.rs", "server.rs"]);
+
+            // Closing the search channel ends the loop
+            drop(search_tx);
+            Ok::<_, YgrepError>(())
+        };
+
+        let (served, searched) = tokio::join!(workspace.watch_and_serve(search_rx, event_tx), client);
+        searched?;
+        served
+    }
+
+    #[test]
+    fn test_index_invalid_utf8() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        // "café" in Latin-1: 0xE9 is not valid UTF-8 on its own
+        std::fs::write(temp_dir.path().join("legacy.c"), b"/* caf\xe9 */\nint latin_total(void);\n").unwrap();
+        std::fs::write(temp_dir.path().join("main.c"), "int main(void) { return latin_total(); }").unwrap();
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        let stats = workspace.index_all()?;
+        assert_eq!((stats.indexed, stats.errors, stats.lossy_decoded), (2, 0, 1));
+
+        let result = workspace.search("latin_total", None)?;
+        let legacy = result.hits.iter().find(|hit| hit.path == "legacy.c").expect("legacy.c not found");
+        assert!(legacy.snippet.contains("caf\u{FFFD}"), "{}", legacy.snippet);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_extra_roots() -> Result<()> {
+        let frontend = tempdir().unwrap();
+        let backend = tempdir().unwrap();
+        std::fs::write(frontend.path().join("app.ts"), "function renderDashboard() {}").unwrap();
+        std::fs::write(backend.path().join("main.rs"), "fn serve_dashboard() {}").unwrap();
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        config.indexer.extra_roots = vec![backend.path().to_path_buf()];
+        let workspace = Workspace::create_with_config(frontend.path(), config.clone())?;
+        assert_eq!(workspace.index_all()?.indexed, 2);
+
+        let result = workspace.search("serve_dashboard", None)?;
+        assert_eq!(result.hits.len(), 1);
+        assert!(result.hits[0].path.ends_with("main.rs"));
+
+        // The index belongs to the set of roots, not to the main root alone
+        let root = std::fs::canonicalize(frontend.path())?;
+        let extra = std::fs::canonicalize(backend.path())?;
+        assert_eq!(hash_roots(&root, &[]), hash_path(&root));
+        assert_ne!(workspace.index_path(), data_dir.path().join("indexes").join(hash_path(&root)));
+        assert_eq!(hash_roots(&root, std::slice::from_ref(&extra)), hash_roots(&extra, std::slice::from_ref(&root)));
+
+        config.indexer.extra_roots = vec![frontend.path().join("missing")];
+        assert!(matches!(Workspace::create_with_config(frontend.path(), config), Err(YgrepError::Config(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_subdirectory_of_workspace_with_extra_roots() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("app/src")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("shared")).unwrap();
+        std::fs::write(temp_dir.path().join("app/src/main.rs"), "fn extra_roots_handler() {}").unwrap();
+        std::fs::write(temp_dir.path().join("app/build.rs"), "fn extra_roots_handler() { 2 }").unwrap();
+        std::fs::write(temp_dir.path().join("shared/util.rs"), "fn extra_roots_handler() { 3 }").unwrap();
+
+        // Relative to the workspace root, so it doesn't resolve from the subdirectory
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        config.indexer.extra_roots = vec![std::path::PathBuf::from("../shared")];
+        Workspace::create_with_config(&temp_dir.path().join("app"), config.clone())?.index_all()?;
+
+        let app = std::fs::canonicalize(temp_dir.path().join("app"))?;
+        let subdir = app.join("src");
+        assert_eq!(find_indexed_parent(&subdir, &config.indexer.data_dir, &config.indexer.extra_roots), Some(app.clone()));
+        assert_eq!(find_indexed_parent(&subdir, &config.indexer.data_dir, &[]), None);
+
+        let workspace = Workspace::open_with_config(&subdir, config)?;
+        assert_eq!(workspace.root(), app);
+        assert_eq!(workspace.scope(), Some("src/"));
+        let result = workspace.search("extra_roots_handler", None)?;
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "src/main.rs");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_tokens_searchable() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let identifier = format!("generated_{}", "x".repeat(140));
+        let blob = "QUJD".repeat(100);
+        std::fs::write(temp_dir.path().join("gen.rs"), format!("const {}: u8 = 1;\nlet blob = \"{}\";", identifier, blob)).unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = temp_dir.path().join("data");
+        let workspace = Workspace::create_with_config(temp_dir.path(), config.clone())?;
+        workspace.index_all()?;
+
+        // 150 characters fit under the limit; the 400-character blob is found by its indexed prefix
+        assert_eq!(workspace.search(&identifier, None)?.hits.len(), 1);
+        assert_eq!(workspace.search(&blob, None)?.hits.len(), 1);
+
+        // The limit is part of the schema hash, so changing it asks for a rebuild
+        config.indexer.max_token_len = 50;
+        assert!(matches!(
+            Workspace::open_with_config(temp_dir.path(), config),
+            Err(YgrepError::IncompatibleIndex { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_definitions_only() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join("config.rs"),
+            "use std::fs;\n\n/// Load it\npub fn parse_config(path: &str) -> Config {\n    todo!()\n}\n",
+        ).unwrap();
+        std::fs::write(
+            temp_dir.path().join("main.rs"),
+            "fn main() {\n    let config = parse_config(\"a.toml\");\n}\n",
+        ).unwrap();
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        config.search.definitions_only = true;
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        workspace.index_all()?;
+
+        for use_regex in [false, true] {
+            let result = workspace.search_filtered("parse_config", None, None, None, None, use_regex)?;
+            assert_eq!(result.hits.len(), 1);
+            let hit = &result.hits[0];
+            assert_eq!(hit.path, "config.rs");
+            // Centered on the definition on line 4
+            assert_eq!(hit.line_start, 2);
+            assert!(hit.snippet.lines().nth(2).unwrap().starts_with("pub fn parse_config"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_with_progress() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        for i in 0..3 {
+            std::fs::write(temp_dir.path().join(format!("file{}.rs", i)), "fn main() {}").unwrap();
+        }
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+
+        // Tests don't run on a terminal, so this takes the plain output path
+        let stats = workspace.index_all_with_progress(false, true)?;
+        assert_eq!((stats.indexed, stats.errors), (3, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_interrupted() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        for i in 0..5 {
+            std::fs::write(temp_dir.path().join(format!("file{}.rs", i)), "fn interrupted_marker() {}").unwrap();
+        }
+
+        let data_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config.clone())?;
+
+        // Interrupted before the walk starts: the first file is finished, then indexing stops
+        let interrupted = std::sync::atomic::AtomicBool::new(true);
+        let stats = workspace.index_all_interruptible(false, false, &interrupted)?;
+        assert!(stats.interrupted);
+        assert_eq!(stats.indexed, 1);
+        drop(workspace);
+
+        // What was indexed is committed and the index opens and searches normally
+        let workspace = Workspace::open_with_config(temp_dir.path(), config)?;
+        let metadata = read_workspace_metadata(workspace.index_path()).unwrap();
+        assert_eq!(metadata["interrupted"], true);
+        assert_eq!(workspace.search("interrupted_marker", None)?.hits.len(), 1);
+
+        // Running again completes the index
+        let stats = workspace.index_all()?;
+        assert!(!stats.interrupted);
+        assert_eq!(workspace.search("interrupted_marker", None)?.hits.len(), 5);
+        assert_eq!(read_workspace_metadata(workspace.index_path()).unwrap()["interrupted"], false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_numbers_with_line_endings() -> Result<()> {
+        let (_temp_dir, _data_dir, workspace) = indexed_workspace(&[
+            ("crlf.rs", "fn a() {}\r\nfn b() {}\r\nlet crlf_marker = 1;\r\n"),
+            ("mac.rs", "fn a() {}\rfn b() {}\rlet mac_marker = 1;"),
+            ("unterminated.rs", "fn a() {}\nfn b() {}\nlet last_marker = 1;"),
+        ]);
+
+        // The marker is on line 3 of each file, and the snippet has no stray `\r`
+        for marker in ["crlf_marker", "mac_marker", "last_marker"] {
+            let result = workspace.search(marker, None)?;
+            assert_eq!(result.hits.len(), 1, "{}", marker);
+            let hit = &result.hits[0];
+            assert_eq!((hit.line_start, hit.line_end), (1, 3), "{}", marker);
+            assert!(hit.snippet.lines().nth(2).unwrap().contains(marker));
+            assert!(!hit.snippet.contains('\r'));
+        }
+        for file in workspace.list_indexed_paths()? {
+            assert_eq!(file.line_count, 3, "{}", file.path);
+        }
 
         Ok(())
     }
@@ -551,11 +2128,12 @@ mod tests {
         let mut config = Config::default();
         config.indexer.data_dir = temp_dir.path().join("data");
 
-        let workspace = Workspace::open_with_config(temp_dir.path(), config)?;
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
 
         // Index
         let stats = workspace.index_all()?;
         assert!(stats.indexed >= 2);
+        assert_eq!(stats.embedded, 0); // Text-only
 
         // Search
         let result = workspace.search("hello", None)?;
@@ -564,4 +2142,283 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_search_empty_query() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("hello.rs"), "fn hello_world() {}").unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = temp_dir.path().join("data");
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        workspace.index_all()?;
+
+        for query in ["", "   \t"] {
+            assert!(workspace.search(query, None)?.is_empty());
+            let result = workspace.search_filtered(query, Some(5), None, Some(vec!["rs".to_string()]), None, false)?;
+            assert!(result.is_empty());
+            assert_eq!(result.limit, 5);
+            // Returns before the embedding model would be loaded
+            #[cfg(feature = "embeddings")]
+            assert!(workspace.search_hybrid(query, None, None)?.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_indexed_paths() -> Result<()> {
+        // Long enough to be split into chunks, which must not be listed
+        let long_file: String = (0..120).map(|i| format!("let x{} = {};\n", i, i)).collect();
+        let (_temp_dir, _data_dir, workspace) = indexed_workspace(&[("b.rs", "fn b() {}\n"), ("a.rs", &long_file)]);
+
+        let files = workspace.list_indexed_paths()?;
+        let paths: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.rs", "b.rs"]);
+        assert_eq!(files[0].line_count, 120);
+        assert_eq!(files[0].size, long_file.len() as u64);
+        assert!(!files[1].has_embedding);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_workspace_search() -> Result<()> {
+        let data_dir = tempdir().unwrap();
+        let project_a = tempdir().unwrap();
+        let project_b = tempdir().unwrap();
+
+        std::fs::write(project_a.path().join("a.rs"), "fn shared_helper() {}").unwrap();
+        std::fs::write(project_b.path().join("b.rs"), "fn shared_helper() {}").unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+
+        Workspace::create_with_config(project_a.path(), config.clone())?.index_all()?;
+        Workspace::create_with_config(project_b.path(), config.clone())?.index_all()?;
+
+        let multi = MultiWorkspace::open_all_with_config(config)?;
+        assert_eq!(multi.workspaces().len(), 2);
+
+        let result = multi.search_filtered("shared_helper", Some(10), None, None, None, false)?;
+        assert_eq!(result.hits.len(), 2);
+        assert!(result.hits.iter().all(|h| Path::new(&h.path).is_absolute()));
+
+        // The global limit applies across the merged set
+        let result = multi.search_filtered("shared_helper", Some(1), None, None, None, false)?;
+        assert_eq!(result.hits.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_multi() -> Result<()> {
+        let data_dir = tempdir().unwrap();
+        let project_a = tempdir().unwrap();
+        let project_b = tempdir().unwrap();
+        std::fs::write(project_a.path().join("a.rs"), "fn parse_config() { parse_config_file(); }").unwrap();
+        std::fs::write(project_a.path().join("notes.md"), "parse_config is called on startup").unwrap();
+        std::fs::write(project_b.path().join("b.rs"), "fn parse_config() {}").unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspaces = [
+            Workspace::create_with_config(project_a.path(), config.clone())?,
+            Workspace::create_with_config(project_b.path(), config)?,
+        ];
+        for workspace in &workspaces {
+            workspace.index_all()?;
+        }
+
+        let result = Workspace::search_multi(&workspaces, "parse_config", None)?;
+        assert_eq!(result.hits.len(), 3);
+        assert!(result.hits.iter().all(|hit| Path::new(&hit.path).is_absolute()));
+        assert!(result.hits.iter().all(|hit| (0.0..=1.0).contains(&hit.score)));
+        // The best hit of each workspace scores 1
+        assert_eq!(result.hits.iter().filter(|hit| hit.score == 1.0).count(), 2);
+
+        assert_eq!(result.total, 3);
+
+        // The total still counts the hits of both workspaces
+        let result = Workspace::search_multi(&workspaces, "parse_config", Some(1))?;
+        assert_eq!((result.hits.len(), result.total), (1, 3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_limit_above_default_max() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        for i in 0..300 {
+            std::fs::write(temp_dir.path().join(format!("file{}.rs", i)), "fn handle_request() {}").unwrap();
+        }
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config.clone())?;
+        workspace.index_all()?;
+
+        // `max_limit` caps larger requests, and no limit means `default_limit`
+        assert_eq!(workspace.search("handle_request", Some(200))?.hits.len(), 100);
+        assert_eq!(workspace.search("handle_request", None)?.hits.len(), 10);
+
+        // Raised, as `ygrep -n 200` does, the request is honored
+        config.search.max_limit = 200;
+        let workspace = Workspace::open_with_config(temp_dir.path(), config)?;
+        assert_eq!(workspace.search("handle_request", Some(200))?.hits.len(), 200);
+        let result = workspace.search_filtered("handle_request", Some(200), Some(150), None, None, false)?;
+        assert_eq!(result.hits.len(), 150);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_open_incompatible_index() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("test.rs"), "fn main() {}").unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+
+        let workspace = Workspace::create_with_config(temp_dir.path(), config.clone())?;
+        workspace.index_all()?;
+
+        // Simulate an index written by a ygrep with a different schema
+        let metadata_path = workspace.index_path().join("workspace.json");
+        let mut metadata: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&metadata_path)?).unwrap();
+        metadata["schema_hash"] = "0000000000000000".into();
+        metadata["version"] = "0.1.0".into();
+        std::fs::write(&metadata_path, metadata.to_string())?;
+
+        assert!(matches!(
+            Workspace::open_with_config(temp_dir.path(), config),
+            Err(YgrepError::IncompatibleIndex { version, .. }) if version == "0.1.0"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "embeddings")]
+    fn test_embed_documents_concurrently() -> Result<()> {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        let temp_dir = tempdir().unwrap();
+        let docs: Vec<(String, String)> = (0..250)
+            .map(|i| (format!("{:016x}", i), format!("fn handler_{}() {{}}", i)))
+            .collect();
+        // Stand-in for the model: one vector per text, failing one batch
+        let embed = |texts: &[&str]| -> Result<Vec<Vec<f32>>> {
+            if texts.iter().any(|text| text.contains("handler_70()")) {
+                return Err(YgrepError::Search("model error".to_string()));
+            }
+            Ok(texts.iter().map(|text| vec![text.len() as f32, 1.0, 0.5]).collect())
+        };
+
+        let run = |threads: usize, dir: &str| -> Result<(usize, usize, usize, usize)> {
+            let vector_index = VectorIndex::new(temp_dir.path().join(dir), 3)?;
+            let progress = AtomicUsize::new(0);
+            let (embedded, failed) = embed_documents(
+                &docs, 16, threads, &AtomicBool::new(false), embed, &vector_index,
+                |count| { progress.fetch_add(count, Ordering::Relaxed); },
+            )?;
+            Ok((embedded, failed, vector_index.len(), progress.into_inner()))
+        };
+
+        let serial = run(1, "serial")?;
+        assert_eq!(serial, (234, 16, 234, 250));
+        assert_eq!(run(4, "concurrent")?, serial);
+
+        // Nothing starts once interrupted
+        let vector_index = VectorIndex::new(temp_dir.path().join("interrupted"), 3)?;
+        let result = embed_documents(&docs, 16, 4, &AtomicBool::new(true), embed, &vector_index, |_| {})?;
+        assert_eq!(result, (0, 0));
+        assert!(vector_index.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    #[cfg(feature = "embeddings")]
+    fn test_identical_files_share_embedding() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        let content = "fn shared() {\n    println!(\"identical content in two files\");\n}\n";
+        std::fs::write(temp_dir.path().join("a.rs"), content).unwrap();
+        std::fs::write(temp_dir.path().join("b.rs"), content).unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        let stats = workspace.index_all_with_options(true)?;
+
+        // Both paths are in the text index, but only one vector is stored
+        assert_eq!(stats.indexed, 2);
+        assert_eq!(workspace.vector_index.len(), 1);
+        let hits = workspace.search("identical", None)?.hits;
+        assert!(hits.iter().any(|hit| hit.path == "a.rs"));
+        assert!(hits.iter().any(|hit| hit.path == "b.rs"));
+
+        Ok(())
+    }
+
+    // Requires the embedding model download
+    #[test]
+    #[ignore]
+    #[cfg(feature = "embeddings")]
+    fn test_index_all_reports_embedded() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join("server.rs"),
+            "fn start_server(port: u16) {\n    println!(\"listening on {}\", port);\n}\n",
+        ).unwrap();
+        std::fs::write(temp_dir.path().join("tiny.rs"), "fn a() {}").unwrap(); // Too short to embed
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        let stats = workspace.index_all_with_options(true)?;
+
+        assert_eq!(stats.indexed, 2);
+        assert_eq!(stats.embedded, 1);
+        assert_eq!(workspace.vector_index.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "embeddings")]
+    fn test_workspace_open_embedding_dimension_mismatch() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("test.rs"), "fn main() {}").unwrap();
+
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+
+        let workspace = Workspace::create_with_config(temp_dir.path(), config.clone())?;
+        workspace.index_all()?;
+
+        // Index stamped with 768-dim embeddings but a 384-dim model
+        let metadata_path = workspace.index_path().join("workspace.json");
+        let mut metadata: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&metadata_path)?).unwrap();
+        metadata["embedding_model"] = ModelType::AllMiniLmL6.name().into();
+        metadata["embedding_dim"] = 768.into();
+        std::fs::write(&metadata_path, metadata.to_string())?;
+
+        assert!(matches!(
+            Workspace::open_with_config(temp_dir.path(), config),
+            Err(YgrepError::EmbeddingDimensionMismatch { index: 768, model: 384, .. })
+        ));
+
+        Ok(())
+    }
 }
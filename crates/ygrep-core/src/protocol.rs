@@ -0,0 +1,136 @@
+//! Line-delimited JSON protocol for editor integrations (`ygrep --proto`)
+//!
+//! Each line read is one `Request` and each line written is one `Response`, so an editor can
+//! keep a single ygrep process alive and send it searches over stdin/stdout:
+//!
+//! ```text
+//! > {"type":"search","query":"fn main","limit":5}
+//! < {"type":"success","data":{"hits":[...],"total":1,...}}
+//! > {"type":"search"}
+//! < {"type":"error","message":"Invalid request: missing field `query` ..."}
+//! ```
+
+use std::io::{BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::search::SearchResult;
+use crate::Workspace;
+
+/// A request from the client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Literal (or, with `regex`, regex) text search, as with `Workspace::search_filtered`
+    Search {
+        query: String,
+        /// Maximum number of results (None = the configured default)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<usize>,
+        /// Ranked hits to skip first
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<usize>,
+        /// Only files with these extensions
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        extensions: Option<Vec<String>>,
+        /// Only paths matching these globs or substrings
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        paths: Option<Vec<String>>,
+        /// Treat `query` as a regex
+        #[serde(default)]
+        regex: bool,
+    },
+}
+
+/// The reply to one request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Success { data: Box<SearchResult> },
+    Error { message: String },
+}
+
+/// Answer one request against `workspace`
+pub fn handle(workspace: &Workspace, request: Request) -> Response {
+    let result = match request {
+        Request::Search { query, limit, offset, extensions, paths, regex } => {
+            workspace.search_filtered(&query, limit, offset, extensions, paths, regex)
+        }
+    };
+    match result {
+        Ok(data) => Response::Success { data: Box::new(data) },
+        Err(e) => Response::Error { message: e.chain() },
+    }
+}
+
+/// Read requests from `input` until it closes, writing a response line for each (blank
+/// lines are skipped; malformed ones get an error response)
+pub fn serve(workspace: &Workspace, input: impl BufRead, mut output: impl Write) -> Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle(workspace, request),
+            Err(e) => Response::Error { message: format!("Invalid request: {}", e) },
+        };
+        let json = serde_json::to_string(&response)
+            .unwrap_or_else(|e| serde_json::json!({ "type": "error", "message": e.to_string() }).to_string());
+        writeln!(output, "{}", json)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_search_round_trip() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let data_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "fn main() {\n    run_server();\n}").unwrap();
+        let mut config = Config::default();
+        config.indexer.data_dir = data_dir.path().to_path_buf();
+        let workspace = Workspace::create_with_config(temp_dir.path(), config)?;
+        workspace.index_all()?;
+
+        let request = Request::Search {
+            query: "run_server".to_string(),
+            limit: Some(5),
+            offset: None,
+            extensions: None,
+            paths: None,
+            regex: false,
+        };
+        let line = serde_json::to_string(&request).unwrap();
+        assert_eq!(line, r#"{"type":"search","query":"run_server","limit":5,"regex":false}"#);
+        assert_eq!(serde_json::from_str::<Request>(&line).unwrap(), request);
+
+        let input = format!("{}\n\nnot json\n", line);
+        let mut output = Vec::new();
+        serve(&workspace, input.as_bytes(), &mut output)?;
+
+        let responses: Vec<Response> = String::from_utf8(output).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 2);
+        match &responses[0] {
+            Response::Success { data } => {
+                assert_eq!(data.hits.len(), 1);
+                assert_eq!(data.hits[0].path, "main.rs");
+                assert!(data.hits[0].snippet.contains("run_server();"));
+            }
+            Response::Error { message } => panic!("search failed: {}", message),
+        }
+        assert!(matches!(&responses[1], Response::Error { message } if message.starts_with("Invalid request")));
+
+        Ok(())
+    }
+}
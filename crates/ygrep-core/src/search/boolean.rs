@@ -0,0 +1,286 @@
+//! Boolean query syntax (`-Q`), e.g. `error AND (timeout OR deadline) NOT test`
+//!
+//! Precedence, tightest first: `NOT` (or a leading `-`), `AND`, `OR`. Adjacent terms
+//! without an operator are ANDed, and parentheses group. `"quoted words"` match as an
+//! adjacent phrase in the content. Operators are only recognised in upper case; words
+//! are matched case-insensitively against the content and the file path.
+
+use tantivy::query::{AllQuery, BooleanQuery, Occur, PhraseQuery, Query, TermQuery};
+use tantivy::schema::IndexRecordOption;
+use tantivy::Term;
+
+use crate::error::{Result, YgrepError};
+use crate::index::schema::SchemaFields;
+
+/// A parsed boolean query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoolExpr {
+    /// A single (lowercased) word
+    Term(String),
+    /// Words that must appear next to each other
+    Phrase(Vec<String>),
+    Not(Box<BoolExpr>),
+    And(Vec<BoolExpr>),
+    Or(Vec<BoolExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl BoolExpr {
+    /// Parse a boolean query string
+    pub fn parse(query: &str) -> Result<Self> {
+        let tokens = tokenize(query);
+        if tokens.is_empty() {
+            return Err(invalid("empty query"));
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(Token::Close) => Err(invalid("unmatched ')'")),
+            Some(token) => Err(invalid(&format!("unexpected {:?}", token))),
+        }
+    }
+
+    /// Words that must or may match (not under a `NOT`), used to pick snippet lines
+    pub fn positive_terms(&self) -> Vec<&str> {
+        match self {
+            BoolExpr::Term(word) => vec![word.as_str()],
+            BoolExpr::Phrase(words) => words.iter().map(String::as_str).collect(),
+            BoolExpr::Not(_) => vec![],
+            BoolExpr::And(exprs) | BoolExpr::Or(exprs) => exprs.iter().flat_map(|e| e.positive_terms()).collect(),
+        }
+    }
+
+    /// Build the Tantivy query for this expression
+    pub(crate) fn to_query(&self, fields: &SchemaFields) -> Box<dyn Query> {
+        match self {
+            BoolExpr::Term(word) => term_query(fields, word),
+            BoolExpr::Phrase(words) if words.len() == 1 => term_query(fields, &words[0]),
+            BoolExpr::Phrase(words) => {
+                let terms = words.iter().map(|word| Term::from_field_text(fields.content, word)).collect();
+                Box::new(PhraseQuery::new(terms))
+            }
+            // A lone NOT excludes its matches from every document
+            BoolExpr::Not(expr) => Box::new(BooleanQuery::new(vec![
+                (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+                (Occur::MustNot, expr.to_query(fields)),
+            ])),
+            BoolExpr::And(exprs) => {
+                let has_positive = exprs.iter().any(|e| !matches!(e, BoolExpr::Not(_)));
+                let clauses = exprs
+                    .iter()
+                    .map(|expr| match expr {
+                        // Excluded directly when something else in the AND must match
+                        BoolExpr::Not(inner) if has_positive => (Occur::MustNot, inner.to_query(fields)),
+                        _ => (Occur::Must, expr.to_query(fields)),
+                    })
+                    .collect();
+                Box::new(BooleanQuery::new(clauses))
+            }
+            BoolExpr::Or(exprs) => {
+                let clauses = exprs.iter().map(|expr| (Occur::Should, expr.to_query(fields))).collect();
+                Box::new(BooleanQuery::new(clauses))
+            }
+        }
+    }
+}
+
+/// Match a word in the content or the path
+fn term_query(fields: &SchemaFields, word: &str) -> Box<dyn Query> {
+    let clauses = [fields.content, fields.path_text]
+        .iter()
+        .map(|&field| {
+            let term = Term::from_field_text(field, word);
+            let query: Box<dyn Query> = Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+            (Occur::Should, query)
+        })
+        .collect();
+    Box::new(BooleanQuery::new(clauses))
+}
+
+fn invalid(message: &str) -> YgrepError {
+    YgrepError::Search(format!("Invalid query syntax: {}", message))
+}
+
+/// Split the query into words, quoted phrases, operators and parentheses
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                tokens.push(Token::Quoted(phrase));
+            }
+            '-' => {
+                // `-word` is shorthand for `NOT word`
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Split text into lowercased index words, the same way literal search extracts terms
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> Result<BoolExpr> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            exprs.push(self.parse_and()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { BoolExpr::Or(exprs) })
+    }
+
+    fn parse_and(&mut self) -> Result<BoolExpr> {
+        let mut exprs = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.pos += 1;
+                    exprs.push(self.parse_unary()?);
+                }
+                // Implicit AND between adjacent terms
+                Some(Token::Word(_) | Token::Quoted(_) | Token::Not | Token::Open) => {
+                    exprs.push(self.parse_unary()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { BoolExpr::And(exprs) })
+    }
+
+    fn parse_unary(&mut self) -> Result<BoolExpr> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Not) => Ok(BoolExpr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(invalid("missing ')'"));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(Token::Word(word)) => match words(&word).as_slice() {
+                [] => Err(invalid(&format!("'{}' has no searchable characters", word))),
+                [single] => Ok(BoolExpr::Term(single.clone())),
+                // e.g. `foo.bar` is searched as the phrase "foo bar"
+                several => Ok(BoolExpr::Phrase(several.to_vec())),
+            },
+            Some(Token::Quoted(phrase)) => {
+                let words = words(&phrase);
+                if words.is_empty() {
+                    return Err(invalid("empty phrase"));
+                }
+                Ok(BoolExpr::Phrase(words))
+            }
+            Some(Token::And | Token::Or) => Err(invalid("AND/OR needs a term on each side")),
+            Some(Token::Close) => Err(invalid("unmatched ')'")),
+            None => Err(invalid("unexpected end of query")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(word: &str) -> BoolExpr {
+        BoolExpr::Term(word.to_string())
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let expr = BoolExpr::parse("error AND (timeout OR deadline) NOT test").unwrap();
+        assert_eq!(
+            expr,
+            BoolExpr::And(vec![
+                term("error"),
+                BoolExpr::Or(vec![term("timeout"), term("deadline")]),
+                BoolExpr::Not(Box::new(term("test"))),
+            ])
+        );
+
+        // AND binds tighter than OR; adjacent terms are ANDed; `-` negates
+        assert_eq!(
+            BoolExpr::parse("a b OR -c").unwrap(),
+            BoolExpr::Or(vec![BoolExpr::And(vec![term("a"), term("b")]), BoolExpr::Not(Box::new(term("c")))])
+        );
+
+        assert_eq!(
+            BoolExpr::parse("\"Connection Refused\" or").unwrap(),
+            BoolExpr::And(vec![
+                BoolExpr::Phrase(vec!["connection".to_string(), "refused".to_string()]),
+                term("or"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for query in ["", "(a OR b", "a OR", "a)", "AND a", "++"] {
+            let err = BoolExpr::parse(query).unwrap_err();
+            assert!(err.to_string().contains("Invalid query syntax"), "{}: {}", query, err);
+        }
+    }
+}
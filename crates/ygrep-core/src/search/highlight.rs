@@ -0,0 +1,102 @@
+//! ANSI highlighting of query matches in terminal output
+
+/// Color used for highlighted matches (always drawn bold)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnsiColor {
+    #[default]
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl AnsiColor {
+    /// SGR foreground color code
+    fn code(self) -> u8 {
+        match self {
+            AnsiColor::Red => 31,
+            AnsiColor::Green => 32,
+            AnsiColor::Yellow => 33,
+            AnsiColor::Blue => 34,
+            AnsiColor::Magenta => 35,
+            AnsiColor::Cyan => 36,
+        }
+    }
+}
+
+/// Wrap every case-insensitive occurrence of `query` in `text` in bold colored ANSI codes
+pub fn highlight_query_in_text(text: &str, query: &str, color: AnsiColor) -> String {
+    if query.is_empty() {
+        return text.to_string();
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end)) = find_ignore_case(rest, query) {
+        output.push_str(&rest[..start]);
+        output.push_str(&format!("\x1b[1;{}m{}\x1b[0m", color.code(), &rest[start..end]));
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Byte range of the first case-insensitive occurrence of `needle` in `haystack`
+pub(crate) fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    let needle = needle.to_lowercase();
+    if needle.is_empty() {
+        return None;
+    }
+
+    // Lowercase once, keeping where each character starts in both strings, since
+    // lowercasing can change byte lengths
+    let mut lowered = String::with_capacity(haystack.len());
+    let mut boundaries = Vec::with_capacity(haystack.len() + 1);
+    for (start, c) in haystack.char_indices() {
+        boundaries.push((lowered.len(), start));
+        lowered.extend(c.to_lowercase());
+    }
+    boundaries.push((lowered.len(), haystack.len()));
+    let original = |offset: usize| {
+        let index = boundaries.binary_search_by_key(&offset, |&(lowered, _)| lowered).ok()?;
+        Some(boundaries[index].1)
+    };
+
+    // A match has to cover whole characters of the haystack
+    let mut from = 0;
+    while let Some(found) = lowered[from..].find(&needle) {
+        let start = from + found;
+        if let (Some(start), Some(end)) = (original(start), original(start + needle.len())) {
+            return Some((start, end));
+        }
+        from = start + lowered[start..].chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_query_in_text() {
+        assert_eq!(
+            highlight_query_in_text("let token = Token::new();", "token", AnsiColor::Red),
+            "let \x1b[1;31mtoken\x1b[0m = \x1b[1;31mToken\x1b[0m::new();"
+        );
+        assert_eq!(highlight_query_in_text("Größe = 1", "GRÖSSE", AnsiColor::Green), "Größe = 1");
+        assert_eq!(highlight_query_in_text("ÉCOLE école", "école", AnsiColor::Cyan), "\x1b[1;36mÉCOLE\x1b[0m \x1b[1;36mécole\x1b[0m");
+        assert_eq!(highlight_query_in_text("no match", "", AnsiColor::Red), "no match");
+    }
+
+    #[test]
+    fn test_find_ignore_case() {
+        assert_eq!(find_ignore_case("let Token = 1;", "token"), Some((4, 9)));
+        assert_eq!(find_ignore_case("ÉCOLE", "école"), Some((0, 6)));
+        // `İ` lowercases to `i` plus a combining dot, so `i` alone doesn't cover it, but the later `I` does
+        assert_eq!(find_ignore_case("İ I", "i"), Some((3, 4)));
+        assert_eq!(find_ignore_case("Größe", "grösse"), None);
+    }
+}
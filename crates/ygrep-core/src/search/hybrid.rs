@@ -1,17 +1,18 @@
 //! Hybrid search combining BM25 and vector search using Reciprocal Rank Fusion
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::time::Instant;
 
-use tantivy::{Index, collector::TopDocs, query::QueryParser};
+use tantivy::{Index, IndexReader, collector::TopDocs, query::QueryParser};
 
 use crate::config::SearchConfig;
-use crate::embeddings::{EmbeddingModel, EmbeddingCache};
-use crate::error::Result;
+use crate::embeddings::{CrossEncoderModel, EmbeddingModel, EmbeddingCache};
+use crate::error::{Result, YgrepError};
 use crate::index::schema::SchemaFields;
 use crate::index::VectorIndex;
-use super::results::{SearchResult, SearchHit, MatchType};
+use super::results::{SearchResult, SearchHit, MatchType, SearchMode, SearchTimings};
+use super::searcher::{create_relevant_snippet, deadline_passed, describe_terms, extract_extension, fuzzy_terms_query, narrow_query, open_reader, retain_filtered, top_docs_within_deadline, SearchFilters};
 
 /// Hybrid searcher combining BM25 text search and vector similarity search
 pub struct HybridSearcher {
@@ -21,6 +22,12 @@ pub struct HybridSearcher {
     vector_index: Arc<VectorIndex>,
     embedding_model: Arc<EmbeddingModel>,
     embedding_cache: Arc<EmbeddingCache>,
+    reranker: Option<Arc<CrossEncoderModel>>,
+    /// Extension/path/file filters, applied to the text candidates before fusion and to the
+    /// fused hits
+    filters: SearchFilters,
+    /// Opened on the first search and reused by later ones (or shared via `with_reader`)
+    reader: OnceLock<IndexReader>,
 }
 
 impl HybridSearcher {
@@ -42,70 +49,205 @@ impl HybridSearcher {
             vector_index,
             embedding_model,
             embedding_cache,
+            reranker: None,
+            filters: SearchFilters::default(),
+            reader: OnceLock::new(),
         }
     }
 
+    /// Only return hits passing `filters`
+    ///
+    /// Text candidates are narrowed in the index query, so the filtered files aren't crowded
+    /// out of the BM25 candidates by others; semantic candidates are filtered after lookup.
+    pub fn with_filters(mut self, filters: SearchFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Search through an existing reader instead of opening one (see `Searcher::with_reader`)
+    pub fn with_reader(self, reader: IndexReader) -> Self {
+        let _ = self.reader.set(reader);
+        self
+    }
+
+    /// The reader searches go through, opened once and reloaded shortly after each commit
+    fn reader(&self) -> Result<&IndexReader> {
+        if let Some(reader) = self.reader.get() {
+            return Ok(reader);
+        }
+        let reader = open_reader(&self.index)?;
+        Ok(self.reader.get_or_init(|| reader))
+    }
+
+    /// Use a cross-encoder for `search_with_rerank`
+    pub fn with_reranker(mut self, reranker: Arc<CrossEncoderModel>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    /// Hybrid search, with the top `rerank_top_n` fused candidates re-ranked by the cross-encoder
+    ///
+    /// Scores become the cross-encoder's relevance (0-1). If the model can't be loaded,
+    /// the RRF order is kept (with a warning), like a failed query embedding.
+    pub fn search_with_rerank(&self, query: &str, limit: Option<usize>, rerank_top_n: usize) -> Result<SearchResult> {
+        let start = Instant::now();
+        let reranker = self.reranker.as_ref().ok_or_else(|| {
+            YgrepError::Config("Re-ranking needs a cross-encoder (HybridSearcher::with_reranker)".into())
+        })?;
+        let limit = self.config.effective_limit(limit);
+
+        let mut result = self.search(query, Some(rerank_top_n.max(limit)), None)?;
+
+        let passages: Vec<&str> = result.hits.iter().map(|hit| hit.snippet.as_str()).collect();
+        match reranker.score_pairs(query, &passages) {
+            Ok(scores) => rerank_hits(&mut result.hits, &scores),
+            Err(e) => tracing::warn!("Re-ranking unavailable, keeping hybrid order: {}", e.chain()),
+        }
+
+        result.hits.truncate(limit);
+        result.query_time_ms = start.elapsed().as_millis() as u64;
+        Ok(result)
+    }
+
     /// Perform hybrid search combining BM25 and vector search
-    pub fn search(&self, query: &str, limit: Option<usize>) -> Result<SearchResult> {
+    ///
+    /// `offset` skips that many fused hits before `limit` is applied (for pagination).
+    pub fn search(&self, query: &str, limit: Option<usize>, offset: Option<usize>) -> Result<SearchResult> {
         let start = Instant::now();
-        let limit = limit.unwrap_or(self.config.default_limit).min(self.config.max_limit);
+        let limit = self.config.effective_limit(limit);
+        let offset = offset.unwrap_or(0);
 
         // Fetch more results from each method for better fusion
-        let fetch_limit = limit * 3;
+        let fetch_limit = (offset + limit) * 3;
 
-        // Run BM25 search
-        let bm25_results = self.bm25_search(query, fetch_limit)?;
+        let mut timings = SearchTimings::default();
+        let elapsed_ms = |phase: Instant| phase.elapsed().as_secs_f64() * 1000.0;
 
-        // Run vector search
-        let vector_results = self.vector_search(query, fetch_limit)?;
+        // Run BM25 search
+        let phase = Instant::now();
+        let (bm25_results, mut timed_out, effective_query) = self.bm25_search(query, fetch_limit, start)?;
+        timings.bm25_ms = elapsed_ms(phase);
+
+        // Run vector search, degrading to BM25 only if the query can't be embedded
+        // (or skipping it once the search is out of time)
+        let (vector_results, search_mode) = if self.vector_index.is_empty() || timed_out {
+            (vec![], SearchMode::BM25Only)
+        } else {
+            let phase = Instant::now();
+            let embedded = self.embed_query(query);
+            timings.embed_ms = elapsed_ms(phase);
+
+            match embedded {
+                Ok((embedding, cache_hit)) => {
+                    timings.embed_cache_hit = cache_hit;
+                    let phase = Instant::now();
+                    let (vector_results, vector_timed_out) = self.vector_search(&embedding, fetch_limit, start)?;
+                    timed_out |= vector_timed_out;
+                    timings.vector_ms = elapsed_ms(phase);
+                    (vector_results, SearchMode::Hybrid)
+                }
+                Err(e) => {
+                    tracing::warn!("Semantic search unavailable, using text results only: {}", e.chain());
+                    (vec![], SearchMode::HybridDegradedNoModel)
+                }
+            }
+        };
+
+        // Candidate counts before fusion, reported as the text/semantic funnel
+        let text_hits = bm25_results.len();
+        let semantic_hits = vector_results.len();
+
+        // Snippets center on the query's words, so a semantic hit (often a chunk) shows the
+        // line that mentions them instead of its first lines
+        let query_terms: Vec<String> = query
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|s| !s.is_empty())
+            .map(str::to_lowercase)
+            .collect();
 
         // Fuse results using Reciprocal Rank Fusion
-        let fused = self.reciprocal_rank_fusion(
+        let phase = Instant::now();
+        let mut fused = self.reciprocal_rank_fusion(
             bm25_results,
             vector_results,
             self.config.bm25_weight,
             self.config.vector_weight,
-            query,
+            &query_terms.join(" "),
         );
+        timings.fusion_ms = elapsed_ms(phase);
+
+        retain_filtered(&mut fused, &self.filters);
 
         // Take top results
         // Note: RRF scores are typically small (max ~0.016 with K=60), so we don't apply min_score filter
+        let total = fused.len();
         let hits: Vec<SearchHit> = fused
             .into_iter()
+            .skip(offset)
             .take(limit)
             .collect();
 
-        // Count text vs semantic hits
-        let text_hits = hits.iter().filter(|h| matches!(h.match_type, MatchType::Text | MatchType::Hybrid)).count();
-        let semantic_hits = hits.iter().filter(|h| matches!(h.match_type, MatchType::Semantic | MatchType::Hybrid)).count();
-
         let query_time_ms = start.elapsed().as_millis() as u64;
 
         Ok(SearchResult {
-            total: hits.len(),
+            total,
             hits,
             query_time_ms,
             text_hits,
             semantic_hits,
+            search_mode,
+            timings: Some(timings),
+            timed_out,
+            effective_query,
+            query_terms,
+            ..SearchResult::empty()
         })
     }
 
     /// BM25 full-text search
-    fn bm25_search(&self, query: &str, limit: usize) -> Result<Vec<RankedResult>> {
-        let reader = self.index.reader()?;
-        let searcher = reader.searcher();
+    /// Returns the ranked results, whether the search ran out of time collecting them
+    /// and the query that ran (see `SearchResult::effective_query`)
+    fn bm25_search(&self, query: &str, limit: usize, start: Instant) -> Result<(Vec<RankedResult>, bool, String)> {
+        let searcher = self.reader()?.searcher();
+
+        let (tantivy_query, effective_query) = if self.config.fuzzy_enabled {
+            let terms: Vec<&str> = query
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|s| !s.is_empty())
+                .collect();
+            let tantivy_query = fuzzy_terms_query(
+                &[self.fields.content],
+                &terms,
+                self.config.fuzzy_distance,
+                self.config.multi_term_mode,
+            );
+            let terms: Vec<String> = terms.iter().map(|term| term.to_lowercase()).collect();
+            let description = describe_terms(&terms, self.config.multi_term_mode, Some(self.config.fuzzy_distance));
+            (tantivy_query, description)
+        } else {
+            let query_parser = QueryParser::for_index(&self.index, vec![self.fields.content]);
 
-        let query_parser = QueryParser::for_index(&self.index, vec![self.fields.content]);
+            // Wrap query in quotes for literal phrase matching (like grep)
+            let quoted_query = format!("\"{}\"", query.replace('"', "\\\""));
+            let (tantivy_query, _errors) = query_parser.parse_query_lenient(&quoted_query);
+            (tantivy_query, quoted_query)
+        };
 
-        // Wrap query in quotes for literal phrase matching (like grep)
-        let quoted_query = format!("\"{}\"", query.replace('"', "\\\""));
-        let (tantivy_query, _errors) = query_parser.parse_query_lenient(&quoted_query);
+        // Narrow the candidates to the filtered files before ranking
+        let tantivy_query = narrow_query(tantivy_query, &self.filters, &self.fields)?;
 
-        let top_docs = searcher.search(&tantivy_query, &TopDocs::with_limit(limit))?;
+        let (top_docs, timed_out) = top_docs_within_deadline(&self.config, &searcher, &tantivy_query, limit, start)?;
+        if timed_out {
+            return Ok((vec![], true, effective_query));
+        }
 
         let mut results = Vec::with_capacity(top_docs.len());
 
         for (rank, (score, doc_address)) in top_docs.iter().enumerate() {
+            if deadline_passed(&self.config, start)? {
+                return Ok((results, true, effective_query));
+            }
+
             let doc = searcher.doc(*doc_address)?;
 
             let path = extract_text(&doc, self.fields.path).unwrap_or_default();
@@ -117,6 +259,8 @@ impl HybridSearcher {
             results.push(RankedResult {
                 doc_id: doc_id.clone(),
                 path,
+                file_extension: extract_extension(&doc, &self.fields),
+                language: extract_text(&doc, self.fields.language).unwrap_or_default(),
                 content,
                 line_start,
                 is_chunk: !chunk_id.is_empty(),
@@ -125,36 +269,45 @@ impl HybridSearcher {
             });
         }
 
-        Ok(results)
+        Ok((results, false, effective_query))
     }
 
-    /// Vector similarity search
-    fn vector_search(&self, query: &str, limit: usize) -> Result<Vec<RankedResult>> {
-        // Check if vector index has data
-        if self.vector_index.is_empty() {
-            return Ok(vec![]);
+    /// Get the query embedding from the cache, or compute it with the model
+    /// Returns the embedding and whether it was a cache hit
+    /// (failures aren't cached so a later search can retry the model)
+    fn embed_query(&self, query: &str) -> Result<(Vec<f32>, bool)> {
+        if let Some(embedding) = self.embedding_cache.get(query) {
+            return Ok((embedding, true));
         }
 
-        // Get or compute query embedding
-        let query_embedding = self.embedding_cache.get_or_insert(query, || {
-            self.embedding_model.embed(query).unwrap_or_else(|_| vec![0.0; 384])
-        });
+        let embedding = self.embedding_model.embed(query)?;
+        self.embedding_cache.insert(query, embedding.clone());
+        Ok((embedding, false))
+    }
 
+    /// Vector similarity search
+    /// Returns the ranked results and whether the search ran out of time collecting them
+    fn vector_search(&self, query_embedding: &[f32], limit: usize, start: Instant) -> Result<(Vec<RankedResult>, bool)> {
         // Search vector index
-        let neighbors = self.vector_index.search(&query_embedding, limit)?;
+        let neighbors = self.vector_index.search(query_embedding, limit)?;
 
         // Look up full document info from tantivy
-        let reader = self.index.reader()?;
-        let searcher = reader.searcher();
+        let searcher = self.reader()?.searcher();
 
         let mut results = Vec::with_capacity(neighbors.len());
 
         for (rank, (_, distance, doc_id)) in neighbors.iter().enumerate() {
+            if deadline_passed(&self.config, start)? {
+                return Ok((results, true));
+            }
+
             // Find document by doc_id in tantivy
             if let Some(hit) = self.lookup_by_doc_id(&searcher, doc_id)? {
                 results.push(RankedResult {
                     doc_id: doc_id.clone(),
                     path: hit.path,
+                    file_extension: hit.file_extension,
+                    language: hit.language,
                     content: hit.content,
                     line_start: hit.line_start,
                     is_chunk: hit.is_chunk,
@@ -164,7 +317,7 @@ impl HybridSearcher {
             }
         }
 
-        Ok(results)
+        Ok((results, false))
     }
 
     /// Look up document by doc_id
@@ -183,6 +336,8 @@ impl HybridSearcher {
 
             Ok(Some(DocInfo {
                 path: extract_text(&doc, self.fields.path).unwrap_or_default(),
+                file_extension: extract_extension(&doc, &self.fields),
+                language: extract_text(&doc, self.fields.language).unwrap_or_default(),
                 content: extract_text(&doc, self.fields.content).unwrap_or_default(),
                 line_start: extract_u64(&doc, self.fields.line_start).unwrap_or(1),
                 is_chunk: !extract_text(&doc, self.fields.chunk_id).unwrap_or_default().is_empty(),
@@ -193,13 +348,15 @@ impl HybridSearcher {
     }
 
     /// Reciprocal Rank Fusion to combine results from multiple retrieval methods
+    ///
+    /// `snippet_query` holds the words each hit's snippet is centered on.
     fn reciprocal_rank_fusion(
         &self,
         bm25_results: Vec<RankedResult>,
         vector_results: Vec<RankedResult>,
         bm25_weight: f32,
         vector_weight: f32,
-        query: &str,
+        snippet_query: &str,
     ) -> Vec<SearchHit> {
         const K: f32 = 60.0; // RRF constant
 
@@ -236,7 +393,7 @@ impl HybridSearcher {
             .into_values()
             .map(|fused| {
                 let total_score = fused.bm25_rrf + fused.vector_rrf;
-                let (snippet, match_offset, line_count) = create_relevant_snippet(&fused.result.content, query, 10);
+                let (snippet, match_offset, line_count) = create_relevant_snippet(&fused.result.content, snippet_query, 10);
 
                 // Adjust line numbers to reflect the snippet position
                 let actual_line_start = fused.result.line_start + match_offset as u64;
@@ -252,6 +409,8 @@ impl HybridSearcher {
 
                 SearchHit {
                     path: fused.result.path,
+                    file_extension: fused.result.file_extension,
+                    language: fused.result.language,
                     line_start: actual_line_start,
                     line_end: actual_line_end,
                     snippet,
@@ -259,22 +418,35 @@ impl HybridSearcher {
                     is_chunk: fused.result.is_chunk,
                     doc_id: fused.result.doc_id,
                     match_type,
+                    match_line: None,
+                    col_start: None,
+                    col_end: None,
                 }
             })
             .collect();
 
-        // Sort by score descending
-        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        // Sort by score descending (HashMap order is random, so ties need a stable order)
+        hits.sort_by(SearchHit::rank_cmp);
 
         hits
     }
 }
 
+/// Sort hits by cross-encoder score (logits, mapped to 0-1 relevance)
+fn rerank_hits(hits: &mut [SearchHit], scores: &[f32]) {
+    for (hit, score) in hits.iter_mut().zip(scores) {
+        hit.score = 1.0 / (1.0 + (-score).exp());
+    }
+    hits.sort_by(SearchHit::rank_cmp);
+}
+
 /// Intermediate result with ranking info
 #[derive(Debug, Clone)]
 struct RankedResult {
     doc_id: String,
     path: String,
+    file_extension: String,
+    language: String,
     content: String,
     line_start: u64,
     is_chunk: bool,
@@ -286,6 +458,8 @@ struct RankedResult {
 /// Document info from lookup
 struct DocInfo {
     path: String,
+    file_extension: String,
+    language: String,
     content: String,
     line_start: u64,
     is_chunk: bool,
@@ -320,38 +494,49 @@ fn extract_u64(doc: &tantivy::TantivyDocument, field: tantivy::schema::Field) ->
     })
 }
 
-/// Create a snippet showing lines relevant to the query
-/// Returns (snippet, line_offset_from_start, line_count)
-fn create_relevant_snippet(content: &str, query: &str, max_lines: usize) -> (String, usize, usize) {
-    let lines: Vec<&str> = content.lines().collect();
-    let query_lower = query.to_lowercase();
-    let query_terms: Vec<&str> = query_lower.split_whitespace().collect();
-
-    // Find lines that contain any query term
-    let mut matching_indices: Vec<usize> = Vec::new();
-    for (i, line) in lines.iter().enumerate() {
-        let line_lower = line.to_lowercase();
-        if query_terms.iter().any(|term| line_lower.contains(term)) {
-            matching_indices.push(i);
-        }
-    }
-
-    if matching_indices.is_empty() {
-        // No direct matches, return first lines
-        let snippet = lines.iter().take(max_lines).copied().collect::<Vec<_>>().join("\n");
-        let line_count = snippet.lines().count();
-        return (snippet, 0, line_count);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IndexerConfig;
+    use crate::embeddings::ModelType;
+    use crate::index::schema::build_document_schema;
+
+    #[test]
+    fn test_chunk_snippet_centers_on_query() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = IndexerConfig {
+            model_cache_dir: Some(temp_dir.path().join("models")),
+            offline: true,
+            ..IndexerConfig::default()
+        };
+        let model = Arc::new(EmbeddingModel::with_config(ModelType::default(), &config));
+        let vector_index = Arc::new(VectorIndex::new(temp_dir.path().join("vectors"), model.dimension())?);
+        let cache = Arc::new(EmbeddingCache::new(1, model.dimension()));
+        let index = Index::create_in_ram(build_document_schema());
+        let searcher = HybridSearcher::new(SearchConfig::default(), index, vector_index, model, cache);
+
+        // A chunk found only by vector search, starting at line 31 with the match on line 45
+        let content = (31..=60)
+            .map(|n| if n == 45 { "    let cfg = parse_config(path)?;".to_string() } else { format!("    step_{}();", n) })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let chunk = RankedResult {
+            doc_id: "abc123:1".to_string(),
+            path: "src/main.rs".to_string(),
+            file_extension: "rs".to_string(),
+            language: String::new(),
+            content,
+            line_start: 31,
+            is_chunk: true,
+            rank: 1,
+            score: 0.9,
+        };
+
+        let hits = searcher.reciprocal_rank_fusion(vec![], vec![chunk], 1.0, 1.0, "parse_config");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].match_type, MatchType::Semantic);
+        assert!(hits[0].snippet.contains("parse_config(path)"), "snippet: {}", hits[0].snippet);
+        assert_eq!(hits[0].line_start, 43);
+        Ok(())
     }
-
-    // Get context around the first match
-    let first_match = matching_indices[0];
-    let context_before = 2;
-    let context_after = max_lines.saturating_sub(context_before + 1);
-
-    let start = first_match.saturating_sub(context_before);
-    let end = (first_match + context_after + 1).min(lines.len());
-
-    let snippet = lines[start..end].join("\n");
-    let line_count = end - start;
-    (snippet, start, line_count)
 }
@@ -1,9 +1,14 @@
 mod searcher;
 mod results;
+mod boolean;
+mod highlight;
 #[cfg(feature = "embeddings")]
 mod hybrid;
 
-pub use searcher::{Searcher, SearchFilters};
-pub use results::{SearchResult, SearchHit, MatchType};
+pub use searcher::{Searcher, SearchFilters, SEARCHABLE_FIELDS};
+pub(crate) use searcher::open_reader;
+pub use boolean::BoolExpr;
+pub use highlight::{highlight_query_in_text, AnsiColor};
+pub use results::{SearchResult, SearchHit, MatchType, SearchMode, SearchTimings, SearchFiltersJson};
 #[cfg(feature = "embeddings")]
 pub use hybrid::HybridSearcher;
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::config::OutputConfig;
+use super::highlight::{find_ignore_case, highlight_query_in_text, AnsiColor};
+use super::searcher::SearchFilters;
+
 /// Type of match for a search hit
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MatchType {
@@ -21,6 +25,37 @@ impl std::fmt::Display for MatchType {
     }
 }
 
+/// Which retrieval methods produced a search result
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// BM25 text search only
+    #[default]
+    #[serde(rename = "bm25_only")]
+    BM25Only,
+    /// Semantic vector search only
+    SemanticOnly,
+    /// BM25 and semantic search fused with RRF
+    Hybrid,
+    /// Hybrid search was requested but the embedding model failed, so only BM25 results are returned
+    HybridDegradedNoModel,
+}
+
+/// Time spent in each phase of a hybrid search, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchTimings {
+    /// BM25 text search
+    pub bm25_ms: f64,
+    /// Query embedding (cache lookup or model call, including a cold model load)
+    pub embed_ms: f64,
+    /// Whether the query embedding came from the cache
+    pub embed_cache_hit: bool,
+    /// Vector similarity search
+    pub vector_ms: f64,
+    /// Reciprocal Rank Fusion of both result lists
+    pub fusion_ms: f64,
+}
+
 /// Result of a search operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -30,12 +65,70 @@ pub struct SearchResult {
     pub total: usize,
     /// Query execution time in milliseconds
     pub query_time_ms: u64,
-    /// Number of hits from text search
+    /// Number of text search matches (before fusion in hybrid search)
     #[serde(default)]
     pub text_hits: usize,
-    /// Number of hits from semantic search
+    /// Number of semantic search matches (before fusion in hybrid search)
     #[serde(default)]
     pub semantic_hits: usize,
+    /// Retrieval methods used to produce the hits
+    #[serde(default)]
+    pub search_mode: SearchMode,
+    /// Per-phase timing breakdown (hybrid search only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<SearchTimings>,
+    /// Whether the search ran past `timeout_ms`, so the hits are partial
+    #[serde(default)]
+    pub timed_out: bool,
+    /// Query the hits were searched for (set by `with_query_context`)
+    #[serde(default)]
+    pub query: String,
+    /// Filters applied to the hits, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<SearchFiltersJson>,
+    /// Requested maximum number of hits
+    #[serde(default)]
+    pub limit: usize,
+    /// The index query the search ran, after rewriting the input (phrase, extracted words, fuzzy
+    /// expansion), in Lucene-like syntax: `"a b"` phrase, `a OR b`, `a AND b`, `a~1` fuzzy,
+    /// `+(...) x.y` for a boosted member chain; literal searches then keep only hits containing
+    /// the query text (empty for searches that don't report it)
+    #[serde(default)]
+    pub effective_query: String,
+    /// Lowercased words extracted from the query for the index lookup
+    #[serde(default)]
+    pub query_terms: Vec<String>,
+}
+
+/// Serializable form of `SearchFilters`, reported in JSON output
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchFiltersJson {
+    /// File extensions hits were limited to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    /// Path patterns hits were limited to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Exact paths hits were limited to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<String>>,
+    /// Languages hits were limited to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+}
+
+impl SearchFiltersJson {
+    /// Mirror `filters`, or `None` when they don't restrict anything
+    pub fn from_filters(filters: &SearchFilters) -> Option<Self> {
+        let json = Self {
+            extensions: filters.extensions.clone().unwrap_or_default(),
+            paths: filters.paths.clone().unwrap_or_default(),
+            files: filters.files.clone(),
+            languages: filters.languages.clone().unwrap_or_default(),
+        };
+        let filtered = !json.extensions.is_empty() || !json.paths.is_empty() || json.files.is_some() || !json.languages.is_empty();
+        filtered.then_some(json)
+    }
 }
 
 /// A single search hit
@@ -43,6 +136,12 @@ pub struct SearchResult {
 pub struct SearchHit {
     /// File path (relative to workspace)
     pub path: String,
+    /// File extension without the dot (empty if the file has none)
+    #[serde(default)]
+    pub file_extension: String,
+    /// Detected language (see `index::language`; empty for indexes without it)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub language: String,
     /// Line range (start-end)
     pub line_start: u64,
     pub line_end: u64,
@@ -57,6 +156,16 @@ pub struct SearchHit {
     /// Type of match (text, semantic, or hybrid)
     #[serde(default = "default_match_type")]
     pub match_type: MatchType,
+    /// Line of the first literal or regex match in the snippet (None for semantic, fuzzy,
+    /// boolean and multiline hits, where there's no single match)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_line: Option<u64>,
+    /// Column where that match starts on `match_line`, in characters from 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub col_start: Option<u64>,
+    /// Column just past the end of the match (exclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub col_end: Option<u64>,
 }
 
 fn default_match_type() -> MatchType {
@@ -72,6 +181,15 @@ impl SearchHit {
             format!("{}-{}", self.line_start, self.line_end)
         }
     }
+
+    /// Result order: highest score first, with ties broken by path, line and doc_id
+    /// so that repeated searches list equal-score hits the same way
+    pub fn rank_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.score.total_cmp(&self.score)
+            .then_with(|| self.path.cmp(&other.path))
+            .then_with(|| self.line_start.cmp(&other.line_start))
+            .then_with(|| self.doc_id.cmp(&other.doc_id))
+    }
 }
 
 impl SearchResult {
@@ -83,23 +201,68 @@ impl SearchResult {
             query_time_ms: 0,
             text_hits: 0,
             semantic_hits: 0,
+            search_mode: SearchMode::BM25Only,
+            timings: None,
+            timed_out: false,
+            query: String::new(),
+            filters: None,
+            limit: 0,
+            effective_query: String::new(),
+            query_terms: vec![],
         }
     }
 
+    /// Record the query, filters and limit the result was produced for (reported by `format_json`)
+    pub fn with_query_context(mut self, query: &str, filters: &SearchFilters, limit: usize) -> Self {
+        self.query = query.to_string();
+        self.filters = SearchFiltersJson::from_filters(filters);
+        self.limit = limit;
+        self
+    }
+
+    /// Combine with the result of another search, e.g. of another workspace
+    ///
+    /// Each side's scores are divided by its best score before the hits are combined and
+    /// sorted, so all scores lie in [0, 1] even though raw BM25 scores of different indexes
+    /// aren't on the same scale, and merging several results gives the same scores in any
+    /// order. Counts and `timed_out` are combined; the query context, search mode and timings
+    /// are kept from `self`.
+    pub fn merge(mut self, other: SearchResult) -> SearchResult {
+        let mut other_hits = other.hits;
+        normalize_scores(&mut self.hits);
+        normalize_scores(&mut other_hits);
+        self.hits.extend(other_hits);
+        self.hits.sort_by(SearchHit::rank_cmp);
+
+        self.total += other.total;
+        self.text_hits += other.text_hits;
+        self.semantic_hits += other.semantic_hits;
+        self.query_time_ms = self.query_time_ms.max(other.query_time_ms);
+        self.timed_out |= other.timed_out;
+        self
+    }
+
     /// Check if there are any results
     pub fn is_empty(&self) -> bool {
         self.hits.is_empty()
     }
 
-    /// Format search type summary (e.g., "5 text + 3 semantic" or "text")
+    /// Format search type summary: the hybrid funnel from matches to returned hits
+    /// (e.g., "text:12→3, semantic:8→3, 45ms"), or "text"
     fn search_type_summary(&self) -> String {
-        if self.text_hits > 0 && self.semantic_hits > 0 {
-            format!("{} text + {} semantic", self.text_hits, self.semantic_hits)
-        } else if self.semantic_hits > 0 {
-            "semantic".to_string()
-        } else {
-            "text".to_string()
+        if self.search_mode != SearchMode::Hybrid {
+            return "text".to_string();
         }
+
+        let returned = |types: &[MatchType]| self.hits.iter().filter(|h| types.contains(&h.match_type)).count();
+        format!(
+            "text:{}→{}, semantic:{}→{}, {}ms",
+            self.text_hits,
+            returned(&[MatchType::Text, MatchType::Hybrid]),
+            self.semantic_hits,
+            returned(&[MatchType::Semantic, MatchType::Hybrid]),
+            self.query_time_ms
+        )
     }
 
     /// Normalize score for display (RRF scores are tiny ~0.01, we want 0-100 range)
@@ -109,8 +272,23 @@ impl SearchResult {
         (score * 3000.0).min(99.9)
     }
 
+    /// Highlight the query in a line of output when `output.highlight` is set
+    fn highlight(&self, text: &str, output: &OutputConfig) -> String {
+        let query = self.query.trim();
+        if output.highlight && !query.is_empty() {
+            highlight_query_in_text(text, query, AnsiColor::default())
+        } else {
+            text.to_string()
+        }
+    }
+
     /// Format results for AI-optimized output (minimal tokens, maximum density)
     pub fn format_ai(&self) -> String {
+        self.format_ai_with(&OutputConfig::default())
+    }
+
+    /// Format results for AI-optimized output, applying output settings such as `highlight`
+    pub fn format_ai_with(&self, options: &OutputConfig) -> String {
         let mut output = String::new();
 
         // Header with count and search type breakdown
@@ -127,15 +305,10 @@ impl SearchResult {
             output.push_str(&format!("{}:{} ({:.0}%){}\n", hit.path, hit.line_start, score_pct, match_indicator));
 
             // Show only the first matching line, trimmed
-            if let Some(first_line) = hit.snippet.lines().next() {
-                let trimmed = first_line.trim();
-                let preview = if trimmed.len() > 100 {
-                    let boundary = trimmed.floor_char_boundary(100);
-                    format!("{}...", &trimmed[..boundary])
-                } else {
-                    trimmed.to_string()
-                };
-                output.push_str(&format!("  {}\n", preview));
+            let max_chars = options.max_snippet_chars.unwrap_or(100);
+            for first_line in hit.snippet.lines().take(options.max_lines_per_result.min(1)) {
+                let preview = truncate_line(first_line.trim(), max_chars);
+                output.push_str(&format!("  {}\n", self.highlight(&preview, options)));
             }
             output.push('\n');
         }
@@ -148,8 +321,82 @@ impl SearchResult {
         serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".to_string())
     }
 
+    /// Format results as CSV (`path,line_start,line_end,score,snippet`) for spreadsheet export
+    /// Snippet newlines are written as `\n` so every hit stays on one row
+    pub fn format_csv(&self, separator: char) -> String {
+        let field = |value: &str| {
+            if value.contains(separator) || value.contains('"') || value.contains('\n') {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        };
+        let sep = separator.to_string();
+
+        let mut output = ["path", "line_start", "line_end", "score", "snippet"].join(&sep);
+        output.push('\n');
+
+        for hit in &self.hits {
+            let snippet = hit.snippet.replace("\r\n", "\\n").replace('\n', "\\n");
+            let row = [
+                field(&hit.path),
+                hit.line_start.to_string(),
+                hit.line_end.to_string(),
+                hit.score.to_string(),
+                field(&snippet),
+            ];
+            output.push_str(&row.join(&sep));
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Format results as grep-style `path:line:column:text` lines (vim `:grep`, quickfix lists, fzf)
+    ///
+    /// The line of the match the search recorded (`match_line`) and every later snippet line
+    /// containing the query (case-insensitive) get their own line, with the 1-based byte column
+    /// of the first match. Hits without a match (semantic or boolean searches) print their first
+    /// line as `path:line:text`.
+    pub fn format_grep(&self) -> String {
+        let query = self.query.trim();
+        let mut output = String::new();
+
+        for hit in &self.hits {
+            let mut matched = false;
+            for (i, line) in hit.snippet.lines().enumerate() {
+                let line_num = hit.line_start + i as u64;
+                let column = match (hit.match_line, hit.col_start) {
+                    // Columns are recorded in characters
+                    (Some(match_line), Some(col_start)) if match_line == line_num => {
+                        line.char_indices().nth(col_start as usize).map(|(byte, _)| byte)
+                    }
+                    // Nothing before the first match can contain the query
+                    (Some(match_line), _) if line_num < match_line => None,
+                    _ if !query.is_empty() => find_ignore_case(line, query).map(|(start, _)| start),
+                    _ => None,
+                };
+                if let Some(column) = column {
+                    output.push_str(&format!("{}:{}:{}:{}\n", hit.path, line_num, column + 1, line));
+                    matched = true;
+                }
+            }
+            if !matched {
+                let line = hit.snippet.lines().next().unwrap_or("");
+                output.push_str(&format!("{}:{}:{}\n", hit.path, hit.line_start, line));
+            }
+        }
+
+        output
+    }
+
     /// Format results for human-readable output (more context, line numbers)
     pub fn format_pretty(&self) -> String {
+        self.format_pretty_with(&OutputConfig::default())
+    }
+
+    /// Format results for human-readable output, applying output settings such as `highlight`
+    pub fn format_pretty_with(&self, options: &OutputConfig) -> String {
         let mut output = String::new();
 
         // Header with breakdown
@@ -165,16 +412,11 @@ impl SearchResult {
             output.push_str(&format!("{}:{}\n", hit.path, hit.lines_str()));
 
             // Show first few lines of snippet with line numbers
-            for (i, line) in hit.snippet.lines().take(3).enumerate() {
+            let max_chars = options.max_snippet_chars.unwrap_or(80);
+            for (i, line) in hit.snippet.lines().take(options.max_lines_per_result).enumerate() {
                 let line_num = hit.line_start + i as u64;
-                let trimmed = line.trim();
-                let preview = if trimmed.len() > 80 {
-                    let boundary = trimmed.floor_char_boundary(80);
-                    format!("{}...", &trimmed[..boundary])
-                } else {
-                    trimmed.to_string()
-                };
-                output.push_str(&format!("  {}: {}\n", line_num, preview));
+                let preview = truncate_line(line.trim(), max_chars);
+                output.push_str(&format!("  {}: {}\n", line_num, self.highlight(&preview, options)));
             }
             output.push('\n');
         }
@@ -183,50 +425,97 @@ impl SearchResult {
     }
 }
 
+/// Cut a snippet line to at most `max_chars` characters, marking the cut with `...`
+fn truncate_line(line: &str, max_chars: usize) -> String {
+    match line.char_indices().nth(max_chars) {
+        Some((boundary, _)) => format!("{}...", &line[..boundary]),
+        None => line.to_string(),
+    }
+}
+
+/// Divide scores by the highest one, so the best hit scores 1
+fn normalize_scores(hits: &mut [SearchHit]) {
+    let max_score = hits.iter().map(|hit| hit.score).fold(0.0, f32::max);
+    if max_score > 0.0 {
+        for hit in hits {
+            hit.score /= max_score;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// A single-line text hit; tests override the fields they care about
+    fn hit(path: &str, line: u64, snippet: &str) -> SearchHit {
+        SearchHit {
+            path: path.to_string(),
+            file_extension: "rs".to_string(),
+            language: String::new(),
+            line_start: line,
+            line_end: line,
+            snippet: snippet.to_string(),
+            score: 0.5,
+            is_chunk: false,
+            doc_id: "abc".to_string(),
+            match_type: MatchType::Text,
+            match_line: None,
+            col_start: None,
+            col_end: None,
+        }
+    }
+
     #[test]
     fn test_lines_str() {
-        let hit = SearchHit {
-            path: "test.rs".to_string(),
-            line_start: 10,
-            line_end: 25,
-            snippet: "content".to_string(),
-            score: 0.8,
-            is_chunk: false,
-            doc_id: "abc123".to_string(),
+        let range = SearchHit { line_end: 25, ..hit("test.rs", 10, "content") };
+        assert_eq!(range.lines_str(), "10-25");
+        assert_eq!(hit("test.rs", 5, "content").lines_str(), "5");
+    }
+
+    #[test]
+    fn test_merge() {
+        let scored = |path: &str, score| SearchHit { score, doc_id: path.to_string(), ..hit(path, 1, "") };
+        let result = |hits: Vec<SearchHit>| SearchResult {
+            total: hits.len(),
+            text_hits: hits.len(),
+            hits,
+            ..SearchResult::empty()
         };
-        assert_eq!(hit.lines_str(), "10-25");
+        let a = result(vec![scored("a/main.rs", 8.0), scored("a/lib.rs", 2.0)]);
+        let b = result(vec![scored("b/main.rs", 0.5)]);
+        let c = result(vec![scored("c/main.rs", 3.0), scored("c/lib.rs", 1.5)]);
 
-        let single_line = SearchHit {
-            line_start: 5,
-            line_end: 5,
-            ..hit.clone()
+        let scores = |result: &SearchResult| -> Vec<(String, f32)> {
+            result.hits.iter().map(|hit| (hit.path.clone(), hit.score)).collect()
         };
-        assert_eq!(single_line.lines_str(), "5");
+
+        let merged = a.clone().merge(b.clone()).merge(c.clone());
+        assert_eq!(scores(&merged), [
+            ("a/main.rs".to_string(), 1.0),
+            ("b/main.rs".to_string(), 1.0),
+            ("c/main.rs".to_string(), 1.0),
+            ("c/lib.rs".to_string(), 0.5),
+            ("a/lib.rs".to_string(), 0.25),
+        ]);
+        assert_eq!((merged.total, merged.text_hits), (5, 5));
+
+        // The order of merging doesn't change the scores
+        assert_eq!(scores(&c.merge(a.merge(b))), scores(&merged));
     }
 
     #[test]
     fn test_format_ai() {
         let result = SearchResult {
-            hits: vec![
-                SearchHit {
-                    path: "src/main.rs".to_string(),
-                    line_start: 1,
-                    line_end: 10,
-                    snippet: "fn main() {\n    println!(\"hello\");\n}".to_string(),
-                    score: 0.9,
-                    is_chunk: false,
-                    doc_id: "abc".to_string(),
-                    match_type: MatchType::Text,
-                },
-            ],
+            hits: vec![SearchHit {
+                line_end: 10,
+                score: 0.03,
+                ..hit("src/main.rs", 1, "fn main() {\n    println!(\"hello\");\n}")
+            }],
             total: 1,
             query_time_ms: 15,
             text_hits: 1,
-            semantic_hits: 0,
+            ..SearchResult::empty()
         };
 
         let output = result.format_ai();
@@ -234,4 +523,168 @@ mod tests {
         assert!(output.contains("src/main.rs:1"));
         assert!(output.contains("(90%)"));
     }
+
+    #[test]
+    fn test_format_pretty_highlight() {
+        let mut result = SearchResult::empty();
+        result.query = "token".to_string();
+        result.hits = vec![hit("src/auth.rs", 3, "let token = read();")];
+
+        assert!(result.format_pretty().contains("  3: let token = read();\n"));
+        let options = OutputConfig { highlight: true, ..OutputConfig::default() };
+        assert!(result.format_pretty_with(&options).contains("  3: let \x1b[1;31mtoken\x1b[0m = read();\n"));
+        assert!(result.format_ai_with(&options).contains("  let \x1b[1;31mtoken\x1b[0m = read();\n"));
+    }
+
+    #[test]
+    fn test_format_snippet_caps() {
+        let mut result = SearchResult::empty();
+        result.hits = vec![SearchHit {
+            line_end: 14,
+            score: 0.01,
+            ..hit(
+                "src/lib.rs",
+                10,
+                "fn parse_config() {\n    let path = config_path();\n    let text = read(path);\n    toml::from_str(&text)\n}",
+            )
+        }];
+
+        // Defaults: three lines in pretty output, one in AI output
+        let pretty = result.format_pretty();
+        assert!(pretty.contains("  12: let text = read(path);\n"));
+        assert!(!pretty.contains("  13:"));
+        assert!(result.format_ai().contains("  fn parse_config() {\n\n"));
+
+        let options = OutputConfig { max_lines_per_result: 2, max_snippet_chars: Some(8), ..OutputConfig::default() };
+        let pretty = result.format_pretty_with(&options);
+        assert!(pretty.contains("  10: fn parse...\n  11: let path...\n\n"));
+        assert!(!pretty.contains("  12:"));
+        assert!(result.format_ai_with(&options).contains("  fn parse...\n\n"));
+
+        // No snippet lines at all
+        let options = OutputConfig { max_lines_per_result: 0, ..OutputConfig::default() };
+        assert!(result.format_ai_with(&options).ends_with("src/lib.rs:10 (30%)\n\n"));
+        assert!(result.format_pretty_with(&options).ends_with("src/lib.rs:10-14\n\n"));
+    }
+
+    #[test]
+    fn test_format_ai_hybrid_funnel() {
+        let typed = |match_type| SearchHit { match_type, ..hit("src/main.rs", 1, "fn main() {}") };
+        let result = SearchResult {
+            hits: vec![typed(MatchType::Hybrid), typed(MatchType::Text), typed(MatchType::Semantic)],
+            total: 3,
+            query_time_ms: 45,
+            text_hits: 12,
+            semantic_hits: 8,
+            search_mode: SearchMode::Hybrid,
+            ..SearchResult::empty()
+        };
+
+        assert!(result.format_ai().starts_with("# 3 results (text:12→2, semantic:8→2, 45ms)\n"));
+    }
+
+    #[test]
+    fn test_format_grep() {
+        let block = |line_start, snippet, match_type| SearchHit {
+            line_end: line_start + 2,
+            match_type,
+            ..hit("src/auth.rs", line_start, snippet)
+        };
+        let mut result = SearchResult::empty();
+        result.query = "Token".to_string();
+        result.hits = vec![
+            block(10, "fn check() {\n    let token = read_token();\n}", MatchType::Text),
+            block(40, "fn verify_credentials() {\n}", MatchType::Semantic),
+        ];
+
+        assert_eq!(
+            result.format_grep(),
+            "src/auth.rs:11:9:    let token = read_token();\n\
+             src/auth.rs:40:fn verify_credentials() {\n"
+        );
+
+        // A recorded match (e.g. a regex's) is printed at its column, converted from characters to bytes
+        result.query = "t.ken".to_string();
+        result.hits = vec![SearchHit {
+            match_line: Some(11),
+            col_start: Some(6),
+            col_end: Some(11),
+            ..hit("src/auth.rs", 10, "fn check() {\n    é token\n}")
+        }];
+        assert_eq!(result.format_grep(), "src/auth.rs:11:8:    é token\n");
+    }
+
+    #[test]
+    fn test_search_mode_json() {
+        let mut result = SearchResult::empty();
+        result.search_mode = SearchMode::HybridDegradedNoModel;
+        assert!(result.format_json().contains("\"search_mode\": \"hybrid_degraded_no_model\""));
+
+        result.search_mode = SearchMode::BM25Only;
+        assert!(result.format_json().contains("\"search_mode\": \"bm25_only\""));
+    }
+
+    #[test]
+    fn test_query_context_json() {
+        let filters = SearchFilters { extensions: Some(vec!["rs".to_string()]), ..SearchFilters::default() };
+        let result = SearchResult::empty().with_query_context("fn main", &filters, 20);
+
+        let json: serde_json::Value = serde_json::from_str(&result.format_json()).unwrap();
+        assert_eq!(json["query"], "fn main");
+        assert_eq!(json["limit"], 20);
+        assert_eq!(json["filters"], serde_json::json!({ "extensions": ["rs"] }));
+
+        let unfiltered = SearchResult::empty().with_query_context("fn main", &SearchFilters::default(), 20);
+        assert!(unfiltered.filters.is_none());
+    }
+
+    #[test]
+    fn test_effective_query_json() {
+        let result = SearchResult {
+            effective_query: "config AND get".to_string(),
+            query_terms: vec!["config".to_string(), "get".to_string()],
+            ..SearchResult::empty()
+        };
+        let json: serde_json::Value = serde_json::from_str(&result.format_json()).unwrap();
+        assert_eq!(json["effective_query"], "config AND get");
+        assert_eq!(json["query_terms"], serde_json::json!(["config", "get"]));
+
+        // Always present, so scripts don't have to check for the keys
+        let json: serde_json::Value = serde_json::from_str(&SearchResult::empty().format_json()).unwrap();
+        assert_eq!(json["effective_query"], "");
+    }
+
+    #[test]
+    fn test_timings_json() {
+        let mut result = SearchResult::empty();
+        assert!(!result.format_json().contains("timings"));
+
+        result.timings = Some(SearchTimings { embed_cache_hit: true, ..Default::default() });
+        let parsed: SearchResult = serde_json::from_str(&result.format_json()).unwrap();
+        assert_eq!(parsed.timings, result.timings);
+    }
+
+    #[test]
+    fn test_format_csv_round_trip() {
+        let mut result = SearchResult::empty();
+        result.hits = vec![SearchHit { line_end: 4, ..hit("src/a,b.rs", 3, "let (a, b) = \"x\";\n\tfoo(a, b)") }];
+
+        for separator in [',', '\t'] {
+            let csv_output = result.format_csv(separator);
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(separator as u8)
+                .from_reader(csv_output.as_bytes());
+
+            let headers = reader.headers().unwrap().clone();
+            assert_eq!(headers.iter().collect::<Vec<_>>(), ["path", "line_start", "line_end", "score", "snippet"]);
+
+            let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+            assert_eq!(rows.len(), 1);
+            assert_eq!(&rows[0][0], "src/a,b.rs");
+            assert_eq!(&rows[0][1], "3");
+            assert_eq!(&rows[0][2], "4");
+            assert_eq!(rows[0][3].parse::<f32>().unwrap(), 0.5);
+            assert_eq!(&rows[0][4], "let (a, b) = \"x\";\\n\tfoo(a, b)");
+        }
+    }
 }
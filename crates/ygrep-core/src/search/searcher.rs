@@ -1,22 +1,69 @@
 use std::time::Instant;
-use tantivy::{Index, collector::TopDocs, query::QueryParser};
+use std::sync::OnceLock;
+use tantivy::{Index, IndexReader, ReloadPolicy, collector::TopDocs, query::QueryParser};
 use regex::RegexBuilder;
 
-use crate::config::SearchConfig;
-use crate::error::Result;
-use crate::index::schema::SchemaFields;
+use crate::config::{MultiTermMode, SearchConfig};
+use crate::error::{Result, YgrepError};
+use crate::fs::glob::glob_match;
+use crate::index::schema::{fields, register_tokenizers, SchemaFields, CODE_TOKENIZER};
+use super::boolean::BoolExpr;
+use super::highlight::find_ignore_case;
 use super::results::{SearchResult, SearchHit, MatchType};
 
 /// Search engine for querying the index
+///
+/// This is the supported entry point for using ygrep-core as a library: point it at
+/// any Tantivy `Index` built with `index::build_document_schema` (e.g. by `index::Indexer`),
+/// no `Workspace` needed. Construct it once and reuse it across queries.
+///
+/// # Example
+///
+/// ```
+/// use tantivy::Index;
+/// use ygrep_core::config::{IndexerConfig, SearchConfig};
+/// use ygrep_core::index::{build_document_schema, register_tokenizers, Indexer};
+/// use ygrep_core::search::Searcher;
+///
+/// # fn main() -> ygrep_core::Result<()> {
+/// let dir = tempfile::tempdir()?;
+/// let file = dir.path().join("main.rs");
+/// std::fs::write(&file, "fn main() { run_server(); }")?;
+///
+/// let index = Index::create_in_ram(build_document_schema());
+/// register_tokenizers(index.tokenizers());
+/// let indexer = Indexer::new(IndexerConfig::default(), index.clone(), dir.path())?;
+/// indexer.index_file(&file)?;
+/// indexer.commit()?;
+///
+/// let searcher = Searcher::new(SearchConfig::default(), index);
+/// let result = searcher.search("run_server", None, None)?;
+/// assert_eq!(result.hits[0].path, "main.rs");
+/// # Ok(())
+/// # }
+/// ```
 pub struct Searcher {
     config: SearchConfig,
     index: Index,
     fields: SchemaFields,
+    /// Opened on the first search and reused by later ones (or shared via `with_reader`)
+    reader: OnceLock<IndexReader>,
+    /// Lines of context around the matching lines in literal search snippets (see
+    /// `with_snippet_context`); None keeps the default 10-line window
+    snippet_context: Option<usize>,
+    /// Directory prefix every search is limited to (see `with_scope`)
+    scope: Option<String>,
 }
 
 impl Searcher {
     /// Create a new searcher for an index
+    ///
+    /// Registers ygrep's tokenizers on the index unless it already has them (e.g. with a
+    /// configured token length limit), so a freshly opened `Index` works as-is.
     pub fn new(config: SearchConfig, index: Index) -> Self {
+        if index.tokenizers().get(CODE_TOKENIZER).is_none() {
+            register_tokenizers(index.tokenizers());
+        }
         let schema = index.schema();
         let fields = SchemaFields::new(&schema);
 
@@ -24,20 +71,68 @@ impl Searcher {
             config,
             index,
             fields,
+            reader: OnceLock::new(),
+            snippet_context: None,
+            scope: None,
+        }
+    }
+
+    /// Make literal search snippets run from `context_lines` before the first matching line
+    /// to `context_lines` after the last one, instead of a fixed window around the best match
+    pub fn with_snippet_context(mut self, context_lines: usize) -> Self {
+        self.snippet_context = Some(context_lines);
+        self
+    }
+
+    /// Limit every search to paths under `scope`, a directory relative to the index root with
+    /// `/` separators and a trailing `/` (e.g. `services/api/`)
+    ///
+    /// The prefix is part of the index query, so it applies before ranking and pagination.
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Search through an existing reader instead of opening one, so segments already loaded
+    /// by it are reused (e.g. a reader kept open across many searches)
+    pub fn with_reader(self, reader: IndexReader) -> Self {
+        let _ = self.reader.set(reader);
+        self
+    }
+
+    /// The reader searches go through, opened once and reloaded shortly after each commit
+    fn reader(&self) -> Result<&IndexReader> {
+        if let Some(reader) = self.reader.get() {
+            return Ok(reader);
         }
+        let reader = open_reader(&self.index)?;
+        Ok(self.reader.get_or_init(|| reader))
+    }
+
+    /// `query` narrowed to the searcher's scope and the files `filters` can pass
+    fn narrow(&self, query: Box<dyn tantivy::query::Query>, filters: &SearchFilters) -> Result<Box<dyn tantivy::query::Query>> {
+        let scope = SearchFilters { scope: self.scope.clone(), ..SearchFilters::default() };
+        narrow_query(narrow_query(query, filters, &self.fields)?, &scope, &self.fields)
     }
 
     /// Search the index with a query string (literal text matching like grep)
-    pub fn search(&self, query: &str, limit: Option<usize>) -> Result<SearchResult> {
+    ///
+    /// `offset` skips that many ranked hits before `limit` is applied (for pagination).
+    pub fn search(&self, query: &str, limit: Option<usize>, offset: Option<usize>) -> Result<SearchResult> {
+        let limit = self.config.effective_limit(limit);
+        let offset = offset.unwrap_or(0);
+
+        let mut result = self.search_literal(query, offset + limit, &SearchFilters::default())?;
+        paginate(&mut result, offset, limit);
+        Ok(result)
+    }
+
+    /// Collect up to `window` literal matches in the files `filters` can pass, ranked
+    fn search_literal(&self, query: &str, window: usize, filters: &SearchFilters) -> Result<SearchResult> {
         let start = Instant::now();
-        let limit = limit.unwrap_or(self.config.default_limit).min(self.config.max_limit);
 
         // Get a reader
-        let reader = self.index.reader()?;
-        let searcher = reader.searcher();
-
-        // Build query parser for content field
-        let query_parser = QueryParser::for_index(&self.index, vec![self.fields.content]);
+        let searcher = self.reader()?.searcher();
 
         // Extract alphanumeric words for Tantivy query (it can't search special chars)
         // Then we'll post-filter for exact literal match
@@ -49,32 +144,83 @@ impl Searcher {
         // If no searchable terms, return empty
         if search_terms.is_empty() {
             return Ok(SearchResult {
-                total: 0,
-                hits: vec![],
                 query_time_ms: start.elapsed().as_millis() as u64,
-                text_hits: 0,
-                semantic_hits: 0,
+                ..SearchResult::empty()
             });
         }
 
-        // Search for the extracted terms
-        let tantivy_query_str = search_terms.join(" ");
-        let (tantivy_query, _errors) = query_parser.parse_query_lenient(&tantivy_query_str);
+        // Search for the words as an adjacent phrase when possible, otherwise for the extracted terms
+        // (fuzzy mode matches each word within the configured edit distance instead)
+        // (with `phrase_slop`, the phrase may have other tokens in between, so matching the
+        // phrase query stands in for the literal check)
+        let fuzzy = self.config.fuzzy_enabled;
+        let mut proximity = false;
+        // In `MultiTermMode::All` without a phrase, the words may be anywhere in the file
+        let mut every_term = false;
+        let query_terms: Vec<String> = search_terms.iter().map(|term| term.to_lowercase()).collect();
+        let (tantivy_query, effective_query) = if fuzzy {
+            let tantivy_query = fuzzy_terms_query(
+                &[self.fields.content, self.fields.path_text],
+                &search_terms,
+                self.config.fuzzy_distance,
+                self.config.multi_term_mode,
+            );
+            let description = describe_terms(&query_terms, self.config.multi_term_mode, Some(self.config.fuzzy_distance));
+            (tantivy_query, description)
+        } else {
+            let (base, description) = match self.phrase_query(query) {
+                Some(phrase) => {
+                    let phrase_text = query.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+                    let description = match self.config.phrase_slop {
+                        0 => format!("\"{}\"", phrase_text),
+                        slop => format!("\"{}\"~{}", phrase_text, slop),
+                    };
+                    proximity = self.config.phrase_slop > 0;
+                    (phrase, description)
+                }
+                None => {
+                    every_term = self.config.multi_term_mode == MultiTermMode::All && query_terms.len() > 1;
+                    (self.terms_query(&search_terms), describe_terms(&query_terms, self.config.multi_term_mode, None))
+                }
+            };
+            let chains = self.member_chains(query);
+            let description = if chains.is_empty() {
+                description
+            } else {
+                format!("+({}) {}", description, chains.join(" "))
+            };
+            (self.boost_member_chains(query, base), description)
+        };
+        let tantivy_query = self.narrow(tantivy_query, filters)?;
 
         // Fetch more results since we'll filter them down
-        let fetch_limit = limit * 10;
-        let top_docs = searcher.search(&tantivy_query, &TopDocs::with_limit(fetch_limit))?;
+        let fetch_limit = window * 10;
+        let (top_docs, mut timed_out) =
+            top_docs_within_deadline(&self.config, &searcher, &tantivy_query, fetch_limit, start)?;
 
         // Build results
         let mut hits = Vec::with_capacity(top_docs.len());
         let max_score = top_docs.first().map(|(score, _)| *score).unwrap_or(1.0);
 
-        // Case-insensitive literal matching (like grep -i)
+        // Case-insensitive literal matching (like grep -i); in proximity and all-words mode a
+        // line matches when it has all the words
         let query_lower = query.to_lowercase();
+        let line_matches = |line: &str| {
+            let line = line.to_lowercase();
+            if proximity || every_term {
+                query_terms.iter().all(|term| line.contains(term.as_str()))
+            } else {
+                line.contains(&query_lower)
+            }
+        };
 
         for (score, doc_address) in top_docs {
             // Stop if we have enough results
-            if hits.len() >= limit {
+            if hits.len() >= window {
+                break;
+            }
+            if deadline_passed(&self.config, start)? {
+                timed_out = true;
                 break;
             }
 
@@ -87,23 +233,57 @@ impl Searcher {
             let line_start = extract_u64(&doc, self.fields.line_start).unwrap_or(1);
             let chunk_id = extract_text(&doc, self.fields.chunk_id).unwrap_or_default();
 
-            // LITERAL GREP-LIKE FILTER: Only include if content contains exact query string
-            if !content.to_lowercase().contains(&query_lower) {
-                continue;
+            // LITERAL GREP-LIKE FILTER: Only include if content (or the file path) contains exact query string,
+            // or every word of it in all-words mode
+            // (fuzzy and proximity hits are already limited to near matches of the query words)
+            if !fuzzy && !proximity {
+                let (content_lower, path_lower) = (content.to_lowercase(), path.to_lowercase());
+                let contains = |text: &str| content_lower.contains(text) || path_lower.contains(text);
+                let matches = if every_term {
+                    query_terms.iter().all(|term| contains(term))
+                } else {
+                    contains(&query_lower)
+                };
+                if !matches {
+                    continue;
+                }
             }
 
             // Normalize score to 0-1 range
             let normalized_score = if max_score > 0.0 { score / max_score } else { 0.0 };
 
-            // Create snippet showing lines that match the query
-            let (snippet, match_line_offset, snippet_line_count) = create_relevant_snippet(&content, query, 10);
+            // Create snippet showing lines that match the query (or, inverted, the first lines that don't)
+            let (snippet, match_line_offset, snippet_line_count) = if self.config.invert_match {
+                match create_inverted_snippet(&content, line_matches, 10) {
+                    Some(snippet) => snippet,
+                    None => continue,
+                }
+            } else if self.config.definitions_only {
+                match matching_definition(&doc, &self.fields, &content, line_matches) {
+                    Some(line) => create_line_snippet(&content, line, 10),
+                    None => continue,
+                }
+            } else if let Some(context_lines) = self.snippet_context {
+                create_context_snippet(&content, line_matches, context_lines, MAX_CONTEXT_SNIPPET_LINES)
+                    .unwrap_or_else(|| create_relevant_snippet(&content, query, 10))
+            } else {
+                create_relevant_snippet(&content, query, 10)
+            };
 
             // Adjust line numbers to reflect where the match actually is
             let actual_line_start = line_start + match_line_offset as u64;
             let actual_line_end = actual_line_start + snippet_line_count.saturating_sub(1) as u64;
 
+            let location = if fuzzy || proximity || every_term || self.config.invert_match {
+                None
+            } else {
+                locate_match(&snippet, actual_line_start, |line| find_ignore_case(line, query))
+            };
+
             hits.push(SearchHit {
                 path,
+                file_extension: extract_extension(&doc, &self.fields),
+                language: extract_text(&doc, self.fields.language).unwrap_or_default(),
                 line_start: actual_line_start,
                 line_end: actual_line_end,
                 snippet,
@@ -111,9 +291,13 @@ impl Searcher {
                 is_chunk: !chunk_id.is_empty(),
                 doc_id,
                 match_type: MatchType::Text,
+                match_line: location.map(|(line, _, _)| line),
+                col_start: location.map(|(_, start, _)| start),
+                col_end: location.map(|(_, _, end)| end),
             });
         }
 
+        hits.sort_by(SearchHit::rank_cmp);
         let query_time_ms = start.elapsed().as_millis() as u64;
         let text_hits = hits.len();
 
@@ -122,7 +306,10 @@ impl Searcher {
             hits,
             query_time_ms,
             text_hits,
-            semantic_hits: 0,
+            timed_out,
+            effective_query,
+            query_terms,
+            ..SearchResult::empty()
         })
     }
 
@@ -131,86 +318,322 @@ impl Searcher {
         &self,
         query: &str,
         limit: Option<usize>,
+        offset: Option<usize>,
         filters: SearchFilters,
         use_regex: bool,
     ) -> Result<SearchResult> {
+        let limit = self.config.effective_limit(limit);
+        let offset = offset.unwrap_or(0);
+
+        // Over-fetch so filtering doesn't starve the requested page
+        let window = (offset + limit) * 2;
+
         // Use regex search if requested
         let mut result = if use_regex {
-            self.search_regex(query, Some(limit.unwrap_or(self.config.max_limit) * 2))?
+            self.search_regex_window(query, window, &filters)?
         } else {
-            self.search(query, Some(limit.unwrap_or(self.config.max_limit) * 2))?
+            self.search_literal(query, window, &filters)?
         };
 
-        // Apply filters
-        if let Some(ref extensions) = filters.extensions {
-            result.hits.retain(|hit| {
-                if let Some(ext) = std::path::Path::new(&hit.path).extension() {
-                    extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext.to_string_lossy()))
-                } else {
-                    false
-                }
+        apply_filters(&mut result, &filters, self.config.max_results_per_file);
+        paginate(&mut result, offset, limit);
+
+        Ok(result)
+    }
+
+    /// Search with a boolean expression, e.g. `error AND (timeout OR deadline) NOT test`
+    ///
+    /// See `BoolExpr` for the syntax. Hits are whatever the expression matches: there is
+    /// no literal post-filter on the whole query string.
+    pub fn search_boolean(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        filters: SearchFilters,
+    ) -> Result<SearchResult> {
+        let start = Instant::now();
+        let limit = self.config.effective_limit(limit);
+        let offset = offset.unwrap_or(0);
+
+        let expr = BoolExpr::parse(query)?;
+        let tantivy_query = self.narrow(expr.to_query(&self.fields), &filters)?;
+        let snippet_terms = expr.positive_terms().join(" ");
+
+        let searcher = self.reader()?.searcher();
+
+        // Over-fetch so filtering doesn't starve the requested page
+        let window = (offset + limit) * 2;
+        let (top_docs, mut timed_out) = top_docs_within_deadline(&self.config, &searcher, &tantivy_query, window, start)?;
+        let max_score = top_docs.first().map(|(score, _)| *score).unwrap_or(1.0);
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (score, doc_address) in top_docs {
+            if deadline_passed(&self.config, start)? {
+                timed_out = true;
+                break;
+            }
+
+            let doc = searcher.doc(doc_address)?;
+            let content = extract_text(&doc, self.fields.content).unwrap_or_default();
+            let line_start = extract_u64(&doc, self.fields.line_start).unwrap_or(1);
+            let chunk_id = extract_text(&doc, self.fields.chunk_id).unwrap_or_default();
+
+            let (snippet, match_line_offset, snippet_line_count) = create_relevant_snippet(&content, &snippet_terms, 10);
+            let actual_line_start = line_start + match_line_offset as u64;
+
+            hits.push(SearchHit {
+                path: extract_text(&doc, self.fields.path).unwrap_or_default(),
+                file_extension: extract_extension(&doc, &self.fields),
+                language: extract_text(&doc, self.fields.language).unwrap_or_default(),
+                line_start: actual_line_start,
+                line_end: actual_line_start + snippet_line_count.saturating_sub(1) as u64,
+                snippet,
+                score: if max_score > 0.0 { score / max_score } else { 0.0 },
+                is_chunk: !chunk_id.is_empty(),
+                doc_id: extract_text(&doc, self.fields.doc_id).unwrap_or_default(),
+                match_type: MatchType::Text,
+                match_line: None,
+                col_start: None,
+                col_end: None,
             });
         }
 
-        if let Some(ref paths) = filters.paths {
-            result.hits.retain(|hit| {
-                paths.iter().any(|p| hit.path.starts_with(p) || hit.path.contains(p))
+        hits.sort_by(SearchHit::rank_cmp);
+        let mut result = SearchResult {
+            total: hits.len(),
+            text_hits: hits.len(),
+            hits,
+            query_time_ms: start.elapsed().as_millis() as u64,
+            timed_out,
+            ..SearchResult::empty()
+        };
+        apply_filters(&mut result, &filters, self.config.max_results_per_file);
+        paginate(&mut result, offset, limit);
+
+        Ok(result)
+    }
+
+    /// List indexed files with no match for the query (like `grep -L`), sorted by path
+    ///
+    /// Every file from an `AllQuery`, minus the files with a document that matches the
+    /// query the same way `search_filtered` would. Copies of a file (see `content_hash_dedup`)
+    /// have no content of their own, so they match when its content does.
+    pub fn files_without_match(&self, query: &str, use_regex: bool) -> Result<Vec<String>> {
+        use std::collections::{BTreeSet, HashSet};
+        use tantivy::collector::DocSetCollector;
+        use tantivy::query::AllQuery;
+        use tantivy::schema::Value;
+
+        let searcher = self.reader()?.searcher();
+
+        // Candidates from the same pre-filter the search uses, confirmed against the content
+        let mut matched = HashSet::new();
+        let mut matched_content = HashSet::new();
+        if use_regex {
+            let regex = self.build_regex(query)?;
+            let prefilter = self.regex_prefilter(query).unwrap_or_else(|| Box::new(AllQuery));
+            let candidates = searcher.search(&self.narrow(prefilter, &SearchFilters::default())?, &DocSetCollector)?;
+            for address in candidates {
+                let doc: tantivy::TantivyDocument = searcher.doc(address)?;
+                if regex.is_match(&extract_text(&doc, self.fields.content).unwrap_or_default()) {
+                    matched.insert(extract_text(&doc, self.fields.path).unwrap_or_default());
+                    matched_content.insert(self.file_doc_id(&doc));
+                }
+            }
+        } else {
+            let search_terms: Vec<&str> = query
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|s| !s.is_empty())
+                .collect();
+            if !search_terms.is_empty() {
+                let prefilter = self.phrase_query(query).unwrap_or_else(|| self.terms_query(&search_terms));
+                let prefilter = self.narrow(prefilter, &SearchFilters::default())?;
+                let query_lower = query.to_lowercase();
+                for address in searcher.search(&prefilter, &DocSetCollector)? {
+                    let doc: tantivy::TantivyDocument = searcher.doc(address)?;
+                    let path = extract_text(&doc, self.fields.path).unwrap_or_default();
+                    let content = extract_text(&doc, self.fields.content).unwrap_or_default();
+                    if content.to_lowercase().contains(&query_lower) {
+                        matched_content.insert(self.file_doc_id(&doc));
+                        matched.insert(path);
+                    } else if path.to_lowercase().contains(&query_lower) {
+                        matched.insert(path);
+                    }
+                }
+            }
+        }
+
+        let mut files = BTreeSet::new();
+        for address in searcher.search(&self.narrow(Box::new(AllQuery), &SearchFilters::default())?, &DocSetCollector)? {
+            let doc: tantivy::TantivyDocument = searcher.doc(address)?;
+            let path = extract_text(&doc, self.fields.path).unwrap_or_default();
+            let is_copy = doc.get_first(self.fields.copy).and_then(|v| v.as_bool()).unwrap_or(false);
+            let copy_matched = is_copy && matched_content.contains(&self.file_doc_id(&doc));
+            if !matched.contains(&path) && !copy_matched {
+                files.insert(path);
+            }
+        }
+        Ok(files.into_iter().collect())
+    }
+
+    /// doc_id of the file a document belongs to (its own, or its parent's for a chunk)
+    fn file_doc_id(&self, doc: &tantivy::TantivyDocument) -> String {
+        extract_text(doc, self.fields.parent_doc)
+            .filter(|parent| !parent.is_empty())
+            .or_else(|| extract_text(doc, self.fields.doc_id))
+            .unwrap_or_default()
+    }
+
+    /// Search a single schema field (e.g. `path:auth`) in the files `filters` can pass
+    ///
+    /// `content` is matched by its words, all of which must be present; `path`, `extension`
+    /// and `doc_id` are stored untokenized, so they're matched as substrings.
+    pub fn search_field(
+        &self,
+        field_name: &str,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        filters: SearchFilters,
+    ) -> Result<SearchResult> {
+        use tantivy::query::{BooleanQuery, Occur, Query, RegexQuery, TermQuery};
+        use tantivy::schema::IndexRecordOption;
+        use tantivy::Term;
+
+        let start = Instant::now();
+        let limit = self.config.effective_limit(limit);
+        let offset = offset.unwrap_or(0);
+
+        let field = match field_name {
+            fields::CONTENT => self.fields.content,
+            fields::PATH => self.fields.path,
+            fields::EXTENSION => self.fields.extension,
+            fields::DOC_ID => self.fields.doc_id,
+            _ => {
+                return Err(YgrepError::Config(format!(
+                    "Unknown search field '{}' (valid fields: {})",
+                    field_name,
+                    SEARCHABLE_FIELDS.join(", ")
+                ).into()));
+            }
+        };
+        let is_content = field == self.fields.content;
+
+        let tantivy_query: Box<dyn Query> = if is_content {
+            // Split into words like the main search, since that's how content is indexed
+            let clauses: Vec<(Occur, Box<dyn Query>)> = query
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|word| !word.is_empty())
+                .map(|word| {
+                    let term = Term::from_field_text(field, &word.to_lowercase());
+                    let query: Box<dyn Query> = Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+                    (Occur::Must, query)
+                })
+                .collect();
+            if clauses.is_empty() {
+                return Ok(SearchResult {
+                    query_time_ms: start.elapsed().as_millis() as u64,
+                    ..SearchResult::empty()
+                });
+            }
+            Box::new(BooleanQuery::new(clauses))
+        } else {
+            let pattern = format!(".*{}.*", regex::escape(query));
+            Box::new(RegexQuery::from_pattern(&pattern, field)?)
+        };
+        let tantivy_query = self.narrow(tantivy_query, &filters)?;
+
+        let searcher = self.reader()?.searcher();
+
+        // Chunks share their file's path, so over-fetch and keep whole files for metadata fields
+        let window = offset + limit;
+        let fetch_limit = if is_content { window } else { window * 10 };
+        let (top_docs, mut timed_out) = top_docs_within_deadline(&self.config, &searcher, &tantivy_query, fetch_limit, start)?;
+        let max_score = top_docs.first().map(|(score, _)| *score).unwrap_or(1.0);
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (score, doc_address) in top_docs {
+            if hits.len() >= window {
+                break;
+            }
+            if deadline_passed(&self.config, start)? {
+                timed_out = true;
+                break;
+            }
+
+            let doc = searcher.doc(doc_address)?;
+            let chunk_id = extract_text(&doc, self.fields.chunk_id).unwrap_or_default();
+            if !is_content && !chunk_id.is_empty() {
+                continue;
+            }
+
+            let content = extract_text(&doc, self.fields.content).unwrap_or_default();
+            let line_start = extract_u64(&doc, self.fields.line_start).unwrap_or(1);
+            let snippet_query = if is_content { query } else { "" };
+            let (snippet, match_line_offset, snippet_line_count) = create_relevant_snippet(&content, snippet_query, 10);
+            let actual_line_start = line_start + match_line_offset as u64;
+
+            hits.push(SearchHit {
+                path: extract_text(&doc, self.fields.path).unwrap_or_default(),
+                file_extension: extract_extension(&doc, &self.fields),
+                language: extract_text(&doc, self.fields.language).unwrap_or_default(),
+                line_start: actual_line_start,
+                line_end: actual_line_start + snippet_line_count.saturating_sub(1) as u64,
+                snippet,
+                score: if max_score > 0.0 { score / max_score } else { 0.0 },
+                is_chunk: !chunk_id.is_empty(),
+                doc_id: extract_text(&doc, self.fields.doc_id).unwrap_or_default(),
+                match_type: MatchType::Text,
+                match_line: None,
+                col_start: None,
+                col_end: None,
             });
         }
 
-        // Re-limit
-        let limit = limit.unwrap_or(self.config.default_limit).min(self.config.max_limit);
-        result.hits.truncate(limit);
-        result.total = result.hits.len();
+        hits.sort_by(SearchHit::rank_cmp);
+        let mut result = SearchResult {
+            total: hits.len(),
+            text_hits: hits.len(),
+            hits,
+            query_time_ms: start.elapsed().as_millis() as u64,
+            timed_out,
+            ..SearchResult::empty()
+        };
+        apply_filters(&mut result, &filters, self.config.max_results_per_file);
+        paginate(&mut result, offset, limit);
 
         Ok(result)
     }
 
     /// Search the index with a regex pattern
     pub fn search_regex(&self, pattern: &str, limit: Option<usize>) -> Result<SearchResult> {
+        let limit = self.config.effective_limit(limit);
+        self.search_regex_window(pattern, limit, &SearchFilters::default())
+    }
+
+    /// Collect up to `window` regex matches in the files `filters` can pass, ranked
+    fn search_regex_window(&self, pattern: &str, window: usize, filters: &SearchFilters) -> Result<SearchResult> {
         let start = Instant::now();
-        let limit = limit.unwrap_or(self.config.default_limit).min(self.config.max_limit);
 
-        // Compile regex (case-insensitive by default, like grep -i)
-        let regex = match RegexBuilder::new(pattern)
-            .case_insensitive(true)
-            .build() {
-            Ok(r) => r,
-            Err(e) => {
-                return Err(crate::error::YgrepError::Search(
-                    format!("Invalid regex pattern: {}", e)
-                ));
-            }
-        };
+        let regex = self.build_regex(pattern)?;
+        let query_terms: Vec<String> = regex_terms(pattern).iter().map(|term| term.to_lowercase()).collect();
 
         // Get a reader
-        let reader = self.index.reader()?;
-        let searcher = reader.searcher();
-
-        // Build query parser for content field
-        let query_parser = QueryParser::for_index(&self.index, vec![self.fields.content]);
-
-        // Extract alphanumeric words from the regex pattern for Tantivy pre-filter
-        // This is a rough heuristic - we extract literal parts from the regex
-        let search_terms: Vec<&str> = pattern
-            .split(|c: char| !c.is_alphanumeric() && c != '_')
-            .filter(|s| !s.is_empty() && s.len() > 1)  // Skip single chars (likely regex syntax)
-            .collect();
+        let searcher = self.reader()?.searcher();
 
         // If we have searchable terms, use Tantivy to narrow down candidates
-        let candidates: Vec<_> = if !search_terms.is_empty() {
-            let tantivy_query_str = search_terms.join(" ");
-            let (tantivy_query, _errors) = query_parser.parse_query_lenient(&tantivy_query_str);
-
+        let (candidates, mut timed_out) = if let Some(tantivy_query) = self.regex_prefilter(pattern) {
             // Fetch many candidates since regex might be selective
-            let fetch_limit = limit * 20;
-            searcher.search(&tantivy_query, &TopDocs::with_limit(fetch_limit))?
+            let fetch_limit = window * 20;
+            let tantivy_query = self.narrow(tantivy_query, filters)?;
+            top_docs_within_deadline(&self.config, &searcher, &tantivy_query, fetch_limit, start)?
         } else {
             // No good search terms - scan all documents
             // This is slow but necessary for patterns like "^#" or ".*"
-            let all_query = tantivy::query::AllQuery;
-            let fetch_limit = limit * 50;
-            searcher.search(&all_query, &TopDocs::with_limit(fetch_limit))?
+            let all_query = self.narrow(Box::new(tantivy::query::AllQuery), filters)?;
+            let fetch_limit = window * 50;
+            top_docs_within_deadline(&self.config, &searcher, &all_query, fetch_limit, start)?
         };
 
         // Build results by applying regex filter
@@ -219,7 +642,11 @@ impl Searcher {
 
         for (score, doc_address) in candidates {
             // Stop if we have enough results
-            if hits.len() >= limit {
+            if hits.len() >= window {
+                break;
+            }
+            if deadline_passed(&self.config, start)? {
+                timed_out = true;
                 break;
             }
 
@@ -240,15 +667,37 @@ impl Searcher {
             // Normalize score to 0-1 range
             let normalized_score = if max_score > 0.0 { score / max_score } else { 0.0 };
 
-            // Create snippet showing lines that match the regex
-            let (snippet, match_line_offset, snippet_line_count) = create_regex_snippet(&content, &regex, 10);
+            // Create snippet showing lines that match the regex (or the whole match span in multiline mode)
+            let (snippet, match_line_offset, snippet_line_count) = if self.config.invert_match {
+                match create_inverted_snippet(&content, |line| regex.is_match(line), 10) {
+                    Some(snippet) => snippet,
+                    None => continue,
+                }
+            } else if self.config.definitions_only {
+                match matching_definition(&doc, &self.fields, &content, |line| regex.is_match(line)) {
+                    Some(line) => create_line_snippet(&content, line, 10),
+                    None => continue,
+                }
+            } else if self.config.multiline {
+                create_multiline_snippet(&content, &regex)
+            } else {
+                create_regex_snippet(&content, &regex, 10)
+            };
 
             // Adjust line numbers to reflect where the match actually is
             let actual_line_start = line_start + match_line_offset as u64;
             let actual_line_end = actual_line_start + snippet_line_count.saturating_sub(1) as u64;
 
+            let location = if self.config.invert_match || self.config.multiline {
+                None
+            } else {
+                locate_match(&snippet, actual_line_start, |line| regex.find(line).map(|m| (m.start(), m.end())))
+            };
+
             hits.push(SearchHit {
                 path,
+                file_extension: extract_extension(&doc, &self.fields),
+                language: extract_text(&doc, self.fields.language).unwrap_or_default(),
                 line_start: actual_line_start,
                 line_end: actual_line_end,
                 snippet,
@@ -256,9 +705,13 @@ impl Searcher {
                 is_chunk: !chunk_id.is_empty(),
                 doc_id,
                 match_type: MatchType::Text,
+                match_line: location.map(|(line, _, _)| line),
+                col_start: location.map(|(_, start, _)| start),
+                col_end: location.map(|(_, _, end)| end),
             });
         }
 
+        hits.sort_by(SearchHit::rank_cmp);
         let query_time_ms = start.elapsed().as_millis() as u64;
         let text_hits = hits.len();
 
@@ -267,9 +720,199 @@ impl Searcher {
             hits,
             query_time_ms,
             text_hits,
-            semantic_hits: 0,
+            timed_out,
+            // The pre-filter's words, or every document
+            effective_query: if query_terms.is_empty() { "*".to_string() } else { query_terms.join(" OR ") },
+            query_terms,
+            ..SearchResult::empty()
         })
     }
+
+    /// Compile a search regex (case-insensitive by default, like grep -i)
+    /// In multiline mode the pattern can span lines of the stored content
+    fn build_regex(&self, pattern: &str) -> Result<regex::Regex> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .dot_matches_new_line(self.config.multiline)
+            .multi_line(self.config.multiline)
+            .build()
+            .map_err(|e| YgrepError::Search(format!("Invalid regex pattern: {}", e)))
+    }
+
+    /// Build a Tantivy pre-filter from the words in a regex pattern, if it has any
+    fn regex_prefilter(&self, pattern: &str) -> Option<Box<dyn tantivy::query::Query>> {
+        let search_terms = regex_terms(pattern);
+        if search_terms.is_empty() {
+            return None;
+        }
+
+        let query_parser = QueryParser::for_index(&self.index, vec![self.fields.content, self.fields.path_text]);
+        let (tantivy_query, _errors) = query_parser.parse_query_lenient(&search_terms.join(" "));
+        Some(tantivy_query)
+    }
+
+    /// Build a phrase pre-fetch query for multi-word queries made only of whole tokens
+    /// (e.g. `public function all`); punctuation shifts token positions, so other queries use `terms_query`
+    ///
+    /// `phrase_slop` allows that many tokens of leeway between the words.
+    fn phrase_query(&self, query: &str) -> Option<Box<dyn tantivy::query::Query>> {
+        use tantivy::query::PhraseQuery;
+        use tantivy::Term;
+
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let is_token = |word: &&str| {
+            word.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '@' | '#' | '-'))
+        };
+        if words.len() < 2 || !words.iter().all(is_token) {
+            return None;
+        }
+
+        let terms = words
+            .iter()
+            .map(|word| Term::from_field_text(self.fields.content, &word.to_lowercase()))
+            .collect();
+        let mut phrase = PhraseQuery::new(terms);
+        phrase.set_slop(self.config.phrase_slop);
+        Some(Box::new(phrase))
+    }
+
+    /// Member access chains in the query (`config.get`, `Foo::bar`), as the code tokenizer indexes them
+    fn member_chains(&self, query: &str) -> Vec<String> {
+        let mut chains = Vec::new();
+        if let Some(mut analyzer) = self.index.tokenizers().get(CODE_TOKENIZER) {
+            analyzer.token_stream(query).process(&mut |token| {
+                let is_chain = [".", "::", "->"].iter().any(|separator| token.text.contains(separator));
+                if is_chain && !chains.contains(&token.text) {
+                    chains.push(token.text.clone());
+                }
+            });
+        }
+        chains
+    }
+
+    /// Boost documents containing the query's member access chains (`config.get`, `Foo::bar`)
+    /// as a unit, since the code tokenizer indexes each chain alongside its parts
+    fn boost_member_chains(&self, query: &str, base: Box<dyn tantivy::query::Query>) -> Box<dyn tantivy::query::Query> {
+        use tantivy::query::{BooleanQuery, Occur, TermQuery};
+        use tantivy::schema::IndexRecordOption;
+        use tantivy::Term;
+
+        let chains = self.member_chains(query);
+        if chains.is_empty() {
+            return base;
+        }
+
+        let mut clauses = vec![(Occur::Must, base)];
+        clauses.extend(chains.iter().map(|chain| {
+            let term = Term::from_field_text(self.fields.content, chain);
+            let query: Box<dyn tantivy::query::Query> = Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+            (Occur::Should, query)
+        }));
+        Box::new(BooleanQuery::new(clauses))
+    }
+
+    /// Build the BM25 pre-fetch query for the extracted query words
+    fn terms_query(&self, terms: &[&str]) -> Box<dyn tantivy::query::Query> {
+        use tantivy::query::{BooleanQuery, Occur, TermQuery};
+        use tantivy::schema::IndexRecordOption;
+        use tantivy::Term;
+
+        match self.config.multi_term_mode {
+            MultiTermMode::Any => {
+                let query_parser = QueryParser::for_index(&self.index, vec![self.fields.content, self.fields.path_text]);
+                let (query, _errors) = query_parser.parse_query_lenient(&terms.join(" "));
+                query
+            }
+            MultiTermMode::All => {
+                // Every word must be present, in the content or the path (both tokenizers lowercase)
+                let clauses = terms
+                    .iter()
+                    .map(|term| {
+                        let fields = [self.fields.content, self.fields.path_text];
+                        let either = fields
+                            .iter()
+                            .map(|&field| {
+                                let term = Term::from_field_text(field, &term.to_lowercase());
+                                let query: Box<dyn tantivy::query::Query> =
+                                    Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+                                (Occur::Should, query)
+                            })
+                            .collect();
+                        let query: Box<dyn tantivy::query::Query> = Box::new(BooleanQuery::new(either));
+                        (Occur::Must, query)
+                    })
+                    .collect();
+                Box::new(BooleanQuery::new(clauses))
+            }
+        }
+    }
+}
+
+/// Build a BM25 query matching each term exactly or within `distance` edits, in any of `fields`
+///
+/// With `MultiTermMode::All` every term must match; otherwise any of them may.
+pub(crate) fn fuzzy_terms_query(
+    fields: &[tantivy::schema::Field],
+    terms: &[&str],
+    distance: u8,
+    mode: MultiTermMode,
+) -> Box<dyn tantivy::query::Query> {
+    use tantivy::query::{BooleanQuery, FuzzyTermQuery, Occur, Query, TermQuery};
+    use tantivy::schema::IndexRecordOption;
+    use tantivy::Term;
+
+    let occur = match mode {
+        MultiTermMode::Any => Occur::Should,
+        MultiTermMode::All => Occur::Must,
+    };
+    let clauses = terms
+        .iter()
+        .map(|term| {
+            let term = term.to_lowercase();
+            let variants = fields
+                .iter()
+                .flat_map(|&field| {
+                    // Keep the exact term so exact matches still rank above near misses
+                    let field_term = Term::from_field_text(field, &term);
+                    let exact: Box<dyn Query> =
+                        Box::new(TermQuery::new(field_term.clone(), IndexRecordOption::WithFreqs));
+                    let fuzzy: Box<dyn Query> = Box::new(FuzzyTermQuery::new(field_term, distance, true));
+                    [(Occur::Should, exact), (Occur::Should, fuzzy)]
+                })
+                .collect();
+            let query: Box<dyn Query> = Box::new(BooleanQuery::new(variants));
+            (occur, query)
+        })
+        .collect();
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Words of a regex pattern usable as a Tantivy pre-filter
+///
+/// This is a rough heuristic: alphanumeric runs of the pattern, skipping single characters
+/// (likely regex syntax like `\s`).
+fn regex_terms(pattern: &str) -> Vec<&str> {
+    pattern
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|s| !s.is_empty() && s.len() > 1)
+        .collect()
+}
+
+/// Describe a query over `terms` for `SearchResult::effective_query`: `a OR b` (`a AND b` with
+/// `MultiTermMode::All`), with `~distance` on each term when fuzzy
+pub(crate) fn describe_terms(terms: &[String], mode: MultiTermMode, fuzzy_distance: Option<u8>) -> String {
+    let separator = match mode {
+        MultiTermMode::Any => " OR ",
+        MultiTermMode::All => " AND ",
+    };
+    terms
+        .iter()
+        .map(|term| match fuzzy_distance {
+            Some(distance) => format!("{}~{}", term, distance),
+            None => term.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
 }
 
 /// Filters for search
@@ -277,8 +920,244 @@ impl Searcher {
 pub struct SearchFilters {
     /// Filter by file extensions (e.g., ["rs", "ts"])
     pub extensions: Option<Vec<String>>,
-    /// Filter by path patterns
+    /// Filter by path: glob patterns (`src/auth/**`, `**/*_test.go`) or, without
+    /// wildcards, substrings of the path
     pub paths: Option<Vec<String>>,
+    /// Only these exact paths, e.g. the files changed since a git ref (see `fs::git::changed_files`)
+    pub files: Option<Vec<String>>,
+    /// Filter by detected language (e.g., ["rust", "python"], see `index::language`)
+    pub languages: Option<Vec<String>>,
+    /// Only paths under this directory: relative to the index root, with `/` separators and a
+    /// trailing `/` (e.g. a workspace's `scope`)
+    pub scope: Option<String>,
+}
+
+/// Fields that can be targeted with `Searcher::search_field` (and `field:value` queries)
+pub const SEARCHABLE_FIELDS: &[&str] = &[fields::CONTENT, fields::PATH, fields::EXTENSION, fields::DOC_ID];
+
+/// Open a reader that picks up new commits (including ones from other processes, like
+/// `ygrep watch`) shortly after they happen
+pub(crate) fn open_reader(index: &Index) -> Result<IndexReader> {
+    Ok(index.reader_builder().reload_policy(ReloadPolicy::OnCommitWithDelay).try_into()?)
+}
+
+/// Whether a search started at `start` has run past `timeout_ms`
+///
+/// With `strict_timeout`, running out of time is an error instead of a partial result.
+pub(crate) fn deadline_passed(config: &SearchConfig, start: Instant) -> Result<bool> {
+    let Some(timeout_ms) = config.timeout_ms else {
+        return Ok(false);
+    };
+    if start.elapsed().as_millis() < u128::from(timeout_ms) {
+        return Ok(false);
+    }
+    if config.strict_timeout {
+        return Err(YgrepError::Timeout);
+    }
+    Ok(true)
+}
+
+/// Collect the top `limit` documents for `query`, giving up once a search started at `start`
+/// runs past `timeout_ms`; returns no documents and `true` when it did
+///
+/// Tantivy can't interrupt a collection, so with a timeout it runs on its own thread, which
+/// is left to finish in the background if the deadline passes first.
+pub(crate) fn top_docs_within_deadline(
+    config: &SearchConfig,
+    searcher: &tantivy::Searcher,
+    query: &dyn tantivy::query::Query,
+    limit: usize,
+    start: Instant,
+) -> Result<(Vec<(tantivy::Score, tantivy::DocAddress)>, bool)> {
+    use std::sync::mpsc::{self, RecvTimeoutError};
+
+    let Some(timeout_ms) = config.timeout_ms else {
+        return Ok((searcher.search(query, &TopDocs::with_limit(limit))?, false));
+    };
+    if deadline_passed(config, start)? {
+        return Ok((vec![], true));
+    }
+    let remaining = std::time::Duration::from_millis(timeout_ms).saturating_sub(start.elapsed());
+
+    let (tx, rx) = mpsc::channel();
+    let thread_searcher = searcher.clone();
+    let query = query.box_clone();
+    let handle = std::thread::spawn(move || {
+        let _ = tx.send(thread_searcher.search(query.as_ref(), &TopDocs::with_limit(limit)));
+    });
+
+    match rx.recv_timeout(remaining) {
+        Ok(top_docs) => Ok((top_docs?, false)),
+        Err(RecvTimeoutError::Timeout) => {
+            if config.strict_timeout {
+                return Err(YgrepError::Timeout);
+            }
+            Ok((vec![], true))
+        }
+        // The search thread panicked before sending; surface the panic as searching inline would
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => unreachable!("the search thread always sends before exiting"),
+        },
+    }
+}
+
+/// Drop hits outside the extension/path/file filters, and cap hits per file
+fn apply_filters(result: &mut SearchResult, filters: &SearchFilters, max_results_per_file: Option<usize>) {
+    retain_filtered(&mut result.hits, filters);
+
+    // Keep one large file from crowding out the rest (hits are ranked, so the best ones stay)
+    if let Some(max_per_file) = max_results_per_file {
+        let mut per_file: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        result.hits.retain(|hit| {
+            let count = per_file.entry(hit.path.clone()).or_insert(0);
+            *count += 1;
+            *count <= max_per_file
+        });
+    }
+}
+
+/// Drop hits outside the extension/path/file filters
+pub(crate) fn retain_filtered(hits: &mut Vec<SearchHit>, filters: &SearchFilters) {
+    if let Some(extensions) = &filters.extensions {
+        hits.retain(|hit| {
+            if let Some(ext) = std::path::Path::new(&hit.path).extension() {
+                extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext.to_string_lossy()))
+            } else {
+                false
+            }
+        });
+    }
+
+    if let Some(paths) = &filters.paths {
+        hits.retain(|hit| {
+            paths.iter().any(|p| path_filter_matches(p, &hit.path))
+        });
+    }
+
+    if let Some(files) = &filters.files {
+        let files: std::collections::HashSet<&str> = files.iter().map(String::as_str).collect();
+        hits.retain(|hit| files.contains(hit.path.as_str()));
+    }
+
+    if let Some(languages) = &filters.languages {
+        hits.retain(|hit| languages.iter().any(|l| l.eq_ignore_ascii_case(&hit.language)));
+    }
+
+    if let Some(scope) = &filters.scope {
+        hits.retain(|hit| hit.path.starts_with(scope.as_str()));
+    }
+}
+
+/// A query for the documents whose path can pass `filters`, to narrow a search before ranking
+/// (`None` when nothing is filtered)
+///
+/// Globs only keep their literal parts, so this matches a superset and hits still need
+/// `retain_filtered`. Chunks have no extension of their own, so extensions are matched on the path.
+pub(crate) fn filter_query(filters: &SearchFilters, fields: &SchemaFields) -> Result<Option<Box<dyn tantivy::query::Query>>> {
+    use tantivy::query::{BooleanQuery, Occur, Query, RegexQuery, TermQuery};
+    use tantivy::schema::IndexRecordOption;
+    use tantivy::Term;
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+
+    if let Some(extensions) = &filters.extensions {
+        // Extensions compare case-insensitively
+        let alternatives: Vec<String> = extensions
+            .iter()
+            .map(|ext| {
+                ext.chars()
+                    .map(|c| match c.is_ascii_alphabetic() {
+                        true => format!("[{}{}]", c.to_ascii_lowercase(), c.to_ascii_uppercase()),
+                        false => regex::escape(&c.to_string()),
+                    })
+                    .collect()
+            })
+            .collect();
+        let pattern = format!(".*\\.({})", alternatives.join("|"));
+        clauses.push((Occur::Must, Box::new(RegexQuery::from_pattern(&pattern, fields.path)?)));
+    }
+
+    if let Some(paths) = &filters.paths {
+        let alternatives: Vec<String> = paths
+            .iter()
+            .map(|pattern| {
+                pattern
+                    .replace('\\', "/")
+                    .split(['*', '?'])
+                    .map(|part| regex::escape(part.trim_matches('/')))
+                    .collect::<Vec<_>>()
+                    .join(".*")
+            })
+            .collect();
+        let pattern = format!(".*({}).*", alternatives.join("|"));
+        clauses.push((Occur::Must, Box::new(RegexQuery::from_pattern(&pattern, fields.path)?)));
+    }
+
+    if let Some(files) = &filters.files {
+        let files: Vec<(Occur, Box<dyn Query>)> = files
+            .iter()
+            .map(|file| {
+                let term = Term::from_field_text(fields.path, file);
+                (Occur::Should, Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>)
+            })
+            .collect();
+        clauses.push((Occur::Must, Box::new(BooleanQuery::new(files))));
+    }
+
+    if let Some(languages) = &filters.languages {
+        let languages: Vec<(Occur, Box<dyn Query>)> = languages
+            .iter()
+            .map(|language| {
+                let term = Term::from_field_text(fields.language, &language.to_lowercase());
+                (Occur::Should, Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>)
+            })
+            .collect();
+        clauses.push((Occur::Must, Box::new(BooleanQuery::new(languages))));
+    }
+
+    if let Some(scope) = &filters.scope {
+        let pattern = format!("{}.*", regex::escape(scope));
+        clauses.push((Occur::Must, Box::new(RegexQuery::from_pattern(&pattern, fields.path)?)));
+    }
+
+    Ok((!clauses.is_empty()).then(|| Box::new(BooleanQuery::new(clauses)) as Box<dyn Query>))
+}
+
+/// `query` narrowed to the documents whose path can pass `filters` (see `filter_query`)
+pub(crate) fn narrow_query(
+    query: Box<dyn tantivy::query::Query>,
+    filters: &SearchFilters,
+    fields: &SchemaFields,
+) -> Result<Box<dyn tantivy::query::Query>> {
+    use tantivy::query::{BooleanQuery, Occur};
+
+    Ok(match filter_query(filters, fields)? {
+        Some(filter) => Box::new(BooleanQuery::new(vec![(Occur::Must, query), (Occur::Must, filter)])),
+        None => query,
+    })
+}
+
+/// Whether a path matches a path filter: a glob when it has `*` or `?` (e.g. `src/auth/**`
+/// or `**/*_test.go`), otherwise a substring, as if wrapped in `**`
+fn path_filter_matches(pattern: &str, path: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern, path)
+    } else {
+        path.contains(pattern)
+    }
+}
+
+/// Skip `offset` hits and keep at most `limit` of the rest
+/// The totals still count every hit, not just the page
+fn paginate(result: &mut SearchResult, offset: usize, limit: usize) {
+    result.total = result.hits.len();
+    result.text_hits = result.hits.len();
+    result.hits = std::mem::take(&mut result.hits)
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .collect();
 }
 
 /// Extract text value from a document
@@ -292,6 +1171,17 @@ fn extract_text(doc: &tantivy::TantivyDocument, field: tantivy::schema::Field) -
     })
 }
 
+/// Extract a document's file extension (chunks don't store one, so fall back to the path's)
+pub(crate) fn extract_extension(doc: &tantivy::TantivyDocument, fields: &SchemaFields) -> String {
+    extract_text(doc, fields.extension)
+        .filter(|ext| !ext.is_empty())
+        .or_else(|| {
+            let path = extract_text(doc, fields.path)?;
+            std::path::Path::new(&path).extension().map(|e| e.to_string_lossy().to_string())
+        })
+        .unwrap_or_default()
+}
+
 /// Extract u64 value from a document
 fn extract_u64(doc: &tantivy::TantivyDocument, field: tantivy::schema::Field) -> Option<u64> {
     doc.get_first(field).and_then(|v| {
@@ -303,42 +1193,109 @@ fn extract_u64(doc: &tantivy::TantivyDocument, field: tantivy::schema::Field) ->
     })
 }
 
-/// Create a snippet showing lines relevant to the query
+/// Create a snippet showing lines relevant to the query, centered on the best matching line
 /// Returns (snippet, line_offset_from_start, line_count)
-fn create_relevant_snippet(content: &str, query: &str, max_lines: usize) -> (String, usize, usize) {
+pub(crate) fn create_relevant_snippet(content: &str, query: &str, max_lines: usize) -> (String, usize, usize) {
     let lines: Vec<&str> = content.lines().collect();
     let query_lower = query.to_lowercase();
     let query_terms: Vec<&str> = query_lower.split_whitespace().collect();
+    let lines_lower: Vec<String> = lines.iter().map(|line| line.to_lowercase()).collect();
 
-    // Find lines that contain any query term
-    let mut matching_indices: Vec<usize> = Vec::new();
-    for (i, line) in lines.iter().enumerate() {
-        let line_lower = line.to_lowercase();
-        if query_terms.iter().any(|term| line_lower.contains(term)) {
-            matching_indices.push(i);
-        }
-    }
+    let context_before = 2;
+    let context_after = max_lines.saturating_sub(context_before + 1);
+    let window = |center: usize| {
+        let start = center.saturating_sub(context_before);
+        (start, (center + context_after + 1).min(lines.len()))
+    };
+
+    // Rank the lines that contain any query term: the whole query on one line first, then the
+    // window covering the most distinct terms, then the most terms and occurrences on the line
+    // (the earliest line wins ties)
+    let best_match = lines_lower
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| query_terms.iter().any(|term| line.contains(term)))
+        .max_by_key(|&(i, line)| {
+            let (start, end) = window(i);
+            let window_terms = query_terms
+                .iter()
+                .filter(|term| lines_lower[start..end].iter().any(|l| l.contains(*term)))
+                .count();
+            let line_terms = query_terms.iter().filter(|term| line.contains(*term)).count();
+            let occurrences: usize = query_terms.iter().map(|term| line.matches(term).count()).sum();
+            (line.contains(&query_lower), window_terms, line_terms, occurrences, std::cmp::Reverse(i))
+        })
+        .map(|(i, _)| i);
 
-    if matching_indices.is_empty() {
+    let Some(best_match) = best_match else {
         // No direct matches, return first lines
         let snippet = lines.iter().take(max_lines).copied().collect::<Vec<_>>().join("\n");
         let line_count = snippet.lines().count();
         return (snippet, 0, line_count);
-    }
-
-    // Get context around the first match
-    let first_match = matching_indices[0];
-    let context_before = 2;
-    let context_after = max_lines.saturating_sub(context_before + 1);
-
-    let start = first_match.saturating_sub(context_before);
-    let end = (first_match + context_after + 1).min(lines.len());
+    };
 
+    // Get context around the best match
+    let (start, end) = window(best_match);
     let snippet = lines[start..end].join("\n");
     let line_count = end - start;
     (snippet, start, line_count)
 }
 
+/// The first match in a snippet whose first line is `line_start`, as (line, start column,
+/// end column); `find` gives a match's byte range within a line, and columns count characters
+fn locate_match(
+    snippet: &str,
+    line_start: u64,
+    find: impl Fn(&str) -> Option<(usize, usize)>,
+) -> Option<(u64, u64, u64)> {
+    snippet.lines().enumerate().find_map(|(i, line)| {
+        let (start, end) = find(line)?;
+        let column = |byte: usize| line[..byte].chars().count() as u64;
+        Some((line_start + i as u64, column(start), column(end)))
+    })
+}
+
+/// Most lines a `with_snippet_context` snippet spans, so matches far apart in a large
+/// document don't return most of it
+const MAX_CONTEXT_SNIPPET_LINES: usize = 50;
+
+/// Create a snippet from `context_lines` before the first matching line to `context_lines`
+/// after the last one, within the document's lines and at most `max_lines` long (cut at the
+/// end, always keeping the first matching line)
+/// Returns (snippet, line_offset_from_start, line_count), or None when no line matches
+fn create_context_snippet(
+    content: &str,
+    is_match: impl Fn(&str) -> bool,
+    context_lines: usize,
+    max_lines: usize,
+) -> Option<(String, usize, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let first = lines.iter().position(|line| is_match(line))?;
+    let last = lines.iter().rposition(|line| is_match(line))?;
+    let max_lines = max_lines.max(1);
+    let start = first.saturating_sub(context_lines).max((first + 1).saturating_sub(max_lines));
+    let end = (last + context_lines + 1).min(lines.len()).min(start + max_lines);
+    Some((lines[start..end].join("\n"), start, end - start))
+}
+
+/// Create a snippet of the first run of lines that don't match (for `--invert-match`)
+/// Returns (snippet, line_offset_from_start, line_count), or None when every line matches
+fn create_inverted_snippet(
+    content: &str,
+    is_match: impl Fn(&str) -> bool,
+    max_lines: usize,
+) -> Option<(String, usize, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.iter().position(|line| !is_match(line))?;
+    let run: Vec<&str> = lines[start..]
+        .iter()
+        .take_while(|line| !is_match(line))
+        .take(max_lines)
+        .copied()
+        .collect();
+    Some((run.join("\n"), start, run.len()))
+}
+
 /// Create a snippet showing lines relevant to a regex match
 /// Returns (snippet, line_offset_from_start, line_count)
 fn create_regex_snippet(content: &str, regex: &regex::Regex, max_lines: usize) -> (String, usize, usize) {
@@ -372,48 +1329,758 @@ fn create_regex_snippet(content: &str, regex: &regex::Regex, max_lines: usize) -
     (snippet, start, line_count)
 }
 
+/// The first of a document's stored definition lines (as a 0-based index) that `is_match` accepts
+fn matching_definition(
+    doc: &tantivy::TantivyDocument,
+    fields: &SchemaFields,
+    content: &str,
+    is_match: impl Fn(&str) -> bool,
+) -> Option<usize> {
+    let lines: Vec<&str> = content.lines().collect();
+    doc.get_all(fields.def_lines)
+        .filter_map(|v| match v {
+            tantivy::schema::OwnedValue::U64(n) => (*n as usize).checked_sub(1),
+            _ => None,
+        })
+        .find(|&i| lines.get(i).is_some_and(|line| is_match(line)))
+}
+
+/// Create a snippet around one line (0-based), with the same context as `create_relevant_snippet`
+/// Returns (snippet, line_offset_from_start, line_count)
+fn create_line_snippet(content: &str, line: usize, max_lines: usize) -> (String, usize, usize) {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = line.saturating_sub(2);
+    let end = (line + max_lines.saturating_sub(3) + 1).min(lines.len());
+    (lines[start..end].join("\n"), start, end - start)
+}
+
+/// Create a snippet covering exactly the lines of the first regex match (no context lines)
+/// Returns (snippet, line_offset_from_start, line_count)
+fn create_multiline_snippet(content: &str, regex: &regex::Regex) -> (String, usize, usize) {
+    let Some(m) = regex.find(content) else {
+        return (String::new(), 0, 0);
+    };
+
+    let first_line = content[..m.start()].matches('\n').count();
+    let span_lines = content[m.start()..m.end()].trim_end_matches('\n').matches('\n').count() + 1;
+
+    let snippet = content
+        .lines()
+        .skip(first_line)
+        .take(span_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+    (snippet, first_line, span_lines)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::index::schema::build_document_schema;
+    use crate::index::schema::{build_document_schema, register_tokenizers};
     use tantivy::doc;
-    use tempfile::tempdir;
+    use tempfile::{tempdir, TempDir};
+
+    /// A one-line `.rs` file in the `/test` workspace; tests override the fields they need
+    struct TestDoc {
+        doc_id: String,
+        path: String,
+        content: String,
+        extension: String,
+        line_end: u64,
+        chunk_id: String,
+        path_text: bool,
+    }
 
-    #[test]
-    fn test_basic_search() -> Result<()> {
-        let temp_dir = tempdir().unwrap();
-        let index_path = temp_dir.path();
+    impl TestDoc {
+        fn new(doc_id: impl Into<String>, path: impl Into<String>, content: impl Into<String>) -> Self {
+            Self {
+                doc_id: doc_id.into(),
+                path: path.into(),
+                content: content.into(),
+                extension: "rs".to_string(),
+                line_end: 1,
+                chunk_id: String::new(),
+                path_text: false,
+            }
+        }
 
-        // Create index with schema
-        let schema = build_document_schema();
-        let index = Index::create_in_dir(index_path, schema.clone())?;
+        fn extension(self, extension: &str) -> Self {
+            Self { extension: extension.to_string(), ..self }
+        }
+
+        fn line_end(self, line_end: u64) -> Self {
+            Self { line_end, ..self }
+        }
+
+        fn chunk_id(self, chunk_id: impl Into<String>) -> Self {
+            Self { chunk_id: chunk_id.into(), ..self }
+        }
+
+        /// Also index the path for path-aware queries
+        fn with_path_text(self) -> Self {
+            Self { path_text: true, ..self }
+        }
+
+        fn build(self, fields: &SchemaFields) -> tantivy::TantivyDocument {
+            let mut doc = doc!(
+                fields.doc_id => self.doc_id,
+                fields.path => self.path.as_str(),
+                fields.workspace => "/test",
+                fields.content => self.content,
+                fields.mtime => 0u64,
+                fields.size => 100u64,
+                fields.extension => self.extension,
+                fields.line_start => 1u64,
+                fields.line_end => self.line_end,
+                fields.chunk_id => self.chunk_id,
+                fields.parent_doc => ""
+            );
+            if self.path_text {
+                doc.add_text(fields.path_text, self.path);
+            }
+            doc
+        }
+    }
 
+    /// An index in a temporary directory holding `docs`, committed
+    fn index_docs(docs: impl IntoIterator<Item = TestDoc>) -> Result<(TempDir, Index)> {
+        let temp_dir = tempdir().unwrap();
+        let schema = build_document_schema();
+        let index = Index::create_in_dir(temp_dir.path(), schema.clone())?;
+        register_tokenizers(index.tokenizers());
         let fields = SchemaFields::new(&schema);
 
-        // Add a test document
         let mut writer = index.writer(50_000_000)?;
-        writer.add_document(doc!(
-            fields.doc_id => "test1",
-            fields.path => "src/main.rs",
-            fields.workspace => "/test",
-            fields.content => "fn main() { println!(\"Hello, world!\"); }",
-            fields.mtime => 0u64,
-            fields.size => 100u64,
-            fields.extension => "rs",
-            fields.line_start => 1u64,
-            fields.line_end => 1u64,
-            fields.chunk_id => "",
-            fields.parent_doc => ""
-        ))?;
+        for doc in docs {
+            writer.add_document(doc.build(&fields))?;
+        }
         writer.commit()?;
+        Ok((temp_dir, index))
+    }
+
+    #[test]
+    fn test_relevant_snippet_prefers_densest_line() {
+        let mut lines: Vec<String> = (0..40).map(|i| format!("filler line {}", i)).collect();
+        lines[3] = "let timeout = 5;".to_string();
+        lines[25] = "// connection timeout: retry the connection".to_string();
+        let content = lines.join("\n");
+
+        // The line with both terms wins over the earlier line with just one
+        let (snippet, offset, _) = create_relevant_snippet(&content, "connection timeout", 6);
+        assert!(snippet.contains("retry the connection"));
+        assert_eq!(offset, 23);
+
+        // Single term: the first match, as before
+        let (snippet, offset, _) = create_relevant_snippet(&content, "timeout", 6);
+        assert!(snippet.contains("let timeout = 5;"));
+        assert_eq!(offset, 1);
+
+        // No match: the first lines
+        let (snippet, offset, line_count) = create_relevant_snippet(&content, "missing", 6);
+        assert!(snippet.starts_with("filler line 0"));
+        assert_eq!((offset, line_count), (0, 6));
+    }
+
+    #[test]
+    fn test_context_snippet_bounds() {
+        let mut lines: Vec<String> = (0..40).map(|i| format!("filler line {}", i)).collect();
+        lines[1] = "connect()".to_string();
+        lines[30] = "connect()".to_string();
+        let content = lines.join("\n");
+        let is_match = |line: &str| line.contains("connect");
+
+        // The context stops at the first and last lines of the document
+        let (snippet, offset, line_count) = create_context_snippet(&content, is_match, 3, 100).unwrap();
+        assert_eq!((offset, line_count), (0, 34));
+        assert!(snippet.ends_with("filler line 33"));
+
+        // Matches far apart are cut to the line limit, from the first match's context on
+        let (snippet, offset, line_count) = create_context_snippet(&content, is_match, 3, 10).unwrap();
+        assert_eq!((offset, line_count), (0, 10));
+        assert!(snippet.ends_with("filler line 9"));
+
+        // A context longer than the limit still shows the first match
+        let (snippet, offset, line_count) = create_context_snippet(&content, is_match, 20, 5).unwrap();
+        assert_eq!((offset, line_count), (0, 5));
+        assert!(snippet.contains("connect()"));
+        let (snippet, offset, _) = create_context_snippet(&lines[10..].join("\n"), is_match, 30, 5).unwrap();
+        assert_eq!(offset, 16);
+        assert!(snippet.ends_with("connect()"));
+
+        assert!(create_context_snippet(&content, |line| line.contains("missing"), 3, 10).is_none());
+    }
+
+    #[test]
+    fn test_basic_search() -> Result<()> {
+        let (_temp_dir, index) =
+            index_docs([TestDoc::new("test1", "src/main.rs", "fn main() { println!(\"Hello, world!\"); }")])?;
 
         // Search
         let config = SearchConfig::default();
         let searcher = Searcher::new(config, index);
-        let result = searcher.search("hello", None)?;
+        let result = searcher.search("hello", None, None)?;
+
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "src/main.rs");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_term_mode() -> Result<()> {
+        let contents = ["fn main() { exit(exit_code) }", "fn main() {}"];
+        let (_temp_dir, index) = index_docs(
+            contents.iter().enumerate().map(|(i, content)| TestDoc::new(format!("doc{}", i), format!("src/file{}.rs", i), *content)),
+        )?;
+
+        let candidates = |mode: MultiTermMode| -> Result<usize> {
+            let config = SearchConfig { multi_term_mode: mode, ..SearchConfig::default() };
+            let searcher = Searcher::new(config, index.clone());
+            let query = searcher.terms_query(&["Main", "exit_code"]);
+            Ok(index.reader()?.searcher().search(&query, &tantivy::collector::Count)?)
+        };
+
+        assert_eq!(candidates(MultiTermMode::Any)?, 2);
+        assert_eq!(candidates(MultiTermMode::All)?, 1);
+
+        // All words, not necessarily next to each other (`(` keeps this from being a phrase query)
+        let search = |mode: MultiTermMode, query: &str| -> Result<Vec<String>> {
+            let config = SearchConfig { multi_term_mode: mode, ..SearchConfig::default() };
+            let result = Searcher::new(config, index.clone()).search(query, None, None)?;
+            Ok(result.hits.into_iter().map(|hit| hit.doc_id).collect())
+        };
+        assert_eq!(search(MultiTermMode::All, "exit_code( main")?, ["doc0"]);
+        assert!(search(MultiTermMode::Any, "exit_code( main")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_query() -> Result<()> {
+        let contents = [
+            "public function all() {}",
+            "function all() {}\n// TODO: make this public",
+        ];
+        let (_temp_dir, index) = index_docs(contents.iter().enumerate().map(|(i, content)| {
+            TestDoc::new(format!("doc{}", i), format!("src/file{}.php", i), *content).extension("php").line_end(2)
+        }))?;
+
+        let searcher = Searcher::new(SearchConfig::default(), index.clone());
+        let query = searcher.phrase_query("Public function").expect("two plain words are a phrase");
+        assert_eq!(index.reader()?.searcher().search(&query, &tantivy::collector::Count)?, 1);
+
+        // Single words and punctuated queries keep the term pre-fetch
+        assert!(searcher.phrase_query("function").is_none());
+        assert!(searcher.phrase_query("all() {").is_none());
+
+        let result = searcher.search("public function", None, None)?;
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "src/file0.php");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_columns() -> Result<()> {
+        let (_temp_dir, index) = index_docs([
+            TestDoc::new("doc0", "src/greet.rs", "fn greet() {\n    // héllo\n    println!(\"hello world\");\n}").line_end(4),
+        ])?;
+
+        let searcher = Searcher::new(SearchConfig::default(), index.clone());
+        let hit = &searcher.search("World", None, None)?.hits[0];
+        assert_eq!((hit.match_line, hit.col_start, hit.col_end), (Some(3), Some(20), Some(25)));
+
+        let hit = &searcher.search_regex(r"hello\s+world", None)?.hits[0];
+        assert_eq!((hit.match_line, hit.col_start, hit.col_end), (Some(3), Some(14), Some(25)));
+
+        // Columns count characters, not bytes
+        let hit = &searcher.search("héllo", None, None)?.hits[0];
+        assert_eq!((hit.match_line, hit.col_start, hit.col_end), (Some(2), Some(7), Some(12)));
+
+        let json = serde_json::to_value(hit).unwrap();
+        assert_eq!(json["col_start"], 7);
+        assert_eq!(json["col_end"], 12);
+
+        // Fuzzy hits have no single match
+        let config = SearchConfig { fuzzy_enabled: true, ..SearchConfig::default() };
+        let hit = &Searcher::new(config, index.clone()).search("wrld", None, None)?.hits[0];
+        assert_eq!((hit.match_line, hit.col_start, hit.col_end), (None, None, None));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_slop() -> Result<()> {
+        let contents = [
+            "public function all() {}",
+            "public static function find() {}",
+            "function run() {}\n// public",
+        ];
+        let (_temp_dir, index) = index_docs(contents.iter().enumerate().map(|(i, content)| {
+            TestDoc::new(format!("doc{}", i), format!("src/file{}.php", i), *content).extension("php").line_end(2)
+        }))?;
+
+        let paths = |config: SearchConfig| -> Result<Vec<String>> {
+            let result = Searcher::new(config, index.clone()).search("public function", None, None)?;
+            let mut paths: Vec<String> = result.hits.into_iter().map(|hit| hit.path).collect();
+            paths.sort();
+            Ok(paths)
+        };
+        assert_eq!(paths(SearchConfig::default())?, ["src/file0.php"]);
+
+        let config = SearchConfig { phrase_slop: 1, ..SearchConfig::default() };
+        assert_eq!(paths(config.clone())?, ["src/file0.php", "src/file1.php"]);
+        let result = Searcher::new(config, index.clone()).search("public function", None, None)?;
+        assert_eq!(result.effective_query, "\"public function\"~1");
+        let hit = result.hits.iter().find(|hit| hit.path == "src/file1.php").unwrap();
+        assert_eq!(hit.snippet, "public static function find() {}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_effective_query() -> Result<()> {
+        let (_temp_dir, index) = index_docs([])?;
+
+        let searcher = Searcher::new(SearchConfig::default(), index.clone());
+        let result = searcher.search("Public function", None, None)?;
+        assert_eq!(result.effective_query, "\"public function\"");
+        assert_eq!(result.query_terms, ["public", "function"]);
+        assert_eq!(searcher.search("config.get(key)", None, None)?.effective_query, "+(config OR get OR key) config.get");
+        assert_eq!(searcher.search_regex("fn\\s+main", None)?.effective_query, "fn OR main");
+
+        let config = SearchConfig {
+            fuzzy_enabled: true,
+            fuzzy_distance: 2,
+            multi_term_mode: MultiTermMode::All,
+            ..SearchConfig::default()
+        };
+        let searcher = Searcher::new(config, index);
+        assert_eq!(searcher.search("authenticte User", None, None)?.effective_query, "authenticte~2 AND user~2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_member_chain_boost() -> Result<()> {
+        let contents = [
+            "let config = load(); get(config); config = get(config);",
+            "return config.get(key);",
+        ];
+        let (_temp_dir, index) = index_docs(
+            contents.iter().enumerate().map(|(i, content)| TestDoc::new(format!("doc{}", i), format!("src/file{}.js", i), *content).extension("js")),
+        )?;
+        let fields = SchemaFields::new(&index.schema());
+
+        let searcher = Searcher::new(SearchConfig::default(), index.clone());
+        let top_path = |query: &dyn tantivy::query::Query| -> Result<String> {
+            let tantivy_searcher = index.reader()?.searcher();
+            let top = tantivy_searcher.search(query, &TopDocs::with_limit(1))?;
+            let doc: tantivy::TantivyDocument = tantivy_searcher.doc(top[0].1)?;
+            Ok(extract_text(&doc, fields.path).unwrap())
+        };
+
+        // The parts alone favour the file repeating them; the chain favours the file using it
+        let base = searcher.terms_query(&["config", "get"]);
+        assert_eq!(top_path(base.as_ref())?, "src/file0.js");
+        let boosted = searcher.boost_member_chains("config.get", searcher.terms_query(&["config", "get"]));
+        assert_eq!(top_path(boosted.as_ref())?, "src/file1.js");
+
+        Ok(())
+    }
 
+    #[test]
+    fn test_compound_identifier_search() -> Result<()> {
+        let (_temp_dir, index) =
+            index_docs([TestDoc::new("doc0", "src/users.ts", "const user = await getUserProfile(id);").extension("ts")])?;
+
+        // The whole identifier, one of its words, and a phrase across it all find the file
+        let searcher = Searcher::new(SearchConfig::default(), index);
+        for query in ["getUserProfile", "profile", "await getUserProfile"] {
+            let result = searcher.search(query, None, None)?;
+            assert_eq!(result.hits.len(), 1, "{}", query);
+            assert_eq!(result.hits[0].path, "src/users.ts");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_results_per_file() -> Result<()> {
+        // Three chunks of lib.rs and one main.rs, all matching
+        let paths = ["src/lib.rs", "src/lib.rs", "src/lib.rs", "src/main.rs"];
+        let (_temp_dir, index) = index_docs(
+            paths.iter().enumerate().map(|(i, path)| TestDoc::new(format!("doc{}", i), *path, "use std::io;").chunk_id(format!("chunk{}", i))),
+        )?;
+
+        let config = SearchConfig { max_results_per_file: Some(1), ..SearchConfig::default() };
+        let searcher = Searcher::new(config, index);
+        let result = searcher.search_filtered("use", None, None, SearchFilters::default(), false)?;
+
+        let mut paths: Vec<_> = result.hits.iter().map(|h| h.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["src/lib.rs", "src/main.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiline_regex() -> Result<()> {
+        let (_temp_dir, index) = index_docs([
+            TestDoc::new("doc1", "src/Foo.php", "<?php\n\nclass Foo\n{\n    public $bar;\n}").extension("php").line_end(6),
+        ])?;
+
+        let search = |multiline: bool, pattern: &str| {
+            let config = SearchConfig { multiline, ..SearchConfig::default() };
+            Searcher::new(config, index.clone()).search_filtered(pattern, None, None, SearchFilters::default(), true)
+        };
+
+        let result = search(true, r"class\s+Foo\s*\{.*?bar")?;
+        assert_eq!(result.hits.len(), 1);
+        let hit = &result.hits[0];
+        assert_eq!((hit.line_start, hit.line_end), (3, 5));
+        assert_eq!(hit.snippet, "class Foo\n{\n    public $bar;");
+
+        // Without multiline, `.` stops at the end of a line
+        assert!(search(false, r"class\s+Foo\s*\{.*?bar")?.hits.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_offset() -> Result<()> {
+        let (_temp_dir, index) =
+            index_docs((0..5).map(|i| TestDoc::new(format!("doc{}", i), format!("src/file{}.rs", i), "fn hello() {}")))?;
+
+        let searcher = Searcher::new(SearchConfig::default(), index);
+        let all = searcher.search("hello", Some(5), None)?;
+        let page = searcher.search("hello", Some(2), Some(2))?;
+
+        assert_eq!(page.hits.len(), 2);
+        assert_eq!(page.hits[0].doc_id, all.hits[2].doc_id);
+        assert_eq!(page.hits[1].doc_id, all.hits[3].doc_id);
+        // The total counts the hits before the page, not just those on it
+        assert_eq!(page.total, 4);
+
+        // Past the end yields nothing
+        assert!(searcher.search("hello", Some(2), Some(5))?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_matches_path() -> Result<()> {
+        let paths = ["src/auth/request_handler.rs", "src/main.rs"];
+        let (_temp_dir, index) =
+            index_docs(paths.iter().enumerate().map(|(i, path)| TestDoc::new(format!("doc{}", i), *path, "fn run() {}").with_path_text()))?;
+
+        // Neither file mentions "handler" in its content
+        let searcher = Searcher::new(SearchConfig::default(), index);
+        let result = searcher.search("Handler", None, None)?;
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "src/auth/request_handler.rs");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_field() -> Result<()> {
+        let paths = ["src/auth/login.rs", "src/main.rs"];
+        let (_temp_dir, index) =
+            index_docs(paths.iter().enumerate().map(|(i, path)| TestDoc::new(format!("doc{}", i), *path, "fn login() {}")))?;
+
+        let searcher = Searcher::new(SearchConfig::default(), index);
+
+        let result = searcher.search_field("path", "auth", None, None, SearchFilters::default())?;
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "src/auth/login.rs");
+
+        assert_eq!(searcher.search_field("content", "login", None, None, SearchFilters::default())?.hits.len(), 2);
+
+        // Every word of a multi-word value, wherever it is in the file
+        let search = |field: &str, value: &str, filters: SearchFilters| -> Result<Vec<String>> {
+            let result = searcher.search_field(field, value, None, None, filters)?;
+            Ok(result.hits.into_iter().map(|hit| hit.path).collect())
+        };
+        assert_eq!(search("content", "login fn", SearchFilters::default())?.len(), 2);
+        assert!(search("content", "login logout", SearchFilters::default())?.is_empty());
+
+        // Combined with the other filters
+        let filters = SearchFilters { paths: Some(vec!["src/auth".to_string()]), ..SearchFilters::default() };
+        assert_eq!(search("content", "login", filters)?, ["src/auth/login.rs"]);
+        let filters = SearchFilters { extensions: Some(vec!["py".to_string()]), ..SearchFilters::default() };
+        assert!(search("path", "main", filters)?.is_empty());
+
+        let err = searcher.search_field("author", "me", None, None, SearchFilters::default()).unwrap_err();
+        assert!(err.chain().contains("valid fields: content, path"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy_search() -> Result<()> {
+        let (_temp_dir, index) =
+            index_docs([TestDoc::new("doc0", "src/auth.rs", "fn authenticate(user: &User) -> bool { true }")])?;
+
+        // One-character typo
+        let literal = Searcher::new(SearchConfig::default(), index.clone());
+        assert!(literal.search("authenticat", None, None)?.hits.is_empty());
+
+        let config = SearchConfig { fuzzy_enabled: true, ..SearchConfig::default() };
+        let fuzzy = Searcher::new(config, index);
+        let result = fuzzy.search("authenticat", None, None)?;
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].path, "src/auth.rs");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_filter_globs() {
+        let hit = |path: &str| SearchHit {
+            path: path.to_string(),
+            file_extension: String::new(),
+            language: String::new(),
+            line_start: 1,
+            line_end: 1,
+            snippet: String::new(),
+            score: 1.0,
+            is_chunk: false,
+            doc_id: path.to_string(),
+            match_type: MatchType::Text,
+            match_line: None,
+            col_start: None,
+            col_end: None,
+        };
+        let filtered = |paths: &[&str]| {
+            let mut result = SearchResult::empty();
+            result.hits = ["src/auth/login.go", "src/auth/login_test.go", "pkg/api/auth_test.go", "README.md"]
+                .into_iter()
+                .map(hit)
+                .collect();
+            let filters = SearchFilters { paths: Some(paths.iter().map(|p| p.to_string()).collect()), ..SearchFilters::default() };
+            apply_filters(&mut result, &filters, None);
+            result.hits.into_iter().map(|h| h.path).collect::<Vec<_>>()
+        };
+
+        assert_eq!(filtered(&["src/auth/**"]), ["src/auth/login.go", "src/auth/login_test.go"]);
+        assert_eq!(filtered(&["**/*_test.go"]), ["src/auth/login_test.go", "pkg/api/auth_test.go"]);
+        // No wildcards: substring, as before
+        assert_eq!(filtered(&["auth"]), ["src/auth/login.go", "src/auth/login_test.go", "pkg/api/auth_test.go"]);
+        assert_eq!(filtered(&["README", "api/"]), ["pkg/api/auth_test.go", "README.md"]);
+    }
+
+    #[test]
+    fn test_filter_query() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let schema = build_document_schema();
+        let index = Index::create_in_dir(temp_dir.path(), schema.clone())?;
+        register_tokenizers(index.tokenizers());
+        let fields = SchemaFields::new(&schema);
+
+        let paths = ["src/auth/login.go", "src/auth/login_test.go", "pkg/api/Handler.TS", "README.md"];
+        let mut writer = index.writer(50_000_000)?;
+        for path in paths {
+            // Stored like a chunk, without an extension
+            writer.add_document(doc!(fields.path => path, fields.extension => ""))?;
+        }
+        writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // Matching paths, in the order they were added
+        let matching = |filters: SearchFilters| -> Result<Vec<String>> {
+            let query = filter_query(&filters, &fields)?.expect("filters set");
+            let mut addresses: Vec<_> = searcher.search(&query, &tantivy::collector::DocSetCollector)?.into_iter().collect();
+            addresses.sort();
+            addresses
+                .into_iter()
+                .map(|address| Ok(extract_text(&searcher.doc(address)?, fields.path).unwrap_or_default()))
+                .collect()
+        };
+
+        assert!(filter_query(&SearchFilters::default(), &fields)?.is_none());
+        let extensions = |exts: &[&str]| SearchFilters { extensions: Some(exts.iter().map(|e| e.to_string()).collect()), ..SearchFilters::default() };
+        assert_eq!(matching(extensions(&["go"]))?, ["src/auth/login.go", "src/auth/login_test.go"]);
+        assert_eq!(matching(extensions(&["ts", "md"]))?, ["pkg/api/Handler.TS", "README.md"]);
+
+        let paths_filter = |globs: &[&str]| SearchFilters { paths: Some(globs.iter().map(|g| g.to_string()).collect()), ..SearchFilters::default() };
+        assert_eq!(matching(paths_filter(&["**/*_test.go"]))?, ["src/auth/login_test.go"]);
+        assert_eq!(matching(paths_filter(&["src/auth/**", "api/"]))?, ["src/auth/login.go", "src/auth/login_test.go", "pkg/api/Handler.TS"]);
+
+        let files = SearchFilters { files: Some(vec!["README.md".to_string()]), extensions: Some(vec!["md".to_string()]), ..SearchFilters::default() };
+        assert_eq!(matching(files)?, ["README.md"]);
+
+        // The scope is a directory prefix, not a substring
+        let scope = |dir: &str| SearchFilters { scope: Some(dir.to_string()), ..SearchFilters::default() };
+        assert_eq!(matching(scope("src/auth/"))?, ["src/auth/login.go", "src/auth/login_test.go"]);
+        assert!(matching(scope("auth/"))?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filters_apply_before_ranking() -> Result<()> {
+        // Many short Rust files outrank the one long Python file
+        let padding = "let value = compute(value);\n".repeat(50);
+        let files = (0..30)
+            .map(|i| (format!("src/file{}.rs", i), "rs", "fn hello() {}".to_string()))
+            .chain([("scripts/tool.py".to_string(), "py", format!("{}def hello(): pass\n", padding))]);
+        let (_temp_dir, index) =
+            index_docs(files.enumerate().map(|(i, (path, ext, content))| TestDoc::new(format!("doc{}", i), path, content).extension(ext)))?;
+
+        let searcher = Searcher::new(SearchConfig::default(), index);
+        let filters = SearchFilters { extensions: Some(vec!["py".to_string()]), ..SearchFilters::default() };
+        for use_regex in [false, true] {
+            let result = searcher.search_filtered("hello", Some(1), None, filters.clone(), use_regex)?;
+            assert_eq!(result.hits.len(), 1, "regex: {}", use_regex);
+            assert_eq!(result.hits[0].path, "scripts/tool.py");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hit_file_extension() -> Result<()> {
+        // A whole file, and a chunk (chunks are stored without an extension)
+        let files = [("src/main.rs", "rs", ""), ("app/views.py", "", "abc:0")];
+        let (_temp_dir, index) = index_docs(files.into_iter().map(|(path, extension, chunk_id)| {
+            TestDoc::new(format!("id-{}", path), path, "def handler(): pass").extension(extension).chunk_id(chunk_id)
+        }))?;
+
+        let result = Searcher::new(SearchConfig::default(), index).search("handler", None, None)?;
+        let hits: Vec<_> = result.hits.iter().map(|hit| (hit.path.as_str(), hit.file_extension.as_str(), hit.is_chunk)).collect();
+        assert_eq!(hits, [("app/views.py", "py", true), ("src/main.rs", "rs", false)]);
+        assert!(result.format_json().contains("\"file_extension\": \"rs\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_equal_scores_stable_order() -> Result<()> {
+        let temp_dir = tempdir().unwrap();
+        let schema = build_document_schema();
+        let index = Index::create_in_dir(temp_dir.path(), schema.clone())?;
+        register_tokenizers(index.tokenizers());
+        let fields = SchemaFields::new(&schema);
+
+        // Identical content scores identically; spread over segments out of path order
+        let mut writer = index.writer(50_000_000)?;
+        for batch in [["src/d.rs", "src/b.rs"], ["src/c.rs", "src/a.rs"]] {
+            for path in batch {
+                writer.add_document(TestDoc::new(format!("id-{}", path), path, "fn handler() { respond() }").build(&fields))?;
+            }
+            writer.commit()?;
+        }
+
+        let searcher = Searcher::new(SearchConfig::default(), index);
+        for use_regex in [false, true] {
+            for _ in 0..3 {
+                let result = searcher.search_filtered("handler", None, None, SearchFilters::default(), use_regex)?;
+                let paths: Vec<_> = result.hits.iter().map(|hit| hit.path.as_str()).collect();
+                assert_eq!(paths, ["src/a.rs", "src/b.rs", "src/c.rs", "src/d.rs"]);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_timeout() -> Result<()> {
+        let (_temp_dir, index) = index_docs((0..2000).map(|i| {
+            TestDoc::new(format!("doc{}", i), format!("src/file{}.rs", i), format!("fn handler_{}() {{ handle(request) }}", i))
+        }))?;
+
+        let search = |timeout_ms: Option<u64>, strict_timeout: bool, use_regex: bool| {
+            let config = SearchConfig { timeout_ms, strict_timeout, max_limit: 1000, ..SearchConfig::default() };
+            let filters = SearchFilters::default();
+            Searcher::new(config, index.clone()).search_filtered("handle", Some(1000), None, filters, use_regex)
+        };
+
+        let complete = search(None, false, false)?;
+        assert!(!complete.timed_out);
+        assert_eq!(complete.hits.len(), 1000);
+
+        // Out of time before the first candidate: partial (empty) results, flagged
+        for use_regex in [false, true] {
+            let partial = search(Some(0), false, use_regex)?;
+            assert!(partial.timed_out);
+            assert!(partial.hits.is_empty());
+        }
+
+        assert!(matches!(search(Some(0), true, false), Err(YgrepError::Timeout)));
+
+        // Field searches stop at the deadline too
+        let config = SearchConfig { timeout_ms: Some(0), ..SearchConfig::default() };
+        let partial = Searcher::new(config, index.clone()).search_field("path", "file1", None, None, SearchFilters::default())?;
+        assert!(partial.timed_out);
+        assert!(partial.hits.is_empty());
+
+        // Collection that finishes in time returns every document; past the deadline, none
+        let searcher = index.reader()?.searcher();
+        let query = tantivy::query::AllQuery;
+        let config = SearchConfig { timeout_ms: Some(60_000), ..SearchConfig::default() };
+        let (top_docs, timed_out) = top_docs_within_deadline(&config, &searcher, &query, 10, Instant::now())?;
+        assert_eq!((top_docs.len(), timed_out), (10, false));
+        let expired = Instant::now() - std::time::Duration::from_secs(120);
+        let (top_docs, timed_out) = top_docs_within_deadline(&config, &searcher, &query, 10, expired)?;
+        assert_eq!((top_docs.len(), timed_out), (0, true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_boolean() -> Result<()> {
+        let files = [
+            ("src/client.rs", "return Err(error::timeout());"),
+            ("src/server.rs", "log the error when the deadline passes"),
+            ("tests/client_test.rs", "assert the error is a timeout"),
+            ("src/lib.rs", "pub mod client; // no errors here"),
+        ];
+        let (_temp_dir, index) = index_docs(
+            files.into_iter().enumerate().map(|(i, (path, content))| TestDoc::new(format!("doc{}", i), path, content).with_path_text()),
+        )?;
+
+        let searcher = Searcher::new(SearchConfig::default(), index);
+        let paths = |query: &str| -> Result<Vec<String>> {
+            let result = searcher.search_boolean(query, None, None, SearchFilters::default())?;
+            let mut paths: Vec<String> = result.hits.into_iter().map(|hit| hit.path).collect();
+            paths.sort();
+            Ok(paths)
+        };
+
+        assert_eq!(paths("error AND timeout")?, ["src/client.rs", "tests/client_test.rs"]);
+        assert_eq!(paths("timeout OR deadline")?, ["src/client.rs", "src/server.rs", "tests/client_test.rs"]);
+        assert_eq!(paths("error AND (timeout OR deadline) NOT test")?, ["src/client.rs", "src/server.rs"]);
+        assert_eq!(paths("NOT error")?, ["src/lib.rs"]);
+        assert_eq!(paths("\"the deadline\" OR errors")?, ["src/lib.rs", "src/server.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invert_match() -> Result<()> {
+        let files = [
+            ("src/main.rs", "// Licensed under MIT\nfn main() {}\nfn run() {}"),
+            ("src/lib.rs", "pub mod auth;"),
+            ("src/auth.rs", "// licensed under mit"),
+        ];
+        let (_temp_dir, index) = index_docs(
+            files.into_iter().enumerate().map(|(i, (path, content))| TestDoc::new(format!("doc{}", i), path, content).line_end(3)),
+        )?;
+
+        // File level: only the file without the header
+        let searcher = Searcher::new(SearchConfig::default(), index.clone());
+        assert_eq!(searcher.files_without_match("Licensed under MIT", false)?, ["src/lib.rs"]);
+        assert_eq!(searcher.files_without_match("^// licensed", true)?, ["src/lib.rs"]);
+
+        // Line level: the lines of matching files that don't match (files where every line matches are left out)
+        let config = SearchConfig { invert_match: true, ..SearchConfig::default() };
+        let searcher = Searcher::new(config, index);
+        let result = searcher.search("Licensed under MIT", None, None)?;
         assert_eq!(result.hits.len(), 1);
         assert_eq!(result.hits[0].path, "src/main.rs");
+        assert_eq!(result.hits[0].snippet, "fn main() {}\nfn run() {}");
+        assert_eq!((result.hits[0].line_start, result.hits[0].line_end), (2, 3));
 
         Ok(())
     }
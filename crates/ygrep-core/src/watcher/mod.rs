@@ -9,11 +9,26 @@ use notify_debouncer_full::{
     notify::RecursiveMode,
     DebounceEventResult,
 };
-use parking_lot::Mutex;
-use tokio::sync::mpsc;
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{mpsc, oneshot};
 
 use crate::config::IndexerConfig;
 use crate::error::{Result, YgrepError};
+use crate::fs::glob::{glob_match, glob_match_dir};
+use crate::search::{SearchFilters, SearchResult};
+
+/// A search answered by `Workspace::watch_and_serve` between index updates
+#[derive(Debug)]
+pub struct SearchRequest {
+    /// Query text (literal matching, as with `Workspace::search_filtered`)
+    pub query: String,
+    /// Extension and path filters
+    pub filters: SearchFilters,
+    /// Maximum number of results (None = the configured default)
+    pub limit: Option<usize>,
+    /// Receives the result, or the error the search failed with
+    pub reply: oneshot::Sender<Result<SearchResult>>,
+}
 
 /// Events emitted by the file watcher
 #[derive(Debug, Clone)]
@@ -52,12 +67,13 @@ type PlatformDebouncer = notify_debouncer_full::Debouncer<
 /// File system watcher with debouncing
 pub struct FileWatcher {
     root: PathBuf,
-    #[allow(dead_code)]
     config: IndexerConfig,
     debouncer: PlatformDebouncer,
     event_rx: mpsc::UnboundedReceiver<WatchEvent>,
     /// All paths being watched (root + symlink targets)
     watched_paths: Vec<PathBuf>,
+    /// Ignore patterns added after the watcher was created (see `add_ignore_pattern`)
+    runtime_ignore_patterns: Arc<RwLock<Vec<String>>>,
 }
 
 impl FileWatcher {
@@ -68,7 +84,8 @@ impl FileWatcher {
 
         // Find symlink targets upfront so we can pass them to the event handler
         let symlink_targets = if config.follow_symlinks {
-            find_symlink_targets(&root)
+            let skip_dirs: &[String] = if config.no_ignore { &[] } else { &config.skip_dirs };
+            find_symlink_targets(&root, skip_dirs)
         } else {
             vec![]
         };
@@ -80,6 +97,8 @@ impl FileWatcher {
 
         // Clone for the closure
         let config_clone = config.clone();
+        let runtime_ignore_patterns = Arc::new(RwLock::new(Vec::new()));
+        let runtime_ignore_patterns_for_closure = Arc::clone(&runtime_ignore_patterns);
 
         // Create debouncer with 500ms delay
         let debouncer = new_debouncer(
@@ -89,6 +108,7 @@ impl FileWatcher {
                 use std::collections::HashSet;
 
                 let tx = event_tx.lock();
+                let runtime_ignore_patterns = runtime_ignore_patterns_for_closure.read();
                 match result {
                     Ok(events) => {
                         // Deduplicate events by path to avoid processing same file twice
@@ -100,6 +120,7 @@ impl FileWatcher {
                                 &event,
                                 &watched_paths_for_closure,
                                 &config_clone,
+                                &runtime_ignore_patterns,
                             );
                             for e in watch_events {
                                 match &e {
@@ -136,9 +157,24 @@ impl FileWatcher {
             debouncer,
             event_rx,
             watched_paths,
+            runtime_ignore_patterns,
         })
     }
 
+    /// Ignore paths matching `pattern` from now on, in addition to `ignore_patterns` from the
+    /// config. Returns false if the pattern was already ignored.
+    ///
+    /// Files already in the index stay there; only later changes to them are skipped.
+    pub fn add_ignore_pattern(&self, pattern: String) -> bool {
+        let mut patterns = self.runtime_ignore_patterns.write();
+        if self.config.ignore_patterns.contains(&pattern) || patterns.contains(&pattern) {
+            return false;
+        }
+        tracing::info!("Ignoring {}", pattern);
+        patterns.push(pattern);
+        true
+    }
+
     /// Start watching the directory
     pub fn start(&mut self) -> Result<()> {
         // Watch all paths (root + symlink targets found during construction)
@@ -179,6 +215,14 @@ impl FileWatcher {
         self.event_rx.recv().await
     }
 
+    /// Get the next watch event if one is already queued (non-blocking)
+    ///
+    /// Events from one debounce batch are queued together, so draining with
+    /// this after `next_event` handles a whole batch.
+    pub fn try_next_event(&mut self) -> Option<WatchEvent> {
+        self.event_rx.try_recv().ok()
+    }
+
     /// Get the root directory being watched
     pub fn root(&self) -> &Path {
         &self.root
@@ -190,30 +234,30 @@ fn process_notify_event(
     event: &notify_debouncer_full::DebouncedEvent,
     watched_paths: &[PathBuf],
     config: &IndexerConfig,
+    runtime_ignore_patterns: &[String],
 ) -> Vec<WatchEvent> {
     use notify::EventKind;
 
     let mut events = Vec::new();
 
     for path in &event.paths {
-        // Skip if path is not under any watched path
-        let is_under_watched = watched_paths.iter().any(|wp| path.starts_with(wp));
-        if !is_under_watched {
+        // Skip if path is not under any watched path; the checks below only look at the part
+        // inside it, like the walker, so a project under e.g. /tmp isn't ignored entirely
+        let Some(relative) = watched_paths.iter().find_map(|wp| path.strip_prefix(wp).ok()) else {
             continue;
-        }
+        };
 
         // Skip hidden files/directories
-        if is_hidden(path) {
+        if is_hidden(relative) {
             continue;
         }
 
-        // Skip ignored directories
-        if is_ignored_dir(path) {
-            continue;
-        }
-
-        // Skip files matching ignore patterns
-        if matches_ignore_pattern(path, config) {
+        // Skip ignored directories and files matching ignore patterns (unless nothing is ignored)
+        if !config.no_ignore
+            && (is_ignored_dir(relative, &config.skip_dirs)
+                || matches_ignore_pattern(path, relative, &config.ignore_patterns)
+                || matches_ignore_pattern(path, relative, runtime_ignore_patterns))
+        {
             continue;
         }
 
@@ -225,10 +269,8 @@ fn process_notify_event(
                     events.push(WatchEvent::Changed(path.clone()));
                 }
             }
-            EventKind::Modify(_) => {
-                if path.is_file() {
-                    events.push(WatchEvent::Changed(path.clone()));
-                }
+            EventKind::Modify(_) if path.is_file() => {
+                events.push(WatchEvent::Changed(path.clone()));
             }
             EventKind::Remove(_) => {
                 // Can't check if it was a file or dir since it's deleted
@@ -254,7 +296,7 @@ fn is_hidden(path: &Path) -> bool {
 
 /// Find all symlink targets in a directory tree
 /// Returns the canonical paths of directories that are symlinked
-fn find_symlink_targets(root: &Path) -> Vec<PathBuf> {
+fn find_symlink_targets(root: &Path, skip_dirs: &[String]) -> Vec<PathBuf> {
     use std::collections::HashSet;
     use walkdir::WalkDir;
 
@@ -281,7 +323,7 @@ fn find_symlink_targets(root: &Path) -> Vec<PathBuf> {
 
                 // Canonicalize to resolve any .. or . components
                 if let Ok(canonical) = std::fs::canonicalize(&absolute_target) {
-                    if canonical.is_dir() && !is_ignored_dir(&canonical) {
+                    if canonical.is_dir() && !is_ignored_dir(&canonical, skip_dirs) {
                         targets.insert(canonical);
                     }
                 }
@@ -292,35 +334,29 @@ fn find_symlink_targets(root: &Path) -> Vec<PathBuf> {
     targets.into_iter().collect()
 }
 
-/// Check if path is in an ignored directory
-fn is_ignored_dir(path: &Path) -> bool {
-    const IGNORED_DIRS: &[&str] = &[
-        "node_modules",
-        "vendor",
-        "target",
-        "dist",
-        "build",
-        "cache",
-        ".git",
-        "__pycache__",
-        "logs",
-        "tmp",
-    ];
-
+/// Check if path is in a directory named in `skip_dirs`
+fn is_ignored_dir(path: &Path, skip_dirs: &[String]) -> bool {
     path.components().any(|c| {
         c.as_os_str()
             .to_str()
-            .map(|s| IGNORED_DIRS.contains(&s))
+            .map(|s| skip_dirs.iter().any(|d| d == s))
             .unwrap_or(false)
     })
 }
 
-/// Check if path matches custom ignore patterns
-fn matches_ignore_pattern(path: &Path, config: &IndexerConfig) -> bool {
-    let path_str = path.to_string_lossy();
+/// Check if path matches custom ignore patterns (matched against `relative`, its path
+/// inside the watched directory)
+fn matches_ignore_pattern(path: &Path, relative: &Path, patterns: &[String]) -> bool {
+    let path_str = relative.to_string_lossy();
+    let is_dir = path.is_dir();
 
-    for pattern in &config.ignore_patterns {
-        if glob_match(pattern, &path_str) {
+    for pattern in patterns {
+        let matched = if is_dir {
+            glob_match_dir(pattern, &path_str)
+        } else {
+            glob_match(pattern, &path_str)
+        };
+        if matched {
             return true;
         }
     }
@@ -328,46 +364,6 @@ fn matches_ignore_pattern(path: &Path, config: &IndexerConfig) -> bool {
     false
 }
 
-/// Simple glob matching (copied from walker.rs for consistency)
-fn glob_match(pattern: &str, path: &str) -> bool {
-    // Handle **/dir/** patterns (match dir anywhere in path)
-    if pattern.starts_with("**/") && pattern.ends_with("/**") {
-        let dir_name = &pattern[3..pattern.len() - 3];
-        return path.contains(&format!("/{}/", dir_name))
-            || path.starts_with(&format!("{}/", dir_name))
-            || path.ends_with(&format!("/{}", dir_name));
-    }
-
-    // Handle **/*.ext patterns (match extension anywhere)
-    if pattern.starts_with("**/*.") {
-        let ext = &pattern[5..];
-        return path.ends_with(&format!(".{}", ext));
-    }
-
-    // Handle **/something patterns (match at end)
-    if pattern.starts_with("**/") {
-        let suffix = &pattern[3..];
-        return path.ends_with(suffix) || path.ends_with(&format!("/{}", suffix));
-    }
-
-    // Handle something/** patterns (match at start)
-    if pattern.ends_with("/**") {
-        let prefix = &pattern[..pattern.len() - 3];
-        return path.starts_with(prefix) || path.contains(&format!("/{}", prefix));
-    }
-
-    // Handle simple * patterns (*.ext)
-    if pattern.starts_with("*.") {
-        let ext = &pattern[2..];
-        return path.ends_with(&format!(".{}", ext));
-    }
-
-    // Exact match or path component match
-    path == pattern
-        || path.ends_with(&format!("/{}", pattern))
-        || path.contains(&format!("/{}/", pattern))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -381,8 +377,36 @@ mod tests {
 
     #[test]
     fn test_is_ignored_dir() {
-        assert!(is_ignored_dir(Path::new("/foo/node_modules/bar")));
-        assert!(is_ignored_dir(Path::new("/foo/vendor/package")));
-        assert!(!is_ignored_dir(Path::new("/foo/src/main.rs")));
+        let skip_dirs = IndexerConfig::default().skip_dirs;
+        assert!(is_ignored_dir(Path::new("/foo/node_modules/bar"), &skip_dirs));
+        assert!(is_ignored_dir(Path::new("/foo/vendor/package"), &skip_dirs));
+        assert!(!is_ignored_dir(Path::new("/foo/src/main.rs"), &skip_dirs));
+        assert!(!is_ignored_dir(Path::new("/foo/build/main.rs"), &["vendor".to_string()]));
+    }
+
+    #[test]
+    fn test_add_ignore_pattern() {
+        use notify_debouncer_full::notify::{event::CreateKind, Event, EventKind};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::write(root.join("debug.log"), "x").unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+
+        let config = IndexerConfig::default();
+        let watcher = FileWatcher::new(root.clone(), config.clone()).unwrap();
+        assert!(watcher.add_ignore_pattern("*.log".to_string()));
+        assert!(!watcher.add_ignore_pattern("*.log".to_string()));
+        // Already ignored by the config
+        assert!(!watcher.add_ignore_pattern(config.ignore_patterns[0].clone()));
+
+        let event = Event::new(EventKind::Create(CreateKind::File))
+            .add_path(root.join("debug.log"))
+            .add_path(root.join("main.rs"));
+        let event = notify_debouncer_full::DebouncedEvent::new(event, std::time::Instant::now());
+        let runtime_ignore_patterns = watcher.runtime_ignore_patterns.read();
+        let events = process_notify_event(&event, std::slice::from_ref(&root), &config, &runtime_ignore_patterns);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], WatchEvent::Changed(path) if path == &root.join("main.rs")));
     }
 }